@@ -0,0 +1,91 @@
+use std::{net::SocketAddr, process::ExitCode};
+
+use clap::Parser;
+use hub::{
+    cli::{logger, EXIT_CONFIG_ERROR, EXIT_FAILURE},
+    traffic::{traffic_config::TrafficConfig, traffic_sender::run},
+};
+use log::{error, LevelFilter};
+
+/// Sends synthetic DCS export traffic to the hub, e.g. for load tests.
+#[derive(Debug, Parser)]
+#[command(version)]
+struct TrafficArgs {
+    /// Scenario file, the default scenario being used when none is given
+    #[arg(short, long, value_name = "PATH")]
+    scenario: Option<String>,
+
+    /// Address of the hub's DCS listener, e.g. 127.0.0.1:34254
+    #[arg(long, value_name = "ADDRESS")]
+    target: Option<SocketAddr>,
+
+    /// Multiplies the number of units of every group, e.g. `--scale 100` for thousands of units
+    #[arg(long, value_name = "FACTOR", default_value_t = 1)]
+    scale: usize,
+
+    /// Share of the frames preceded by a malformed record, from 0 to 1
+    #[arg(long, value_name = "RATIO")]
+    malformed_ratio: Option<f64>,
+
+    /// Stops after this many export cycles instead of running until interrupted
+    #[arg(long, value_name = "COUNT")]
+    cycles: Option<u64>,
+
+    /// Prints the default scenario and exits
+    #[arg(long)]
+    print_default_scenario: bool,
+}
+
+impl TrafficArgs {
+    fn scenario(&self) -> std::io::Result<TrafficConfig> {
+        let mut config = match &self.scenario {
+            Some(file_path) => TrafficConfig::from_file(file_path)?,
+            None => TrafficConfig::default(),
+        };
+
+        if let Some(target) = self.target {
+            config.target = target;
+        }
+        if let Some(malformed_ratio) = self.malformed_ratio {
+            config.malformed_ratio = malformed_ratio;
+        }
+        config.scale(self.scale);
+
+        Ok(config)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = TrafficArgs::parse();
+    logger::init(LevelFilter::Info);
+
+    if args.print_default_scenario {
+        return match TrafficConfig::default().to_toml() {
+            Ok(toml) => {
+                print!("{}", toml);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                error!("Failed to serialize the default scenario: {}", err);
+                ExitCode::from(EXIT_FAILURE)
+            }
+        };
+    }
+
+    let config = match args.scenario() {
+        Ok(config) => config,
+        Err(err) => {
+            error!("Invalid scenario: {}", err);
+            return ExitCode::from(EXIT_CONFIG_ERROR);
+        }
+    };
+
+    match run(config, args.cycles).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("Failed to send traffic: {}", err);
+            ExitCode::from(EXIT_FAILURE)
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::net::SocketAddr;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use log::LevelFilter;
+
+use crate::{
+    recording::replay::{parse_mission_time, parse_speed, ReplayOptions},
+    sink::sink_config::{OutputConfig, SinkConfig, WebSocketSinkConfig},
+    user_config::user_config::UserConfig,
+};
+
+use super::config_override::ConfigOverride;
+
+/// Configuration file used when none is given
+pub const DEFAULT_CONFIG_PATH: &str = "hub.toml";
+
+/// JSON configuration file used before the configuration was versioned, migrated to
+/// `DEFAULT_CONFIG_PATH` when that does not exist yet
+pub const LEGACY_CONFIG_PATH: &str = "hub.config";
+
+/// Relays the units exported from DCS to TAK clients.
+#[derive(Debug, Clone, Parser)]
+#[command(version)]
+pub struct CliArgs {
+    /// Configuration file, created with the default settings if it does not exist
+    #[arg(short, long, value_name = "PATH", default_value = DEFAULT_CONFIG_PATH)]
+    pub config: String,
+
+    /// Overrides a setting of the configuration file, e.g. `--set stale_after_cycles=5` or
+    /// `--set sinks.0.address=[::]:9345`
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    pub overrides: Vec<ConfigOverride>,
+
+    /// Prints the default configuration and exits
+    #[arg(long, conflicts_with_all = ["validate_config", "dry_run"])]
+    pub print_default_config: bool,
+
+    /// Checks the configuration and exits, with status 2 if it is invalid
+    #[arg(long)]
+    pub validate_config: bool,
+
+    /// Listens to DCS and logs the units instead of publishing them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Logs more details, `-vv` for everything
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Only logs warnings and errors
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Address to receive the DCS export on, e.g. 0.0.0.0:34254 or [::]:34254
+    #[arg(long, value_name = "ADDRESS")]
+    pub listener_address: Option<SocketAddr>,
+
+    /// Address WebSocket clients connect to, e.g. 0.0.0.0:9345 or [::]:9345
+    #[arg(long, value_name = "ADDRESS")]
+    pub hub_address: Option<SocketAddr>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// What the hub does instead of listening to DCS.
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Replays a recorded session through the hub instead of listening to DCS. While it runs,
+    /// type `pause`, `resume`, `seek HH:MM:SS` or `speed FACTOR`
+    Replay(ReplayArgs),
+}
+
+/// The session to replay and how.
+#[derive(Debug, Clone, Args)]
+pub struct ReplayArgs {
+    /// Session file recorded by the hub
+    #[arg(value_name = "FILE")]
+    pub session_path: String,
+
+    /// How much faster than real time the session is replayed
+    #[arg(long, default_value_t = 1.0, value_parser = parse_speed)]
+    pub speed: f64,
+
+    /// Mission time to start from, as HH:MM:SS or seconds since the start of the mission
+    #[arg(long, value_name = "TIME", value_parser = parse_mission_time)]
+    pub start: Option<i32>,
+
+    /// Starts over at the end of the session
+    #[arg(long = "loop")]
+    pub looping: bool,
+}
+
+impl ReplayArgs {
+    pub fn options(&self) -> ReplayOptions {
+        ReplayOptions {
+            speed: self.speed,
+            start: self.start,
+            looping: self.looping,
+        }
+    }
+}
+
+impl CliArgs {
+    /// The most detailed log records to write.
+    pub fn log_level(&self) -> LevelFilter {
+        match (self.quiet, self.verbose) {
+            (true, _) => LevelFilter::Warn,
+            (false, 0) => LevelFilter::Info,
+            (false, 1) => LevelFilter::Debug,
+            (false, _) => LevelFilter::Trace,
+        }
+    }
+
+    /// Overrides the settings of `user_config` given on the command line. The hub address
+    /// replaces the one of the first WebSocket sink, which is added if there is none.
+    pub fn apply(&self, user_config: &mut UserConfig) {
+        if let Some(listener_address) = self.listener_address {
+            user_config.listener_address = listener_address;
+        }
+
+        if let Some(hub_address) = self.hub_address {
+            let web_socket = user_config
+                .sinks
+                .iter_mut()
+                .find_map(|sink| match &mut sink.output {
+                    OutputConfig::WebSocket(web_socket) => Some(web_socket),
+                    _ => None,
+                });
+
+            match web_socket {
+                Some(web_socket) => web_socket.address = hub_address,
+                None => user_config
+                    .sinks
+                    .push(SinkConfig::new(OutputConfig::WebSocket(
+                        WebSocketSinkConfig {
+                            address: hub_address,
+                            ..Default::default()
+                        },
+                    ))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use clap::Parser;
+    use log::LevelFilter;
+
+    use crate::{
+        recording::replay::ReplayOptions,
+        sink::sink_config::{OutputConfig, RecordingConfig, SinkConfig, WebSocketSinkConfig},
+        user_config::{
+            coalition_flag::CoalitionFlag,
+            unit_type_flag::UnitTypeFlag,
+            user_config::{default_listener_address, default_sinks, UserConfig},
+        },
+    };
+
+    use super::{CliArgs, Command};
+
+    #[test]
+    fn given_addresses_when_applied_then_listener_and_hub_addresses_are_replaced() {
+        // Arrange
+        let args = CliArgs::try_parse_from([
+            "hub",
+            "--listener-address",
+            "0.0.0.0:34254",
+            "--hub-address",
+            "[::]:9000",
+        ])
+        .expect("Failed to parse arguments");
+        let mut config = build_user_config();
+
+        // Act
+        args.apply(&mut config);
+
+        // Assert
+        assert_eq!(config.listener_address, "0.0.0.0:34254".parse().unwrap());
+        assert_eq!(
+            config.sinks,
+            vec![SinkConfig::new(OutputConfig::WebSocket(
+                WebSocketSinkConfig {
+                    address: "[::]:9000".parse().unwrap(),
+                    ..Default::default()
+                }
+            ))]
+        );
+    }
+
+    #[test]
+    fn given_flags_when_parsed_then_modes_and_log_level_are_set() {
+        // Act
+        let default = CliArgs::try_parse_from(["hub"]).expect("Failed to parse arguments");
+        let verbose = CliArgs::try_parse_from([
+            "hub",
+            "-vv",
+            "--config",
+            "other.config",
+            "--set",
+            "stale_after_cycles=5",
+            "--dry-run",
+        ])
+        .expect("Failed to parse arguments");
+        let conflicting = CliArgs::try_parse_from(["hub", "--quiet", "--verbose"]);
+
+        // Assert
+        assert_eq!(default.config, "hub.toml");
+        assert_eq!(default.log_level(), LevelFilter::Info);
+        assert!(!default.dry_run);
+        assert_eq!(verbose.config, "other.config");
+        assert_eq!(verbose.overrides[0].key, "stale_after_cycles");
+        assert_eq!(verbose.log_level(), LevelFilter::Trace);
+        assert!(verbose.dry_run);
+        assert!(conflicting.is_err());
+    }
+
+    #[test]
+    fn given_replay_command_when_parsed_then_replay_options_are_set() {
+        // Act
+        let args = CliArgs::try_parse_from([
+            "hub",
+            "--dry-run",
+            "replay",
+            "recordings/session.jsonl.gz",
+            "--speed",
+            "4",
+            "--start",
+            "0:15:00",
+            "--loop",
+        ])
+        .expect("Failed to parse arguments");
+
+        // Assert
+        assert!(args.dry_run);
+        match args.command {
+            Some(Command::Replay(replay)) => {
+                assert_eq!(replay.session_path, "recordings/session.jsonl.gz");
+                assert_eq!(
+                    replay.options(),
+                    ReplayOptions {
+                        speed: 4.0,
+                        start: Some(900),
+                        looping: true,
+                    }
+                );
+            }
+            None => panic!("Replay command was not parsed"),
+        }
+    }
+
+    #[test]
+    fn given_address_without_port_when_parsed_then_error_is_returned() {
+        // Act
+        let result = CliArgs::try_parse_from(["hub", "--listener-address", "192.168.1.10"]);
+
+        // Assert
+        let err = result.expect_err("Address without port was accepted");
+        assert!(err.to_string().contains("--listener-address"));
+    }
+
+    fn build_user_config() -> UserConfig {
+        UserConfig {
+            coalition_flag: CoalitionFlag::BLUFOR,
+            unit_type_flag: UnitTypeFlag::GROUND,
+            export_frequency_frames: 100,
+            stale_after_cycles: 3,
+            remove_after_cycles: 10,
+            cot_type_map_path: None,
+            geoid_model_path: None,
+            listener_address: default_listener_address(),
+            sinks: default_sinks(),
+            recording: RecordingConfig::default(),
+        }
+    }
+}
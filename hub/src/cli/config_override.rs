@@ -0,0 +1,185 @@
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::user_config::{config_error::ConfigError, user_config::UserConfig};
+
+/// A setting given on the command line as `key=value`, overriding the configuration file. Keys
+/// are paths through the settings, e.g. `stale_after_cycles` or `sinks.0.address`, and values are
+/// read as JSON, falling back to a string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigOverride {
+    /// Path of the setting, separated by dots
+    pub key: String,
+
+    /// The new value of the setting
+    pub value: Value,
+}
+
+impl FromStr for ConfigOverride {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected KEY=VALUE, got '{}'", s))?;
+        if key.is_empty() || key.split('.').any(str::is_empty) {
+            return Err(format!("invalid key '{}'", key));
+        }
+
+        Ok(ConfigOverride {
+            key: key.to_string(),
+            value: serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string())),
+        })
+    }
+}
+
+impl ConfigOverride {
+    /// Applies `overrides` in order to a copy of `user_config`.
+    pub fn apply_all(
+        overrides: &[ConfigOverride],
+        user_config: &UserConfig,
+    ) -> Result<UserConfig, ConfigError> {
+        let mut json =
+            serde_json::to_value(user_config).map_err(|err| ConfigError::InvalidSetting {
+                key: String::new(),
+                reason: err.to_string(),
+            })?;
+        let mut config = None;
+
+        for config_override in overrides {
+            config_override.set(&mut json)?;
+
+            let overridden: UserConfig =
+                serde_json::from_value(json.clone()).map_err(|err| config_override.invalid(err))?;
+
+            // Unknown settings are ignored when reading the configuration, so they would vanish
+            let round_trip =
+                serde_json::to_value(&overridden).map_err(|err| config_override.invalid(err))?;
+            if !config_override.value.is_null() && config_override.find(&round_trip).is_none() {
+                return Err(config_override.invalid("no such setting"));
+            }
+
+            config = Some(overridden);
+        }
+
+        match config {
+            Some(config) => Ok(config),
+            None => serde_json::from_value(json).map_err(|err| ConfigError::InvalidSetting {
+                key: String::new(),
+                reason: err.to_string(),
+            }),
+        }
+    }
+
+    fn set(&self, json: &mut Value) -> Result<(), ConfigError> {
+        let mut target = json;
+        for segment in self.key.split('.') {
+            target = match target {
+                Value::Object(object) => object
+                    .entry(segment)
+                    .or_insert_with(|| Value::Object(Default::default())),
+                Value::Array(array) => segment
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| array.get_mut(index))
+                    .ok_or_else(|| self.invalid(format!("no element '{}'", segment)))?,
+                _ => return Err(self.invalid(format!("'{}' is not a group of settings", segment))),
+            };
+        }
+
+        *target = self.value.clone();
+        Ok(())
+    }
+
+    fn find<'a>(&self, json: &'a Value) -> Option<&'a Value> {
+        self.key
+            .split('.')
+            .try_fold(json, |value, segment| match value {
+                Value::Object(object) => object.get(segment),
+                Value::Array(array) => array.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    fn invalid(&self, reason: impl ToString) -> ConfigError {
+        ConfigError::InvalidSetting {
+            key: self.key.clone(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::{
+        sink::sink_config::OutputConfig,
+        user_config::{config_error::ConfigError, user_config::UserConfig},
+    };
+
+    use super::ConfigOverride;
+
+    #[test]
+    fn given_overrides_when_applied_then_settings_are_replaced() {
+        // Arrange
+        let overrides: Vec<ConfigOverride> = [
+            "stale_after_cycles=5",
+            "cot_type_map_path=cot_types.json",
+            "sinks.0.address=[::]:9000",
+        ]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+
+        // Act
+        let result = ConfigOverride::apply_all(&overrides, &UserConfig::default())
+            .expect("Failed to apply overrides");
+
+        // Assert
+        assert_eq!(result.stale_after_cycles, 5);
+        assert_eq!(result.cot_type_map_path.as_deref(), Some("cot_types.json"));
+        match &result.sinks[0].output {
+            OutputConfig::WebSocket(web_socket) => {
+                assert_eq!(web_socket.address, "[::]:9000".parse().unwrap())
+            }
+            output => panic!("Unexpected output {:?}", output),
+        }
+    }
+
+    #[test]
+    fn given_unknown_or_invalid_settings_when_applied_then_error_names_the_key() {
+        // Arrange
+        let unknown: ConfigOverride = "stale_after_cylces=5".parse().unwrap();
+        let invalid: ConfigOverride = "stale_after_cycles=soon".parse().unwrap();
+        let missing_element: ConfigOverride = "sinks.3.address=[::]:9000".parse().unwrap();
+
+        // Act
+        let results = [unknown, invalid, missing_element].map(|config_override| {
+            ConfigOverride::apply_all(&[config_override], &UserConfig::default())
+        });
+
+        // Assert
+        let keys: Vec<String> = results
+            .into_iter()
+            .map(|result| match result {
+                Err(ConfigError::InvalidSetting { key, .. }) => key,
+                result => panic!("Unexpected result {:?}", result),
+            })
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "stale_after_cylces",
+                "stale_after_cycles",
+                "sinks.3.address"
+            ]
+        );
+    }
+
+    #[test]
+    fn given_text_without_separator_when_parsed_then_error_is_returned() {
+        assert!("stale_after_cycles".parse::<ConfigOverride>().is_err());
+        assert!("=5".parse::<ConfigOverride>().is_err());
+        assert!("sinks..address=5".parse::<ConfigOverride>().is_err());
+    }
+}
@@ -0,0 +1,33 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Writes log records to the console, warnings and errors to stderr and the rest to stdout.
+struct ConsoleLogger {
+    level: LevelFilter,
+}
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        match record.level() {
+            Level::Error | Level::Warn => eprintln!("{}", record.args()),
+            Level::Info => println!("{}", record.args()),
+            Level::Debug | Level::Trace => println!("[{}] {}", record.level(), record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Logs the records up to `level` to the console. Only the first call has an effect.
+pub fn init(level: LevelFilter) {
+    if log::set_boxed_logger(Box::new(ConsoleLogger { level })).is_ok() {
+        log::set_max_level(level);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod cli_args;
+pub mod config_override;
+pub mod logger;
+
+/// Exit code when the hub failed while running, e.g. a socket could not be bound
+pub const EXIT_FAILURE: u8 = 1;
+
+/// Exit code when the configuration or the command line is invalid
+pub const EXIT_CONFIG_ERROR: u8 = 2;
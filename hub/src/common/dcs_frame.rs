@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+
+use super::dcs_unit::{Coalition, DcsUnit, Position3D, UnitType};
+
+/// The frame envelope version understood by the hub.
+pub const DCS_FRAME_VERSION: u8 = 1;
+
+/// Mission clock and ordering information shared by every unit of a frame
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct FrameHeader {
+    /// Monotonically increasing frame counter, starting over when the export restarts
+    pub sequence: u32,
+
+    /// The date of the mission
+    pub mission_date: String,
+
+    /// The start time of the mission
+    pub mission_start_time: i32,
+
+    /// The time elapsed since the start of the mission
+    pub mission_time_elapsed: i32,
+}
+
+/// A unit as carried inside a frame, without the mission clock
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct FrameUnit {
+    /// The unit's identifier
+    pub unit_name: String,
+
+    /// The unit's group identifier
+    pub group_name: String,
+
+    /// The unit's coalition
+    pub coalition: Coalition,
+
+    /// The unit's three-dimensional position
+    pub position: Position3D,
+
+    /// The categorization of the unit
+    pub unit_type: UnitType,
+}
+
+/// Models one export cycle of units. See scripts\dcs_jtac_tools_unit_export.lua
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+pub struct DcsFrame {
+    /// The version of the frame envelope
+    pub version: u8,
+
+    /// Information shared by all units of the frame
+    pub header: FrameHeader,
+
+    /// The units exported during the cycle
+    pub units: Vec<FrameUnit>,
+}
+
+impl DcsFrame {
+    /// Expands the frame into individual `DcsUnit`s carrying the frame's mission clock.
+    pub fn into_units(self) -> Vec<DcsUnit> {
+        let header = self.header;
+
+        self.units
+            .into_iter()
+            .map(|unit| DcsUnit {
+                unit_name: unit.unit_name,
+                group_name: unit.group_name,
+                coalition: unit.coalition,
+                position: unit.position,
+                unit_type: unit.unit_type,
+                mission_date: header.mission_date.clone(),
+                mission_start_time: header.mission_start_time,
+                mission_time_elapsed: header.mission_time_elapsed,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::common::{
+        dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+        unit_type::Level1UnitType,
+    };
+
+    use super::DcsFrame;
+
+    #[test]
+    fn given_json_frame_when_deserialized_and_expanded_then_units_carry_mission_clock() {
+        // Arrange
+        let json = r#"{"version":1,"header":{"sequence":7,"mission_date":"2024-03-08","mission_start_time":28800,"mission_time_elapsed":3600},"units":[{"unit_name":"UNIT-1","group_name":"GROUP-1","coalition":2,"position":{"latitude":30.0090027,"longitude":-85.9578735,"altitude":132.67,"heading":2.0034},"unit_type":{"level_1":1,"level_2":1}}]}"#;
+        let expected = vec![DcsUnit {
+            unit_name: "UNIT-1".to_string(),
+            group_name: "GROUP-1".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 30.0090027,
+                longitude: -85.9578735,
+                altitude: 132.67,
+                heading: 2.0034,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::AIR,
+                level_2: 1,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 28800,
+            mission_time_elapsed: 3600,
+        }];
+
+        // Act
+        let frame: DcsFrame = serde_json::from_str(json).expect("Failed to deserialize frame");
+        let sequence = frame.header.sequence;
+        let result = frame.into_units();
+
+        // Assert
+        assert_eq!(sequence, 7);
+        assert_eq!(result, expected);
+    }
+}
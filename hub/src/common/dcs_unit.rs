@@ -5,7 +5,7 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 use super::unit_type::Level1UnitType;
 
 /// The DCS coalition
-#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq)]
+#[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Clone, Copy)]
 #[repr(u8)]
 pub enum Coalition {
     /// Neutral coalition
@@ -19,17 +19,33 @@ pub enum Coalition {
 }
 
 /// The unit categorization
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct UnitType {
     /// Top-level categorization of unit
     pub level_1: Level1UnitType,
 
     /// The sub-categorization of unit
     pub level_2: u8,
+
+    /// The third level of categorization (e.g. fighter, SAM, aircraft carrier); 0 when unknown
+    #[serde(default, skip_serializing_if = "is_unknown_level")]
+    pub level_3: u8,
+
+    /// The fourth level of categorization; 0 when unknown
+    #[serde(default, skip_serializing_if = "is_unknown_level")]
+    pub level_4: u8,
+
+    /// The DCS type name of the unit (e.g. `mrap_mk19`); empty when unknown
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub type_name: String,
+}
+
+fn is_unknown_level(level: &u8) -> bool {
+    *level == 0
 }
 
 /// 3-dimensional position of the unit
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct Position3D {
     /// Latitudinal position of the unit
     pub latitude: f64,
@@ -45,7 +61,7 @@ pub struct Position3D {
 }
 
 /// Models the exported DCS unit. See scripts\dcs_jtac_tools_unit_export.lua
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
 pub struct DcsUnit {
     /// The unit's identifier
     pub unit_name: String,
@@ -129,6 +145,27 @@ mod unit_tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn given_json_string_with_full_unit_type_when_deserialized_then_all_levels_are_parsed() {
+        // Arrange
+        let json = r#"{"level_1":2,"level_2":17,"level_3":26,"level_4":14,"type_name":"mrap_mk19"}"#;
+
+        // Act
+        let result: UnitType = serde_json::from_str(json).expect("Failed to deserialize unit type");
+
+        // Assert
+        assert_eq!(
+            result,
+            UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 17,
+                level_3: 26,
+                level_4: 14,
+                type_name: "mrap_mk19".to_string(),
+            }
+        );
+    }
+
     #[test]
     fn given_properly_formatted_date_info_when_deserialized_then_succeeds_to_build_date_time() {
         // Arrange
@@ -174,6 +211,9 @@ mod unit_tests {
             unit_type: UnitType {
                 level_1: Level1UnitType::AIR,
                 level_2: 1,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
             },
             mission_date: "2024-03-08".to_string(),
             mission_start_time: 28800,
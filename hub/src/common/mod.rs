@@ -1,2 +1,4 @@
+pub mod dcs_frame;
 pub mod dcs_unit;
+pub mod socket;
 pub mod unit_type;
\ No newline at end of file
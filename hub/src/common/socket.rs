@@ -0,0 +1,100 @@
+use std::{
+    io,
+    net::{SocketAddr, TcpListener, UdpSocket},
+};
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+/// Maximum number of connections waiting to be accepted
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Binds a non-blocking TCP listener. Listening on `[::]` accepts IPv4 clients as well,
+/// whatever the default of the OS.
+pub fn bind_tcp_listener(address: SocketAddr) -> io::Result<TcpListener> {
+    let socket = new_socket(address, Type::STREAM, Protocol::TCP)?;
+    // Restarts would otherwise fail while the connections of the previous run linger
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+    socket.bind(&address.into())?;
+    socket.listen(LISTEN_BACKLOG)?;
+
+    Ok(socket.into())
+}
+
+/// Binds a non-blocking UDP socket. Binding `[::]` receives IPv4 datagrams as well, whatever
+/// the default of the OS.
+pub fn bind_udp_socket(address: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = new_socket(address, Type::DGRAM, Protocol::UDP)?;
+    socket.bind(&address.into())?;
+
+    Ok(socket.into())
+}
+
+fn new_socket(address: SocketAddr, socket_type: Type, protocol: Protocol) -> io::Result<Socket> {
+    let socket = Socket::new(Domain::for_address(address), socket_type, Some(protocol))?;
+    if address.is_ipv6() {
+        socket.set_only_v6(false)?;
+    }
+    socket.set_nonblocking(true)?;
+
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::{
+        io::{ErrorKind, Read, Write},
+        net::{TcpStream, UdpSocket},
+        thread,
+        time::Duration,
+    };
+
+    use super::{bind_tcp_listener, bind_udp_socket};
+
+    #[test]
+    fn given_unspecified_ipv6_address_when_tcp_listener_is_bound_then_ipv4_clients_connect() {
+        // Arrange
+        let listener =
+            bind_tcp_listener("[::]:0".parse().unwrap()).expect("Failed to bind listener");
+        let port = listener.local_addr().unwrap().port();
+
+        // Act
+        let mut client = TcpStream::connect(("127.0.0.1", port)).expect("Failed to connect");
+        client.write_all(b"ping").unwrap();
+        let mut accepted = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(10))
+                }
+                Err(err) => panic!("Failed to accept client: {}", err),
+            }
+        };
+        accepted.set_nonblocking(false).unwrap();
+        let mut received = [0; 4];
+        accepted.read_exact(&mut received).unwrap();
+
+        // Assert
+        assert_eq!(&received, b"ping");
+    }
+
+    #[test]
+    fn given_unspecified_ipv6_address_when_udp_socket_is_bound_then_ipv4_senders_reach_it() {
+        // Arrange
+        let socket = bind_udp_socket("[::]:0".parse().unwrap()).expect("Failed to bind socket");
+        let port = socket.local_addr().unwrap().port();
+        let sender = UdpSocket::bind("127.0.0.1:0").expect("Failed to bind sender");
+
+        // Act
+        sender.send_to(b"ping", ("127.0.0.1", port)).unwrap();
+        socket.set_nonblocking(false).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut received = [0; 4];
+        let (length, _) = socket.recv_from(&mut received).unwrap();
+
+        // Assert
+        assert_eq!(&received[..length], b"ping");
+    }
+}
@@ -2,32 +2,40 @@ use std::fmt;
 
 use crate::common::{dcs_unit::{Coalition, DcsUnit}, unit_type::Level1UnitType};
 
-/// Models a four-level hierarchy used for constructing atomic events. Consider making the number
-/// of levels more dynamic (i.e. linked list).
+use super::cot_type_map::CotTypeMap;
+
+/// Models the hierarchy used for constructing atomic events, e.g. `a-h-G-U-C-A`. The first level
+/// is the atom, the second the affiliation and the remaining ones the MIL-STD-2525 function.
 #[derive(Debug, PartialEq)]
 pub struct AtomicEvent {
-    level_1: char,
-    level_2: char,
-    level_3: char,
+    levels: Vec<char>,
 }
 
 impl fmt::Display for AtomicEvent {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}-{}-{}",
-            self.level_1, self.level_2, self.level_3
-        )
+        for (index, level) in self.levels.iter().enumerate() {
+            if index > 0 {
+                write!(f, "-")?;
+            }
+            write!(f, "{}", level)?;
+        }
+
+        Ok(())
     }
 }
 
 impl AtomicEvent {
-    pub fn from(unit: &DcsUnit) -> AtomicEvent {
-        AtomicEvent {
-            level_1: 'a',
-            level_2: coalition_to_atomic_event_char(&unit.coalition),
-            level_3: level_1_unit_type_char(&unit.unit_type.level_1),
+    /// Builds the atomic event of a unit, falling back to the level 1 unit type when the unit
+    /// type is not found in `cot_type_map`.
+    pub fn from(unit: &DcsUnit, cot_type_map: &CotTypeMap) -> AtomicEvent {
+        let mut levels = vec!['a', coalition_to_atomic_event_char(&unit.coalition)];
+
+        match cot_type_map.function_of(&unit.unit_type) {
+            Some(function) => levels.extend_from_slice(function),
+            None => levels.push(level_1_unit_type_char(&unit.unit_type.level_1)),
         }
+
+        AtomicEvent { levels }
     }
 }
 
@@ -53,7 +61,10 @@ fn level_1_unit_type_char(unit_type: &Level1UnitType) -> char {
 mod unit_tests {
     use crate::{
         common::{dcs_unit::{Coalition, DcsUnit, Position3D, UnitType}, unit_type::Level1UnitType},
-        cursor_on_target::atomic_event::{coalition_to_atomic_event_char, level_1_unit_type_char},
+        cursor_on_target::{
+            atomic_event::{coalition_to_atomic_event_char, level_1_unit_type_char},
+            cot_type_map::CotTypeMap,
+        },
     };
 
     use super::AtomicEvent;
@@ -61,33 +72,14 @@ mod unit_tests {
     #[test]
     fn given_dcs_unit_when_building_atomic_event_then_fields_are_mapped() {
         // Arrange
-        let unit = DcsUnit {
-            unit_name: "J-01334".to_string(),
-            group_name: "J-01335".to_string(),
-            coalition: Coalition::REDFOR,
-            position: Position3D {
-                latitude: 30.0090027,
-                longitude: -85.9578735,
-                altitude: -42.6,
-                heading: 0.0568,
-            },
-            unit_type: UnitType {
-                level_1: Level1UnitType::AIR,
-                level_2: 1,
-            },
-            mission_date: "2005-04-05".to_string(),
-            mission_start_time: 42_000,
-            mission_time_elapsed: 218,
-        };
+        let unit = build_dcs_unit();
 
         let expected = AtomicEvent {
-            level_1: 'a',
-            level_2: 'h',
-            level_3: 'A',
+            levels: vec!['a', 'h', 'A', 'M', 'F'],
         };
 
         // Act
-        let result = AtomicEvent::from(&unit);
+        let result = AtomicEvent::from(&unit, &CotTypeMap::default());
 
         // Assert
         assert_eq!(expected, result);
@@ -97,16 +89,29 @@ mod unit_tests {
     fn given_atomic_event_when_serialized_to_string_then_formatted_as_atomic_event() {
         // Arrange
         let event = AtomicEvent {
-            level_1: 'a',
-            level_2: 'h',
-            level_3: 'A',
+            levels: vec!['a', 'h', 'G', 'U', 'C', 'A'],
         };
 
         // Act
         let result = event.to_string();
 
         // Assert
-        assert_eq!("a-h-A", result);
+        assert_eq!("a-h-G-U-C-A", result);
+    }
+
+    #[test]
+    fn given_unit_type_missing_from_map_when_building_atomic_event_then_level_1_type_is_used() {
+        // Arrange
+        let mut unit = build_dcs_unit();
+        unit.unit_type.level_1 = Level1UnitType::SEA;
+        unit.unit_type.level_2 = 0;
+        let cot_type_map = CotTypeMap::from_json("{}").expect("Failed to parse empty map");
+
+        // Act
+        let result = AtomicEvent::from(&unit, &cot_type_map);
+
+        // Assert
+        assert_eq!("a-h-S", result.to_string());
     }
 
     #[test]
@@ -180,4 +185,28 @@ mod unit_tests {
         // Assert
         assert_eq!('A', result);
     }
+
+    fn build_dcs_unit() -> DcsUnit {
+        DcsUnit {
+            unit_name: "J-01334".to_string(),
+            group_name: "J-01335".to_string(),
+            coalition: Coalition::REDFOR,
+            position: Position3D {
+                latitude: 30.0090027,
+                longitude: -85.9578735,
+                altitude: -42.6,
+                heading: 0.0568,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::AIR,
+                level_2: 1,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
+            },
+            mission_date: "2005-04-05".to_string(),
+            mission_start_time: 42_000,
+            mission_time_elapsed: 218,
+        }
+    }
 }
@@ -0,0 +1,229 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, ErrorKind},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::dcs_unit::UnitType;
+
+/// The mapping table used when no other table is configured.
+const DEFAULT_COT_TYPE_MAP: &str = include_str!("cot_type_map.json");
+
+/// Maps a prefix of the DCS type levels (level 1 first) to a CoT function.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+pub struct UnitTypeMapping {
+    /// DCS type levels, starting at level 1
+    pub levels: Vec<u8>,
+
+    /// The CoT type following the affiliation, e.g. `G-U-C-A` for armor
+    pub function: String,
+}
+
+/// File representation of the mapping table.
+#[derive(Debug, Deserialize, Serialize, Default)]
+struct CotTypeMapFile {
+    /// CoT functions by DCS type name (e.g. `mrap_mk19`)
+    #[serde(default)]
+    type_names: HashMap<String, String>,
+
+    /// CoT functions by DCS type levels
+    #[serde(default)]
+    unit_types: Vec<UnitTypeMapping>,
+}
+
+/// Data-driven mapping of DCS unit types to MIL-STD-2525 CoT functions, i.e. the part of the
+/// CoT type following the affiliation.
+#[derive(Debug, PartialEq)]
+pub struct CotTypeMap {
+    functions_by_type_name: HashMap<String, Vec<char>>,
+    unit_types: Vec<(Vec<u8>, Vec<char>)>,
+}
+
+impl CotTypeMap {
+    /// Parses a mapping table from its JSON representation.
+    pub fn from_json(json: &str) -> io::Result<CotTypeMap> {
+        let file: CotTypeMapFile = serde_json::from_str(json)?;
+
+        let mut functions_by_type_name = HashMap::new();
+        for (type_name, function) in file.type_names {
+            functions_by_type_name.insert(type_name, parse_function(&function)?);
+        }
+
+        let mut unit_types = Vec::new();
+        for mapping in file.unit_types {
+            if mapping.levels.is_empty() {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "No DCS type levels given for CoT function '{}'",
+                        mapping.function
+                    ),
+                ));
+            }
+            unit_types.push((mapping.levels, parse_function(&mapping.function)?));
+        }
+
+        Ok(CotTypeMap {
+            functions_by_type_name,
+            unit_types,
+        })
+    }
+
+    /// Loads a mapping table from the file system.
+    pub fn from_file(file_path: &str) -> io::Result<CotTypeMap> {
+        Self::from_json(&fs::read_to_string(file_path)?)
+    }
+
+    /// Adds the mappings of `other`, which take precedence over the existing ones.
+    pub fn extend(&mut self, other: CotTypeMap) {
+        self.functions_by_type_name
+            .extend(other.functions_by_type_name);
+
+        let mut unit_types = other.unit_types;
+        unit_types.append(&mut self.unit_types);
+        self.unit_types = unit_types;
+    }
+
+    /// Looks up the CoT function of a unit type. Type names are matched first, then the mapping
+    /// sharing the longest prefix of DCS type levels.
+    pub fn function_of(&self, unit_type: &UnitType) -> Option<&[char]> {
+        if let Some(function) = self.functions_by_type_name.get(&unit_type.type_name) {
+            return Some(function);
+        }
+
+        let levels = [
+            unit_type.level_1 as u8,
+            unit_type.level_2,
+            unit_type.level_3,
+            unit_type.level_4,
+        ];
+
+        let mut best_match: Option<&(Vec<u8>, Vec<char>)> = None;
+        for mapping in &self.unit_types {
+            let is_match = levels.starts_with(&mapping.0);
+            let is_better = best_match.is_none_or(|best| mapping.0.len() > best.0.len());
+
+            if is_match && is_better {
+                best_match = Some(mapping);
+            }
+        }
+
+        best_match.map(|(_, function)| function.as_slice())
+    }
+}
+
+impl Default for CotTypeMap {
+    fn default() -> Self {
+        Self::from_json(DEFAULT_COT_TYPE_MAP).expect("The default CoT type map is invalid")
+    }
+}
+
+/// Splits a CoT function such as `A-M-F` into its single-character levels.
+fn parse_function(function: &str) -> io::Result<Vec<char>> {
+    let mut levels = Vec::new();
+
+    for level in function.split('-') {
+        let mut chars = level.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphanumeric() => levels.push(c),
+            _ => {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Invalid CoT function '{}'", function),
+                ))
+            }
+        }
+    }
+
+    Ok(levels)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::common::{dcs_unit::UnitType, unit_type::Level1UnitType};
+
+    use super::CotTypeMap;
+
+    fn unit_type(level_1: Level1UnitType, levels: [u8; 3], type_name: &str) -> UnitType {
+        UnitType {
+            level_1,
+            level_2: levels[0],
+            level_3: levels[1],
+            level_4: levels[2],
+            type_name: type_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn given_known_type_name_when_looked_up_then_type_name_mapping_is_used() {
+        // Arrange
+        let map = CotTypeMap::default();
+        let unit_type = unit_type(Level1UnitType::GROUND, [17, 26, 14], "mrap_mk19");
+
+        // Act
+        let result = map.function_of(&unit_type);
+
+        // Assert
+        assert_eq!(result, Some(&['G', 'U', 'C', 'A', 'W'][..]));
+    }
+
+    #[test]
+    fn given_unknown_type_name_when_looked_up_then_longest_level_prefix_is_used() {
+        // Arrange
+        let map = CotTypeMap::default();
+        let fighter = unit_type(Level1UnitType::AIR, [1, 1, 0], "Some-New-Jet");
+        let helicopter = unit_type(Level1UnitType::AIR, [2, 0, 0], "");
+        let tank = unit_type(Level1UnitType::GROUND, [17, 0, 0], "");
+
+        // Act
+        let fighter_result = map.function_of(&fighter);
+        let helicopter_result = map.function_of(&helicopter);
+        let tank_result = map.function_of(&tank);
+
+        // Assert
+        assert_eq!(fighter_result, Some(&['A', 'M', 'F', 'F'][..]));
+        assert_eq!(helicopter_result, Some(&['A', 'M', 'H'][..]));
+        assert_eq!(tank_result, Some(&['G', 'U', 'C', 'A'][..]));
+    }
+
+    #[test]
+    fn given_custom_map_when_extending_default_then_custom_mappings_take_precedence() {
+        // Arrange
+        let mut map = CotTypeMap::default();
+        let custom = CotTypeMap::from_json(
+            r#"{"type_names":{"mrap_mk19":"G-U-C-R"},"unit_types":[{"levels":[1,1],"function":"A-C-F"}]}"#,
+        )
+        .expect("Failed to parse custom map");
+
+        // Act
+        map.extend(custom);
+
+        // Assert
+        assert_eq!(
+            map.function_of(&unit_type(
+                Level1UnitType::GROUND,
+                [17, 26, 14],
+                "mrap_mk19"
+            )),
+            Some(&['G', 'U', 'C', 'R'][..])
+        );
+        assert_eq!(
+            map.function_of(&unit_type(Level1UnitType::AIR, [1, 0, 0], "")),
+            Some(&['A', 'C', 'F'][..])
+        );
+    }
+
+    #[test]
+    fn given_invalid_function_when_parsed_then_returns_error() {
+        // Arrange
+        let json = r#"{"type_names":{"mrap_mk19":"G-UC-\"A"}}"#;
+
+        // Act
+        let result = CotTypeMap::from_json(json);
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
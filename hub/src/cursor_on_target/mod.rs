@@ -1,83 +1,199 @@
 pub mod atomic_event;
+pub mod cot_type_map;
+pub mod xml_deserializer;
 pub mod xml_serializer;
+pub mod xml_writer;
 
 use serde::{Deserialize, Serialize};
 
-use self::xml_serializer::ToXml;
+use self::{
+    xml_serializer::{ToXml, COT_DELETE_TYPE},
+    xml_writer::XmlWriter,
+};
 
 // Used for building and serializing cursor-on-target data
 // See https://www.mitre.org/sites/default/files/pdf/09_4937.pdf
 
 /// An optional element used to hold CoT sub-schema.
-#[derive(Debug, Deserialize, Serialize)]
-struct Detail {
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Detail {
     /// The unit call sign of the CoT
-    call_sign: String,
+    pub call_sign: Option<String>,
+
+    /// Movement of the CoT
+    pub track: Option<Track>,
+
+    /// Reference to the event this event relates to
+    pub link: Option<Link>,
+
+    /// Free text attached to the event, e.g. the message of a chat
+    pub remarks: Option<String>,
 }
 
 impl ToXml for Detail {
-    fn to_xml(&self) -> String {
-        format!(
-            r#"<detail><contact callsign="{}"/></detail>"#,
-            self.call_sign
-        )
+    fn write_xml(&self, writer: &mut XmlWriter) {
+        writer.start_element("detail");
+
+        if let Some(call_sign) = &self.call_sign {
+            writer
+                .start_element("contact")
+                .attribute("callsign", call_sign)
+                .end_element();
+        }
+
+        if let Some(track) = &self.track {
+            track.write_xml(writer);
+        }
+
+        if let Some(link) = &self.link {
+            link.write_xml(writer);
+            // Asks ATAK to delete the linked event even if it was edited locally
+            writer.start_element("__forcedelete").end_element();
+        }
+
+        if let Some(remarks) = &self.remarks {
+            writer.start_element("remarks").text(remarks).end_element();
+        }
+
+        writer.end_element();
+    }
+}
+
+/// Movement of the CoT
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Track {
+    /// Direction of motion in degrees clockwise from true north
+    pub course: f64,
+
+    /// Magnitude of motion in meters per second
+    pub speed: f64,
+
+    /// Vertical component of motion in degrees above the horizon
+    pub slope: f64,
+}
+
+impl ToXml for Track {
+    fn write_xml(&self, writer: &mut XmlWriter) {
+        writer
+            .start_element("track")
+            .attribute("course", format!("{:.1}", self.course))
+            .attribute("speed", format!("{:.1}", self.speed))
+            .attribute("slope", format!("{:.1}", self.slope))
+            .end_element();
+    }
+}
+
+/// Relationship to another CoT event
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Link {
+    /// Globally unique name of the linked event
+    pub uid: String,
+
+    /// Type of the linked event
+    pub unit_type: String,
+}
+
+impl ToXml for Link {
+    fn write_xml(&self, writer: &mut XmlWriter) {
+        writer
+            .start_element("link")
+            .attribute("uid", &self.uid)
+            .attribute("type", &self.unit_type)
+            .attribute("relation", "none")
+            .end_element();
     }
 }
 
 /// Geographical location of the CoT
-#[derive(Debug, Deserialize, Serialize)]
-struct Point {
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Point {
     /// Latitude referred to the WGS 84 ellipsoid in degrees
-    lat: f64,
+    pub lat: f64,
 
     /// Longitude referred to the WGS 84 in degrees
-    lon: f64,
+    pub lon: f64,
 
     /// Height above the WGS ellipsoid in meters
-    hae: f32,
+    pub hae: f32,
 }
 
 impl ToXml for Point {
-    fn to_xml(&self) -> String {
-        format!(
-            r#"<point lat="{}" lon="{}" ce="0.0" hae="{}" le="0.0"/>"#,
-            self.lat, self.lon, self.hae
-        )
+    fn write_xml(&self, writer: &mut XmlWriter) {
+        writer
+            .start_element("point")
+            .attribute("lat", self.lat)
+            .attribute("lon", self.lon)
+            .attribute("ce", "0.0")
+            .attribute("hae", format!("{:.1}", self.hae))
+            .attribute("le", "0.0")
+            .end_element();
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Event {
+/// A CoT event, as produced by the hub or received from clients.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Event {
     /// Hierarchically organized hint about event type.
-    unit_type: String,
+    pub unit_type: String,
+
+    /// Gives a hint about how the coordinates were generated.
+    pub how: String,
 
     /// Globally unique name for this information on this event.
-    uid: String,
+    pub uid: String,
 
     /// Time stamp: when the event was generated.
-    time: String,
+    pub time: String,
 
     /// Ending time when an event should no longer be considered valid.
-    stale: String,
+    pub stale: String,
 
     /// Geographical location of the CoT.
-    point: Point,
+    pub point: Point,
 
     /// An optional element used to hold CoT sub-schema.
-    detail: Detail,
+    pub detail: Detail,
+}
+
+impl Event {
+    /// The `t-x-d-d` event requesting clients to delete this event.
+    pub fn to_removal(&self) -> Event {
+        Event {
+            unit_type: COT_DELETE_TYPE.to_string(),
+            how: "h-g-i-g-o".to_string(),
+            uid: format!("{}-delete", self.uid),
+            time: self.time.clone(),
+            stale: self.stale.clone(),
+            point: self.point.clone(),
+            detail: Detail {
+                call_sign: None,
+                track: None,
+                link: Some(Link {
+                    uid: self.uid.clone(),
+                    unit_type: self.unit_type.clone(),
+                }),
+                remarks: None,
+            },
+        }
+    }
 }
 
 impl ToXml for Event {
-    fn to_xml(&self) -> String {
-        format!(
-            r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="{}" type="{}" how="m-g" time="{}" start="{}" stale="{}">{}{}</event>"#,
-            self.uid,
-            self.unit_type,
-            self.time,
-            self.time,
-            self.stale,
-            self.point.to_xml(),
-            self.detail.to_xml()
-        )
+    fn write_xml(&self, writer: &mut XmlWriter) {
+        writer
+            .declaration()
+            .start_element("event")
+            .attribute("version", "2.0")
+            .attribute("uid", &self.uid)
+            .attribute("type", &self.unit_type)
+            .attribute("how", &self.how)
+            .attribute("time", &self.time)
+            .attribute("start", &self.time)
+            .attribute("stale", &self.stale);
+
+        self.point.write_xml(writer);
+        self.detail.write_xml(writer);
+
+        writer.end_element();
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,449 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use chrono::DateTime;
+use quick_xml::{
+    events::{BytesStart, Event as XmlEvent},
+    Reader,
+};
+
+use super::{Detail, Event, Link, Point, Track};
+
+/// Used to handle XML deserialization, the inverse of `ToXml`
+pub trait FromXml: Sized {
+    /// Parse and validate an XML string.
+    fn from_xml(xml: &str) -> Result<Self, XmlDeserializationError>;
+}
+
+/// Reasons an XML string could not be deserialized.
+#[derive(Debug, PartialEq)]
+pub enum XmlDeserializationError {
+    /// The XML is not well-formed, or uses features that are not accepted such as DTDs
+    Malformed(String),
+
+    /// An element appeared where it is not allowed
+    UnexpectedElement(String),
+
+    /// A required element is missing
+    MissingElement(&'static str),
+
+    /// A required attribute of an element is missing
+    MissingAttribute(&'static str, &'static str),
+
+    /// An attribute has a value that is not valid for it
+    InvalidAttribute(&'static str, String),
+}
+
+impl Display for XmlDeserializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmlDeserializationError::Malformed(reason) => write!(f, "Malformed XML: {}", reason),
+            XmlDeserializationError::UnexpectedElement(name) => {
+                write!(f, "Unexpected element '{}'", name)
+            }
+            XmlDeserializationError::MissingElement(name) => {
+                write!(f, "Missing element '{}'", name)
+            }
+            XmlDeserializationError::MissingAttribute(element, attribute) => {
+                write!(f, "Missing attribute '{}' of '{}'", attribute, element)
+            }
+            XmlDeserializationError::InvalidAttribute(attribute, value) => {
+                write!(f, "Invalid value '{}' of attribute '{}'", value, attribute)
+            }
+        }
+    }
+}
+
+impl std::error::Error for XmlDeserializationError {}
+
+impl From<quick_xml::Error> for XmlDeserializationError {
+    fn from(err: quick_xml::Error) -> Self {
+        XmlDeserializationError::Malformed(err.to_string())
+    }
+}
+
+type Attributes = HashMap<String, String>;
+
+impl FromXml for Event {
+    /// Parses a single CoT `<event>`. Only the elements the hub understands are read, any other
+    /// detail is skipped.
+    fn from_xml(xml: &str) -> Result<Event, XmlDeserializationError> {
+        let mut reader = Reader::from_str(xml);
+        reader.config_mut().trim_text(true);
+
+        let mut path: Vec<String> = Vec::new();
+        let mut event: Option<Attributes> = None;
+        let mut point: Option<Point> = None;
+        let mut detail = Detail {
+            call_sign: None,
+            track: None,
+            link: None,
+            remarks: None,
+        };
+
+        loop {
+            let (start, is_empty) = match reader.read_event()? {
+                XmlEvent::Start(start) => (start, false),
+                XmlEvent::Empty(start) => (start, true),
+                XmlEvent::End(_) => {
+                    path.pop();
+                    continue;
+                }
+                XmlEvent::Text(text) => {
+                    if is_remarks(&path) {
+                        append_remarks(&mut detail, &text.unescape()?);
+                    }
+                    continue;
+                }
+                XmlEvent::CData(data) => {
+                    if is_remarks(&path) {
+                        let text = String::from_utf8_lossy(&data);
+                        append_remarks(&mut detail, &text);
+                    }
+                    continue;
+                }
+                XmlEvent::DocType(_) => {
+                    return Err(XmlDeserializationError::Malformed(
+                        "Document type declarations are not accepted".to_string(),
+                    ))
+                }
+                XmlEvent::Eof => break,
+                _ => continue,
+            };
+
+            let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+            let parents: Vec<&str> = path.iter().map(String::as_str).collect();
+
+            match (parents.as_slice(), name.as_str()) {
+                ([], "event") if event.is_none() => event = Some(read_attributes(&start)?),
+                ([], _) => return Err(XmlDeserializationError::UnexpectedElement(name)),
+                (["event"], "point") => point = Some(read_point(&read_attributes(&start)?)?),
+                (["event", "detail"], "contact") => {
+                    detail.call_sign = read_attributes(&start)?.remove("callsign");
+                }
+                (["event", "detail"], "track") => {
+                    detail.track = Some(read_track(&read_attributes(&start)?)?);
+                }
+                (["event", "detail"], "link") => {
+                    detail.link = Some(read_link(&mut read_attributes(&start)?)?);
+                }
+                (["event", "detail"], "remarks") => {
+                    detail.remarks.get_or_insert_with(String::new);
+                }
+                _ => {}
+            }
+
+            if !is_empty {
+                path.push(name);
+            }
+        }
+
+        let mut event = event.ok_or(XmlDeserializationError::MissingElement("event"))?;
+        let point = point.ok_or(XmlDeserializationError::MissingElement("point"))?;
+
+        let time = take_attribute(&mut event, "event", "time")?;
+        let stale = take_attribute(&mut event, "event", "stale")?;
+        validate_time("time", &time)?;
+        validate_time("stale", &stale)?;
+
+        Ok(Event {
+            unit_type: take_attribute(&mut event, "event", "type")?,
+            how: take_attribute(&mut event, "event", "how")?,
+            uid: take_attribute(&mut event, "event", "uid")?,
+            time,
+            stale,
+            point,
+            detail,
+        })
+    }
+}
+
+fn is_remarks(path: &[String]) -> bool {
+    path.len() == 3 && path[1] == "detail" && path[2] == "remarks"
+}
+
+fn append_remarks(detail: &mut Detail, text: &str) {
+    detail
+        .remarks
+        .get_or_insert_with(String::new)
+        .push_str(text);
+}
+
+fn read_attributes(start: &BytesStart) -> Result<Attributes, XmlDeserializationError> {
+    let mut attributes = Attributes::new();
+
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+        attributes.insert(key, attribute.unescape_value()?.into_owned());
+    }
+
+    Ok(attributes)
+}
+
+fn read_point(attributes: &Attributes) -> Result<Point, XmlDeserializationError> {
+    let lat: f64 = parse_attribute(attributes, "point", "lat")?;
+    let lon: f64 = parse_attribute(attributes, "point", "lon")?;
+
+    if !(-90.0..=90.0).contains(&lat) {
+        return Err(XmlDeserializationError::InvalidAttribute(
+            "lat",
+            lat.to_string(),
+        ));
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        return Err(XmlDeserializationError::InvalidAttribute(
+            "lon",
+            lon.to_string(),
+        ));
+    }
+
+    Ok(Point {
+        lat,
+        lon,
+        hae: parse_attribute(attributes, "point", "hae")?,
+    })
+}
+
+fn read_track(attributes: &Attributes) -> Result<Track, XmlDeserializationError> {
+    Ok(Track {
+        course: parse_attribute(attributes, "track", "course")?,
+        speed: parse_attribute(attributes, "track", "speed")?,
+        slope: match attributes.contains_key("slope") {
+            true => parse_attribute(attributes, "track", "slope")?,
+            false => 0.0,
+        },
+    })
+}
+
+fn read_link(attributes: &mut Attributes) -> Result<Link, XmlDeserializationError> {
+    Ok(Link {
+        uid: take_attribute(attributes, "link", "uid")?,
+        unit_type: take_attribute(attributes, "link", "type")?,
+    })
+}
+
+fn take_attribute(
+    attributes: &mut Attributes,
+    element: &'static str,
+    attribute: &'static str,
+) -> Result<String, XmlDeserializationError> {
+    match attributes.remove(attribute) {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        Some(value) => Err(XmlDeserializationError::InvalidAttribute(attribute, value)),
+        None => Err(XmlDeserializationError::MissingAttribute(
+            element, attribute,
+        )),
+    }
+}
+
+fn parse_attribute<T: FromStr>(
+    attributes: &Attributes,
+    element: &'static str,
+    attribute: &'static str,
+) -> Result<T, XmlDeserializationError> {
+    let value = attributes
+        .get(attribute)
+        .ok_or(XmlDeserializationError::MissingAttribute(
+            element, attribute,
+        ))?;
+
+    value
+        .trim()
+        .parse()
+        .map_err(|_| XmlDeserializationError::InvalidAttribute(attribute, value.clone()))
+}
+
+fn validate_time(attribute: &'static str, value: &str) -> Result<(), XmlDeserializationError> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(XmlDeserializationError::InvalidAttribute(
+            attribute,
+            value.to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::common::{
+        dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+        unit_type::Level1UnitType,
+    };
+    use crate::cursor_on_target::{
+        xml_serializer::{ToXml, XmlSerializer},
+        Link, Track,
+    };
+
+    use super::*;
+
+    #[test]
+    fn given_atak_marker_when_deserialized_then_event_is_returned() {
+        // Arrange
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+            <event version="2.0" uid="ANDROID-589520ccfcd20f01" type="a-h-G-U-C" how="h-g-i-g-o"
+                time="2024-03-01T12:00:00.000Z" start="2024-03-01T12:00:00.000Z" stale="2024-03-01T12:05:00.000Z">
+                <point lat="41.6" lon="41.6" hae="12.5" ce="9999999.0" le="9999999.0"/>
+                <detail>
+                    <contact callsign="Target &amp; Co"/>
+                    <precisionlocation altsrc="DTED0"/>
+                    <remarks source="JTAC">T-72 &lt;dug in&gt;</remarks>
+                </detail>
+            </event>"#;
+
+        // Act
+        let result = Event::from_xml(xml).expect("Failed to deserialize CoT");
+
+        // Assert
+        assert_eq!(result.uid, "ANDROID-589520ccfcd20f01");
+        assert_eq!(result.unit_type, "a-h-G-U-C");
+        assert_eq!(result.how, "h-g-i-g-o");
+        assert_eq!(result.time, "2024-03-01T12:00:00.000Z");
+        assert_eq!(result.stale, "2024-03-01T12:05:00.000Z");
+        assert_eq!(
+            result.point,
+            Point {
+                lat: 41.6,
+                lon: 41.6,
+                hae: 12.5
+            }
+        );
+        assert_eq!(result.detail.call_sign, Some("Target & Co".to_string()));
+        assert_eq!(result.detail.remarks, Some("T-72 <dug in>".to_string()));
+    }
+
+    #[test]
+    fn given_serialized_event_when_deserialized_then_round_trips() {
+        // Arrange
+        let xml = XmlSerializer::default()
+            .serialize_dcs_unit(&build_dcs_unit())
+            .expect("DCS unit XML serialization failed.");
+
+        // Act
+        let event = Event::from_xml(&xml).expect("Failed to deserialize CoT");
+        let result = event.to_xml().expect("Failed to serialize CoT");
+
+        // Assert
+        assert_eq!(result, xml);
+    }
+
+    #[test]
+    fn given_track_and_link_when_deserialized_then_detail_is_read() {
+        // Arrange
+        let xml = r#"<event uid="a" type="b-m-p-s-p-i" how="h-e" time="2024-03-01T12:00:00Z" stale="2024-03-01T12:05:00Z"><point lat="1" lon="2" hae="3"/><detail><track course="90" speed="12.5"/><link uid="JTAC-1" type="a-f-G-U-C" relation="p-p"/></detail></event>"#;
+
+        // Act
+        let result = Event::from_xml(xml).expect("Failed to deserialize CoT");
+
+        // Assert
+        assert_eq!(
+            result.detail.track,
+            Some(Track {
+                course: 90.0,
+                speed: 12.5,
+                slope: 0.0
+            })
+        );
+        assert_eq!(
+            result.detail.link,
+            Some(Link {
+                uid: "JTAC-1".to_string(),
+                unit_type: "a-f-G-U-C".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn given_missing_point_when_deserialized_then_returns_error() {
+        // Arrange
+        let xml = r#"<event uid="a" type="a-f-G" how="h-e" time="2024-03-01T12:00:00Z" stale="2024-03-01T12:05:00Z"><detail/></event>"#;
+
+        // Act
+        let result = Event::from_xml(xml);
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(XmlDeserializationError::MissingElement("point"))
+        );
+    }
+
+    #[test]
+    fn given_invalid_attributes_when_deserialized_then_returns_error() {
+        // Arrange
+        let missing_uid = r#"<event type="a-f-G" how="h-e" time="2024-03-01T12:00:00Z" stale="2024-03-01T12:05:00Z"><point lat="1" lon="2" hae="3"/></event>"#;
+        let invalid_time = r#"<event uid="a" type="a-f-G" how="h-e" time="yesterday" stale="2024-03-01T12:05:00Z"><point lat="1" lon="2" hae="3"/></event>"#;
+        let invalid_lat = r#"<event uid="a" type="a-f-G" how="h-e" time="2024-03-01T12:00:00Z" stale="2024-03-01T12:05:00Z"><point lat="91" lon="2" hae="3"/></event>"#;
+
+        // Act
+        let missing_uid_result = Event::from_xml(missing_uid);
+        let invalid_time_result = Event::from_xml(invalid_time);
+        let invalid_lat_result = Event::from_xml(invalid_lat);
+
+        // Assert
+        assert_eq!(
+            missing_uid_result,
+            Err(XmlDeserializationError::MissingAttribute("event", "uid"))
+        );
+        assert_eq!(
+            invalid_time_result,
+            Err(XmlDeserializationError::InvalidAttribute(
+                "time",
+                "yesterday".to_string()
+            ))
+        );
+        assert_eq!(
+            invalid_lat_result,
+            Err(XmlDeserializationError::InvalidAttribute(
+                "lat",
+                "91".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn given_malformed_or_hostile_xml_when_deserialized_then_returns_error() {
+        // Arrange
+        let inputs = [
+            "Hello, WebSocketHub!",
+            r#"<event uid="a"><point></event>"#,
+            r#"<!DOCTYPE event [<!ENTITY x "y">]><event uid="&x;"/>"#,
+            r#"<event uid="a" type="a-f-G" how="h-e" time="2024-03-01T12:00:00Z" stale="2024-03-01T12:05:00Z"><point lat="1" lon="2" hae="3"/></event><event/>"#,
+        ];
+
+        for input in inputs {
+            // Act
+            let result = Event::from_xml(input);
+
+            // Assert
+            assert!(result.is_err(), "'{}' was accepted", input);
+        }
+    }
+
+    fn build_dcs_unit() -> DcsUnit {
+        DcsUnit {
+            unit_name: "J-01334 <lead>".to_string(),
+            group_name: "Group 1".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 30.0090027,
+                longitude: -85.9578735,
+                altitude: -42.6,
+                heading: 0.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 17,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
+            },
+            mission_date: "2005-04-05".to_string(),
+            mission_start_time: 42_000,
+            mission_time_elapsed: 218,
+        }
+    }
+}
@@ -1,54 +1,321 @@
 
 
+use std::fmt::{self, Display};
+
 use chrono::{Duration, ParseError};
 
-use crate::common::dcs_unit::{DcsUnit, MissionTimeCalculator};
+use crate::{
+    common::dcs_unit::{DcsUnit, MissionTimeCalculator},
+    geoid::{geoid_grid::GeoidGrid, msl_to_hae},
+    registry::{
+        kinematics::Kinematics,
+        track_event::{TrackEvent, TrackedUnit},
+    },
+};
+
+use super::{
+    atomic_event::AtomicEvent,
+    cot_type_map::CotTypeMap,
+    xml_writer::{XmlError, XmlWriter},
+    Detail, Event, Point, Track,
+};
 
-use super::{atomic_event::AtomicEvent, Detail, Event, Point};
+/// CoT type of events requesting the deletion of another event
+pub const COT_DELETE_TYPE: &str = "t-x-d-d";
 
 /// Used to handle XML serialization
 pub trait ToXml {
-    /// Serialize to an XML string.
-    fn to_xml(&self) -> String;
+    /// Writes the XML representation to `writer`.
+    fn write_xml(&self, writer: &mut XmlWriter);
+
+    /// Serialize to a well-formed XML string.
+    fn to_xml(&self) -> Result<String, XmlError> {
+        let mut writer = XmlWriter::new();
+        self.write_xml(&mut writer);
+        writer.finish()
+    }
+}
+
+/// Reasons a DCS unit could not be serialized.
+#[derive(Debug)]
+pub enum SerializationError {
+    /// The mission time of the unit could not be calculated
+    MissionTime(ParseError),
+
+    /// The resulting XML would not be well-formed
+    Xml(XmlError),
+}
+
+impl Display for SerializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerializationError::MissionTime(err) => write!(f, "Invalid mission time: {}", err),
+            SerializationError::Xml(err) => write!(f, "Invalid XML: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SerializationError {}
+
+impl From<ParseError> for SerializationError {
+    fn from(err: ParseError) -> Self {
+        SerializationError::MissionTime(err)
+    }
+}
+
+impl From<XmlError> for SerializationError {
+    fn from(err: XmlError) -> Self {
+        SerializationError::Xml(err)
+    }
 }
 
 /// Handles serialization of DCS units into the cursor-on-target XML format
-pub struct XmlSerializer;
+#[derive(Default)]
+pub struct XmlSerializer {
+    cot_type_map: CotTypeMap,
+    geoid: Option<GeoidGrid>,
+}
 
 impl XmlSerializer {
-    pub fn serialize_dcs_unit(unit: &DcsUnit) -> Result<String, ParseError> {
+    /// Instantiates a new `XmlSerializer` deriving CoT types from `cot_type_map`. Altitudes are
+    /// converted to height above the ellipsoid using `geoid`; without one, they stay MSL.
+    pub fn new(cot_type_map: CotTypeMap, geoid: Option<GeoidGrid>) -> XmlSerializer {
+        XmlSerializer {
+            cot_type_map,
+            geoid,
+        }
+    }
+
+    pub fn serialize_dcs_unit(&self, unit: &DcsUnit) -> Result<String, SerializationError> {
+        let event = self.build_unit_event(unit, None, Duration::try_minutes(1).unwrap())?;
+
+        Ok(event.to_xml()?)
+    }
+
+    /// Serializes a lifecycle change of a tracked unit. Stale units are sent with an expired stale
+    /// time and removed units as a `t-x-d-d` event, so that clients drop them right away.
+    pub fn serialize_track_event(&self, event: &TrackEvent) -> Result<String, SerializationError> {
+        Ok(self.build_track_event(event)?.to_xml()?)
+    }
+
+    /// Builds the CoT event describing a lifecycle change of a tracked unit, for encodings other
+    /// than XML.
+    pub fn build_track_event(&self, event: &TrackEvent) -> Result<Event, SerializationError> {
+        match event {
+            TrackEvent::New(tracked_unit) | TrackEvent::Updated(tracked_unit) => {
+                self.build_tracked_unit_event(tracked_unit, Duration::try_minutes(1).unwrap())
+            }
+            TrackEvent::Stale(tracked_unit) => {
+                self.build_tracked_unit_event(tracked_unit, Duration::zero())
+            }
+            TrackEvent::Removed(tracked_unit) => self.build_removal_event(&tracked_unit.unit),
+        }
+    }
+
+    fn build_tracked_unit_event(
+        &self,
+        tracked_unit: &TrackedUnit,
+        stale_after: Duration,
+    ) -> Result<Event, SerializationError> {
+        Ok(self.build_unit_event(
+            &tracked_unit.unit,
+            tracked_unit.kinematics.as_ref(),
+            stale_after,
+        )?)
+    }
+
+    fn build_removal_event(&self, unit: &DcsUnit) -> Result<Event, SerializationError> {
+        let unit_event = self.build_unit_event(unit, None, Duration::try_minutes(1).unwrap())?;
+
+        Ok(unit_event.to_removal())
+    }
+
+    fn build_unit_event(
+        &self,
+        unit: &DcsUnit,
+        kinematics: Option<&Kinematics>,
+        stale_after: Duration,
+    ) -> Result<Event, ParseError> {
         let mission_time = unit.calculate_mission_time()?;
 
-        let event = Event {
+        Ok(Event {
             point: Point {
                 lat: unit.position.latitude,
                 lon: unit.position.longitude,
-                hae: unit.position.altitude,
+                hae: self.height_above_ellipsoid(unit),
             },
             detail: Detail {
-                call_sign: unit.unit_name.to_string(),
+                call_sign: Some(unit.unit_name.to_string()),
+                track: kinematics.map(|kinematics| Track {
+                    course: kinematics.course,
+                    speed: kinematics.speed,
+                    slope: kinematics
+                        .vertical_rate
+                        .atan2(kinematics.speed)
+                        .to_degrees(),
+                }),
+                link: None,
+                remarks: None,
             },
-            unit_type: AtomicEvent::from(&unit).to_string(),
+            unit_type: AtomicEvent::from(unit, &self.cot_type_map).to_string(),
+            how: "m-g".to_string(),
             uid: unit.unit_name.clone(),
             time: mission_time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-            stale: (mission_time + Duration::try_minutes(1).unwrap())
-                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-        };
+            stale: (mission_time + stale_after).to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        })
+    }
 
-        Ok(event.to_xml())
+    fn height_above_ellipsoid(&self, unit: &DcsUnit) -> f32 {
+        match &self.geoid {
+            Some(geoid) => msl_to_hae(
+                geoid,
+                unit.position.latitude,
+                unit.position.longitude,
+                unit.position.altitude as f64,
+            ) as f32,
+            None => unit.position.altitude,
+        }
     }
 }
 
 #[cfg(test)]
 mod unit_tests {
     use crate::common::{dcs_unit::{Coalition, Position3D, UnitType}, unit_type::Level1UnitType};
+    use crate::registry::{
+        kinematics::Kinematics,
+        track_event::{TrackEvent, TrackedUnit},
+    };
 
     use super::*;
 
     #[test]
     fn given_dcs_unit_when_serialized_then_xml_is_generated_as_cot() {
         // Arrange
-        let unit = DcsUnit {
+        let unit = build_dcs_unit();
+        let expected = r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="J-01334" type="a-h-A-M-F" how="m-g" time="2005-04-05T11:43:38Z" start="2005-04-05T11:43:38Z" stale="2005-04-05T11:44:38Z"><point lat="30.0090027" lon="-85.9578735" ce="0.0" hae="-42.6" le="0.0"/><detail><contact callsign="J-01334"/></detail></event>"#;
+
+        // Act
+        let result =
+            XmlSerializer::default().serialize_dcs_unit(&unit).expect("DCS unit XML serialization failed.");
+
+        // Assert
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn given_stale_track_event_when_serialized_then_stale_time_equals_event_time() {
+        // Arrange
+        let event = TrackEvent::Stale(TrackedUnit {
+            unit: build_dcs_unit(),
+            kinematics: None,
+        });
+        let expected = r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="J-01334" type="a-h-A-M-F" how="m-g" time="2005-04-05T11:43:38Z" start="2005-04-05T11:43:38Z" stale="2005-04-05T11:43:38Z"><point lat="30.0090027" lon="-85.9578735" ce="0.0" hae="-42.6" le="0.0"/><detail><contact callsign="J-01334"/></detail></event>"#;
+
+        // Act
+        let result = XmlSerializer::default().serialize_track_event(&event)
+            .expect("Track event XML serialization failed.");
+
+        // Assert
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn given_removed_track_event_when_serialized_then_delete_event_links_to_unit() {
+        // Arrange
+        let event = TrackEvent::Removed(TrackedUnit {
+            unit: build_dcs_unit(),
+            kinematics: None,
+        });
+        let expected = r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="J-01334-delete" type="t-x-d-d" how="h-g-i-g-o" time="2005-04-05T11:43:38Z" start="2005-04-05T11:43:38Z" stale="2005-04-05T11:44:38Z"><point lat="30.0090027" lon="-85.9578735" ce="0.0" hae="-42.6" le="0.0"/><detail><link uid="J-01334" type="a-h-A-M-F" relation="none"/><__forcedelete/></detail></event>"#;
+
+        // Act
+        let result = XmlSerializer::default().serialize_track_event(&event)
+            .expect("Track event XML serialization failed.");
+
+        // Assert
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn given_updated_track_event_with_kinematics_when_serialized_then_track_detail_is_generated() {
+        // Arrange
+        let event = TrackEvent::Updated(TrackedUnit {
+            unit: build_dcs_unit(),
+            kinematics: Some(Kinematics {
+                speed: 100.0,
+                course: 271.46,
+                vertical_rate: -100.0,
+            }),
+        });
+        let expected = r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="J-01334" type="a-h-A-M-F" how="m-g" time="2005-04-05T11:43:38Z" start="2005-04-05T11:43:38Z" stale="2005-04-05T11:44:38Z"><point lat="30.0090027" lon="-85.9578735" ce="0.0" hae="-42.6" le="0.0"/><detail><contact callsign="J-01334"/><track course="271.5" speed="100.0" slope="-45.0"/></detail></event>"#;
+
+        // Act
+        let result = XmlSerializer::default().serialize_track_event(&event)
+            .expect("Track event XML serialization failed.");
+
+        // Assert
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn given_hostile_unit_and_group_names_when_serialized_then_xml_is_escaped() {
+        // Arrange
+        let mut unit = build_dcs_unit();
+        unit.unit_name = r#""Tank <1>" & Co"#.to_string();
+        unit.group_name = "</event><event uid='x'>".to_string();
+        let expected = r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="&quot;Tank &lt;1&gt;&quot; &amp; Co" type="a-h-A-M-F" how="m-g" time="2005-04-05T11:43:38Z" start="2005-04-05T11:43:38Z" stale="2005-04-05T11:44:38Z"><point lat="30.0090027" lon="-85.9578735" ce="0.0" hae="-42.6" le="0.0"/><detail><contact callsign="&quot;Tank &lt;1&gt;&quot; &amp; Co"/></detail></event>"#;
+
+        // Act
+        let result = XmlSerializer::default()
+            .serialize_dcs_unit(&unit)
+            .expect("DCS unit XML serialization failed.");
+
+        // Assert
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn given_hostile_unit_name_when_removal_is_serialized_then_link_is_escaped() {
+        // Arrange
+        let mut unit = build_dcs_unit();
+        unit.unit_name = "SAM \"Site\" <&>".to_string();
+        let event = TrackEvent::Removed(TrackedUnit {
+            unit,
+            kinematics: None,
+        });
+
+        // Act
+        let result = XmlSerializer::default()
+            .serialize_track_event(&event)
+            .expect("Track event XML serialization failed.");
+
+        // Assert
+        assert!(result.contains(r#"uid="SAM &quot;Site&quot; &lt;&amp;&gt;-delete""#));
+        assert!(result.contains(r#"<link uid="SAM &quot;Site&quot; &lt;&amp;&gt;" type="a-h-A-M-F""#));
+    }
+
+    #[test]
+    fn given_geoid_model_when_serialized_then_altitude_is_converted_to_hae() {
+        // Arrange
+        let geoid = GeoidGrid::from_grd("25 35 -90 -80 10 10  20 20  30 30")
+            .expect("Failed to parse geoid grid");
+        let serializer = XmlSerializer::new(CotTypeMap::default(), Some(geoid));
+
+        // Act
+        let result = serializer
+            .serialize_dcs_unit(&build_dcs_unit())
+            .expect("DCS unit XML serialization failed.");
+
+        // Assert
+        assert!(
+            result.contains(r#"hae="-17.6""#),
+            "unexpected point in {}",
+            result
+        );
+    }
+
+    fn build_dcs_unit() -> DcsUnit {
+        DcsUnit {
             unit_name: "J-01334".to_string(),
             group_name: "J-01335".to_string(),
             coalition: Coalition::REDFOR,
@@ -56,23 +323,18 @@ mod unit_tests {
                 latitude: 30.0090027,
                 longitude: -85.9578735,
                 altitude: -42.6,
-                heading: 0.0568 
+                heading: 0.0568,
             },
             unit_type: UnitType {
                 level_1: Level1UnitType::AIR,
                 level_2: 1,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
             },
             mission_date: "2005-04-05".to_string(),
             mission_start_time: 42_000,
             mission_time_elapsed: 218,
-        };
-        let expected = r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="J-01334" type="a-h-A" how="m-g" time="2005-04-05T11:43:38Z" start="2005-04-05T11:43:38Z" stale="2005-04-05T11:44:38Z"><point lat="30.0090027" lon="-85.9578735" ce="0.0" hae="-42.6" le="0.0"/><detail><contact callsign="J-01334"/></detail></event>"#;
-
-        // Act
-        let result =
-            XmlSerializer::serialize_dcs_unit(&unit).expect("DCS unit XML serialization failed.");
-
-        // Assert
-        assert_eq!(result, expected);
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,360 @@
+use std::fmt::{self, Display};
+
+/// Reasons the XML written to an `XmlWriter` is not well-formed.
+#[derive(Debug, PartialEq)]
+pub enum XmlError {
+    /// An element or attribute name is not a valid XML name
+    InvalidName(String),
+
+    /// An attribute was written outside of a start tag
+    MisplacedAttribute(String),
+
+    /// The XML declaration was written after other content
+    MisplacedDeclaration,
+
+    /// An element was closed while none was open
+    UnbalancedEndElement,
+
+    /// Elements were left open
+    UnclosedElements(Vec<String>),
+
+    /// More than one root element was written
+    MultipleRootElements,
+}
+
+impl Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            XmlError::InvalidName(name) => write!(f, "'{}' is not a valid XML name", name),
+            XmlError::MisplacedAttribute(name) => {
+                write!(f, "Attribute '{}' written outside of a start tag", name)
+            }
+            XmlError::MisplacedDeclaration => write!(f, "XML declaration is not at the start"),
+            XmlError::UnbalancedEndElement => write!(f, "End of element without a start"),
+            XmlError::UnclosedElements(names) => {
+                write!(f, "Elements left open: {}", names.join(", "))
+            }
+            XmlError::MultipleRootElements => write!(f, "More than one root element"),
+        }
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+/// Writes well-formed XML, escaping all text and attribute values. The first error encountered
+/// is reported by `finish`.
+#[derive(Default)]
+pub struct XmlWriter {
+    xml: String,
+    open_elements: Vec<String>,
+    is_start_tag_open: bool,
+    has_root_element: bool,
+    error: Option<XmlError>,
+}
+
+impl XmlWriter {
+    /// Instantiates a new, empty `XmlWriter`.
+    pub fn new() -> XmlWriter {
+        XmlWriter::default()
+    }
+
+    /// Writes the XML declaration, which must come first.
+    pub fn declaration(&mut self) -> &mut Self {
+        if !self.xml.is_empty() {
+            self.fail(XmlError::MisplacedDeclaration);
+        }
+
+        self.xml
+            .push_str(r#"<?xml version="1.0" standalone="yes"?>"#);
+        self
+    }
+
+    /// Opens an element. Attributes may be written until content or another element is written.
+    pub fn start_element(&mut self, name: &str) -> &mut Self {
+        self.validate_name(name);
+        self.close_start_tag();
+
+        if self.open_elements.is_empty() {
+            if self.has_root_element {
+                self.fail(XmlError::MultipleRootElements);
+            }
+            self.has_root_element = true;
+        }
+
+        self.xml.push('<');
+        self.xml.push_str(name);
+        self.open_elements.push(name.to_string());
+        self.is_start_tag_open = true;
+        self
+    }
+
+    /// Writes an attribute of the element that was just opened.
+    pub fn attribute(&mut self, name: &str, value: impl Display) -> &mut Self {
+        self.validate_name(name);
+
+        if !self.is_start_tag_open {
+            self.fail(XmlError::MisplacedAttribute(name.to_string()));
+            return self;
+        }
+
+        self.xml.push(' ');
+        self.xml.push_str(name);
+        self.xml.push_str("=\"");
+        escape_into(&mut self.xml, &value.to_string(), true);
+        self.xml.push('"');
+        self
+    }
+
+    /// Writes text content of the current element.
+    pub fn text(&mut self, text: &str) -> &mut Self {
+        self.close_start_tag();
+        escape_into(&mut self.xml, text, false);
+        self
+    }
+
+    /// Closes the current element, as an empty-element tag if it has no content.
+    pub fn end_element(&mut self) -> &mut Self {
+        let name = match self.open_elements.pop() {
+            Some(name) => name,
+            None => {
+                self.fail(XmlError::UnbalancedEndElement);
+                return self;
+            }
+        };
+
+        if self.is_start_tag_open {
+            self.xml.push_str("/>");
+            self.is_start_tag_open = false;
+        } else {
+            self.xml.push_str("</");
+            self.xml.push_str(&name);
+            self.xml.push('>');
+        }
+        self
+    }
+
+    /// Returns the written XML, or the first error that made it malformed.
+    pub fn finish(self) -> Result<String, XmlError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        if !self.open_elements.is_empty() {
+            return Err(XmlError::UnclosedElements(self.open_elements));
+        }
+
+        Ok(self.xml)
+    }
+
+    fn close_start_tag(&mut self) {
+        if self.is_start_tag_open {
+            self.xml.push('>');
+            self.is_start_tag_open = false;
+        }
+    }
+
+    fn validate_name(&mut self, name: &str) {
+        if !is_valid_name(name) {
+            self.fail(XmlError::InvalidName(name.to_string()));
+        }
+    }
+
+    fn fail(&mut self, error: XmlError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+/// Whether `name` is a valid (ASCII) XML name.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == ':' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ':' | '-' | '.'))
+}
+
+/// Appends `value` to `xml` with markup characters escaped. Characters that XML 1.0 does not
+/// allow at all are replaced with U+FFFD.
+fn escape_into(xml: &mut String, value: &str, is_attribute: bool) {
+    for c in value.chars() {
+        match c {
+            '&' => xml.push_str("&amp;"),
+            '<' => xml.push_str("&lt;"),
+            '>' => xml.push_str("&gt;"),
+            '"' if is_attribute => xml.push_str("&quot;"),
+            '\'' if is_attribute => xml.push_str("&apos;"),
+            // Preserve whitespace that attribute value normalization would otherwise replace
+            '\t' | '\n' | '\r' if is_attribute => xml.push_str(&format!("&#{};", c as u32)),
+            '\t' | '\n' | '\r' => xml.push(c),
+            c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => xml.push('\u{FFFD}'),
+            c => xml.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{XmlError, XmlWriter};
+
+    #[test]
+    fn given_nested_elements_when_written_then_xml_is_well_formed() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer
+            .declaration()
+            .start_element("event")
+            .attribute("uid", "UNIT-1")
+            .start_element("point")
+            .attribute("lat", 30.5)
+            .end_element()
+            .start_element("remarks")
+            .text("Hello")
+            .end_element()
+            .end_element();
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(
+            result,
+            Ok(r#"<?xml version="1.0" standalone="yes"?><event uid="UNIT-1"><point lat="30.5"/><remarks>Hello</remarks></event>"#.to_string())
+        );
+    }
+
+    #[test]
+    fn given_hostile_attribute_value_when_written_then_value_is_escaped() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer
+            .start_element("contact")
+            .attribute("callsign", r#""Tank <1>" & 'Co'"#)
+            .end_element();
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(
+            result,
+            Ok(
+                r#"<contact callsign="&quot;Tank &lt;1&gt;&quot; &amp; &apos;Co&apos;"/>"#
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn given_hostile_text_when_written_then_text_is_escaped() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer
+            .start_element("remarks")
+            .text("</remarks><inject/>\u{0}\n")
+            .end_element();
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(
+            result,
+            Ok("<remarks>&lt;/remarks&gt;&lt;inject/&gt;\u{FFFD}\n</remarks>".to_string())
+        );
+    }
+
+    #[test]
+    fn given_control_characters_in_attribute_when_written_then_they_are_preserved_as_references() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer
+            .start_element("contact")
+            .attribute("callsign", "A\tB\nC\u{7}")
+            .end_element();
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(
+            result,
+            Ok("<contact callsign=\"A&#9;B&#10;C\u{FFFD}\"/>".to_string())
+        );
+    }
+
+    #[test]
+    fn given_invalid_name_when_written_then_finish_returns_error() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer
+            .start_element("event")
+            .attribute("bad name=\"x\"", "value")
+            .end_element();
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(XmlError::InvalidName("bad name=\"x\"".to_string()))
+        );
+    }
+
+    #[test]
+    fn given_unclosed_element_when_finished_then_returns_error() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer.start_element("event").start_element("detail");
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(XmlError::UnclosedElements(vec![
+                "event".to_string(),
+                "detail".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn given_attribute_after_content_when_written_then_finish_returns_error() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer
+            .start_element("event")
+            .start_element("point")
+            .end_element()
+            .attribute("uid", "UNIT-1")
+            .end_element();
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(result, Err(XmlError::MisplacedAttribute("uid".to_string())));
+    }
+
+    #[test]
+    fn given_second_root_element_when_written_then_finish_returns_error() {
+        // Arrange
+        let mut writer = XmlWriter::new();
+
+        // Act
+        writer
+            .start_element("event")
+            .end_element()
+            .start_element("event")
+            .end_element();
+        let result = writer.finish();
+
+        // Assert
+        assert_eq!(result, Err(XmlError::MultipleRootElements));
+    }
+}
@@ -0,0 +1,242 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+};
+
+/// The EGM96 geoid truncated to degree and order 4, on a 5 degree grid. Computed from the EGM96
+/// spherical harmonic coefficients relative to the WGS 84 normal field, it follows the geoid
+/// within tens of meters; a published grid is more accurate.
+const DEFAULT_GEOID_GRID: &str = include_str!("egm96_degree_4.grd");
+
+/// Geoid undulations (height of the geoid above the WGS 84 ellipsoid) on a regular
+/// latitude/longitude grid, such as the EGM96 or EGM2008 grids published by the NGA.
+///
+/// Grids are read in the NGA `.GRD` text format (e.g. `WW15MGH.GRD`): a header of six numbers
+/// `south north west east lat_spacing lon_spacing` in degrees, followed by the undulations in
+/// meters row by row from north to south, each row from west to east including both edges.
+/// Coarser grids can be produced by keeping every n-th row and column of the published ones.
+#[derive(Debug, PartialEq)]
+pub struct GeoidGrid {
+    south: f64,
+    north: f64,
+    west: f64,
+    east: f64,
+    lat_spacing: f64,
+    lon_spacing: f64,
+    rows: usize,
+    columns: usize,
+    undulations: Vec<f64>,
+}
+
+impl GeoidGrid {
+    /// Parses a grid from its NGA `.GRD` text representation.
+    pub fn from_grd(grd: &str) -> io::Result<GeoidGrid> {
+        let mut values = Vec::new();
+        for token in grd.split_whitespace() {
+            let value = token.parse::<f64>().map_err(|_| {
+                invalid_data(format!("'{}' is not a valid geoid grid value", token))
+            })?;
+            values.push(value);
+        }
+
+        if values.len() < 6 {
+            return Err(invalid_data("Geoid grid header is incomplete".to_string()));
+        }
+        let undulations = values.split_off(6);
+        let (south, north, west, east) = (values[0], values[1], values[2], values[3]);
+        let (lat_spacing, lon_spacing) = (values[4], values[5]);
+
+        if south >= north || west >= east || east - west > 360.0 {
+            return Err(invalid_data("Geoid grid bounds are invalid".to_string()));
+        }
+        if lat_spacing <= 0.0 || lon_spacing <= 0.0 {
+            return Err(invalid_data("Geoid grid spacing is invalid".to_string()));
+        }
+
+        let rows = ((north - south) / lat_spacing).round() as usize + 1;
+        let columns = ((east - west) / lon_spacing).round() as usize + 1;
+        if undulations.len() != rows * columns {
+            return Err(invalid_data(format!(
+                "Geoid grid has {} values, expected {} rows of {} columns",
+                undulations.len(),
+                rows,
+                columns
+            )));
+        }
+
+        Ok(GeoidGrid {
+            south,
+            north,
+            west,
+            east,
+            lat_spacing,
+            lon_spacing,
+            rows,
+            columns,
+            undulations,
+        })
+    }
+
+    /// Loads a grid in the NGA `.GRD` text format from the file system.
+    pub fn from_file(file_path: &str) -> io::Result<GeoidGrid> {
+        Self::from_grd(&fs::read_to_string(file_path)?)
+    }
+
+    /// Height of the geoid above the WGS 84 ellipsoid in meters, bilinearly interpolated.
+    /// Positions outside of a regional grid use the undulation at its nearest edge.
+    pub fn undulation(&self, latitude: f64, longitude: f64) -> f64 {
+        let latitude = latitude.clamp(self.south, self.north);
+        let width = self.east - self.west;
+        let mut longitude = (longitude - self.west).rem_euclid(360.0);
+        if longitude > width {
+            // Snap to whichever edge of a regional grid is closer
+            longitude = if longitude - width < 360.0 - longitude {
+                width
+            } else {
+                0.0
+            };
+        }
+
+        let row = (self.north - latitude) / self.lat_spacing;
+        let column = longitude / self.lon_spacing;
+
+        let (row_0, column_0) = (row.floor() as usize, column.floor() as usize);
+        let (row_1, column_1) = (
+            (row_0 + 1).min(self.rows - 1),
+            (column_0 + 1).min(self.columns - 1),
+        );
+        let (row_weight, column_weight) = (row - row_0 as f64, column - column_0 as f64);
+
+        let north_value = lerp(
+            self.value(row_0, column_0),
+            self.value(row_0, column_1),
+            column_weight,
+        );
+        let south_value = lerp(
+            self.value(row_1, column_0),
+            self.value(row_1, column_1),
+            column_weight,
+        );
+
+        lerp(north_value, south_value, row_weight)
+    }
+
+    fn value(&self, row: usize, column: usize) -> f64 {
+        self.undulations[row.min(self.rows - 1) * self.columns + column.min(self.columns - 1)]
+    }
+}
+
+impl Default for GeoidGrid {
+    fn default() -> Self {
+        Self::from_grd(DEFAULT_GEOID_GRID).expect("The default geoid grid is invalid")
+    }
+}
+
+fn lerp(from: f64, to: f64, weight: f64) -> f64 {
+    from + (to - from) * weight
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::GeoidGrid;
+    use crate::geoid::msl_to_hae;
+
+    /// A global grid with 90 degree spacing; not real geoid data.
+    const GLOBAL_GRID: &str = "-90 90 0 360 90 90
+        10 10 10 10 10
+        0 20 40 20 0
+        -10 -10 -10 -10 -10";
+
+    #[test]
+    fn given_position_on_grid_node_when_looked_up_then_node_value_is_returned() {
+        // Arrange
+        let grid = GeoidGrid::from_grd(GLOBAL_GRID).expect("Failed to parse grid");
+
+        // Act
+        let result = grid.undulation(0.0, 180.0);
+
+        // Assert
+        assert_eq!(result, 40.0);
+    }
+
+    #[test]
+    fn given_position_between_nodes_when_looked_up_then_value_is_interpolated() {
+        // Arrange
+        let grid = GeoidGrid::from_grd(GLOBAL_GRID).expect("Failed to parse grid");
+
+        // Act
+        let result = grid.undulation(45.0, 135.0);
+
+        // Assert
+        // Halfway between 10 and the equator value of 30 at 135 degrees east
+        assert_eq!(result, 20.0);
+    }
+
+    #[test]
+    fn given_western_longitude_when_looked_up_then_longitude_wraps_around() {
+        // Arrange
+        let grid = GeoidGrid::from_grd(GLOBAL_GRID).expect("Failed to parse grid");
+
+        // Act
+        let result = grid.undulation(0.0, -90.0);
+
+        // Assert
+        assert_eq!(result, 20.0);
+    }
+
+    #[test]
+    fn given_position_outside_regional_grid_when_looked_up_then_nearest_edge_is_used() {
+        // Arrange
+        let grid = GeoidGrid::from_grd("40 45 40 45 5 5  1 2  3 4").expect("Failed to parse grid");
+
+        // Act
+        let south_east = grid.undulation(30.0, 50.0);
+        let north_west = grid.undulation(60.0, 30.0);
+
+        // Assert
+        assert_eq!(south_east, 4.0);
+        assert_eq!(north_west, 1.0);
+    }
+
+    #[test]
+    fn given_default_grid_when_looked_up_then_major_undulations_are_followed() {
+        // Arrange
+        let grid = GeoidGrid::default();
+
+        // Act
+        let indian_ocean = grid.undulation(5.0, 78.0);
+        let new_guinea = grid.undulation(-5.0, 145.0);
+
+        // Assert
+        assert!(indian_ocean < -50.0, "undulation was {}", indian_ocean);
+        assert!(new_guinea > 50.0, "undulation was {}", new_guinea);
+    }
+
+    #[test]
+    fn given_grid_with_missing_values_when_parsed_then_returns_error() {
+        // Arrange
+        let grd = "-90 90 0 360 90 90 10 10 10";
+
+        // Act
+        let result = GeoidGrid::from_grd(grd);
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn given_msl_altitude_when_converted_then_undulation_is_added() {
+        // Arrange
+        let grid = GeoidGrid::from_grd(GLOBAL_GRID).expect("Failed to parse grid");
+
+        // Act
+        let result = msl_to_hae(&grid, 0.0, 90.0, 1000.0);
+
+        // Assert
+        assert_eq!(result, 1020.0);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod geoid_grid;
+
+use self::geoid_grid::GeoidGrid;
+
+/// Converts an altitude above mean sea level, as reported by DCS, to height above the WGS 84
+/// ellipsoid using the geoid undulation at the given position.
+pub fn msl_to_hae(geoid: &GeoidGrid, latitude: f64, longitude: f64, altitude_msl: f64) -> f64 {
+    altitude_msl + geoid.undulation(latitude, longitude)
+}
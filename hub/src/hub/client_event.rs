@@ -0,0 +1,14 @@
+use crate::cursor_on_target::Event;
+
+/// A CoT event received from a WebSocket client.
+#[derive(Debug, Clone)]
+pub struct ClientEvent {
+    /// The client that sent the event
+    pub client_id: u32,
+
+    /// The parsed and validated event
+    pub event: Event,
+
+    /// The XML as it was received
+    pub xml: String,
+}
@@ -1,23 +1,37 @@
-use super::{ClientRead, ClientWrite, ClientsByIdRead, ClientsByIdWrite};
+use log::info;
+
+use super::{
+    ClientFilter, ClientRead, ClientSentUnits, ClientWrite, ClientsByIdFilter, ClientsByIdRead,
+    ClientsByIdSentUnits, ClientsByIdWrite,
+};
 
 /// Encapsulates client data needed for starting and ending sessions.
 pub struct ClientSession {
     pub client_id: u32,
     clients_by_id_read: ClientsByIdRead,
     clients_by_id_write: ClientsByIdWrite,
+    clients_by_id_filter: ClientsByIdFilter,
+    clients_by_id_sent_units: ClientsByIdSentUnits,
     pub client_read: ClientRead,
     pub client_write: ClientWrite,
+    pub client_filter: ClientFilter,
+    pub client_sent_units: ClientSentUnits,
 }
 
 impl ClientSession {
-    /// Creates a new instance of `ClientSession` and adds it to the `ClientRead` and `ClientWrite` hash tables.
+    /// Creates a new instance of `ClientSession` and adds it to the `ClientRead`, `ClientWrite`, `ClientFilter` and `ClientSentUnits` hash tables.
     pub async fn new(
         client_id: u32,
         clients_by_id_read: ClientsByIdRead,
         clients_by_id_write: ClientsByIdWrite,
+        clients_by_id_filter: ClientsByIdFilter,
+        clients_by_id_sent_units: ClientsByIdSentUnits,
         client_read: ClientRead,
         client_write: ClientWrite,
     ) -> Self {
+        let client_filter = ClientFilter::default();
+        let client_sent_units = ClientSentUnits::default();
+
         {
             clients_by_id_read
                 .lock()
@@ -30,13 +44,29 @@ impl ClientSession {
                 .await
                 .insert(client_id, client_write.clone());
         }
+        {
+            clients_by_id_filter
+                .lock()
+                .await
+                .insert(client_id, client_filter.clone());
+        }
+        {
+            clients_by_id_sent_units
+                .lock()
+                .await
+                .insert(client_id, client_sent_units.clone());
+        }
 
         Self {
             client_id,
             clients_by_id_read,
             clients_by_id_write,
+            clients_by_id_filter,
+            clients_by_id_sent_units,
             client_read,
             client_write,
+            client_filter,
+            client_sent_units,
         }
     }
 }
@@ -46,6 +76,8 @@ impl Drop for ClientSession {
         let client_id = self.client_id;
         let clients_by_id_read = self.clients_by_id_read.clone();
         let clients_by_id_write = self.clients_by_id_write.clone();
+        let clients_by_id_filter = self.clients_by_id_filter.clone();
+        let clients_by_id_sent_units = self.clients_by_id_sent_units.clone();
 
         tokio::spawn(async move {
             {
@@ -56,7 +88,15 @@ impl Drop for ClientSession {
                 let mut clients = clients_by_id_write.lock().await;
                 clients.remove(&client_id);
             }
-            println!("Client {} disconnected", client_id);
+            {
+                let mut clients = clients_by_id_filter.lock().await;
+                clients.remove(&client_id);
+            }
+            {
+                let mut clients = clients_by_id_sent_units.lock().await;
+                clients.remove(&client_id);
+            }
+            info!("Client {} disconnected", client_id);
         });
     }
 }
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+
+use futures_util::SinkExt;
+use log::{debug, warn};
+use tokio::{
+    sync::mpsc::{channel, error::TrySendError, Sender},
+    task::AbortHandle,
+};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::WriteHalf;
+
+/// Number of messages queued for a client before it is disconnected for falling behind
+pub const WEB_SOCKET_CLIENT_QUEUE_SIZE: usize = 4096;
+
+/// The queue of messages written to a client by its own task, so a slow client holds up neither
+/// the broadcast nor the other clients.
+#[derive(Clone)]
+pub struct ClientWrite {
+    client_id: u32,
+    queued_messages: Sender<Message>,
+    write_task: Arc<AbortHandle>,
+}
+
+impl ClientWrite {
+    /// Starts writing the messages queued for a client to its WebSocket. The task ends once the
+    /// client disconnected.
+    pub fn start(client_id: u32, mut write_half: WriteHalf) -> ClientWrite {
+        let (queued_messages, mut receiver) = channel(WEB_SOCKET_CLIENT_QUEUE_SIZE);
+        let write_task = tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if let Err(err) = write_half.send(message).await {
+                    debug!("Failed to write to client {}: {}", client_id, err);
+                    break;
+                }
+            }
+        });
+
+        ClientWrite {
+            client_id,
+            queued_messages,
+            write_task: Arc::new(write_task.abort_handle()),
+        }
+    }
+
+    /// Queues a message for the client. A client whose queue is full is disconnected rather than
+    /// left with gaps in the units it was sent. Returns false once the client is disconnected.
+    pub fn queue(&self, message: Message) -> bool {
+        match self.queued_messages.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                warn!(
+                    "Client {} fell {} messages behind, disconnecting",
+                    self.client_id, WEB_SOCKET_CLIENT_QUEUE_SIZE
+                );
+                self.write_task.abort();
+                false
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+
+    /// Completes once the client is no longer written to, because the connection failed or the
+    /// client fell behind.
+    pub async fn closed(&self) {
+        self.queued_messages.closed().await
+    }
+}
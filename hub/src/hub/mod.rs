@@ -1,7 +1,15 @@
+pub mod client_event;
 mod client_session;
+mod client_write;
+pub mod relay_guard;
+pub mod snapshot;
+pub mod subscription_filter;
 pub mod web_socket_hub;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use futures_util::{
     lock::Mutex,
@@ -10,9 +18,15 @@ use futures_util::{
 use tokio::net::TcpStream;
 use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
 
+use self::{client_write::ClientWrite, subscription_filter::SubscriptionFilter};
+
 pub type ReadHalf = SplitStream<WebSocketStream<TcpStream>>;
 pub type WriteHalf = SplitSink<WebSocketStream<TcpStream>, Message>;
 pub type ClientRead = Arc<Mutex<ReadHalf>>;
-pub type ClientWrite = Arc<Mutex<WriteHalf>>;
+pub type ClientFilter = Arc<Mutex<SubscriptionFilter>>;
+/// The units a client was sent and not told to delete since
+pub type ClientSentUnits = Arc<Mutex<HashSet<String>>>;
 pub type ClientsByIdRead = Arc<Mutex<HashMap<u32, ClientRead>>>;
 pub type ClientsByIdWrite = Arc<Mutex<HashMap<u32, ClientWrite>>>;
+pub type ClientsByIdFilter = Arc<Mutex<HashMap<u32, ClientFilter>>>;
+pub type ClientsByIdSentUnits = Arc<Mutex<HashMap<u32, ClientSentUnits>>>;
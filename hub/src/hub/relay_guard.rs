@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+use crate::cursor_on_target::Event;
+
+/// Times of the last event relayed for a uid
+struct Relayed {
+    time: DateTime<FixedOffset>,
+    stale: DateTime<FixedOffset>,
+}
+
+/// Protects the relay against loops. An event is only relayed if it is newer than the last one
+/// relayed with the same uid and not yet stale, so events echoed back by clients or other relays
+/// are dropped.
+#[derive(Default)]
+pub struct RelayGuard {
+    relayed_by_uid: HashMap<String, Relayed>,
+}
+
+impl RelayGuard {
+    /// Whether `event` should be relayed, recording it if so.
+    pub fn should_relay(&mut self, event: &Event, now: DateTime<Utc>) -> bool {
+        let (time, stale) = match (
+            DateTime::parse_from_rfc3339(&event.time),
+            DateTime::parse_from_rfc3339(&event.stale),
+        ) {
+            (Ok(time), Ok(stale)) => (time, stale),
+            _ => return false,
+        };
+
+        // Stale events are never relayed, so forgetting them cannot open a loop
+        self.relayed_by_uid.retain(|_, relayed| relayed.stale > now);
+        if stale <= now {
+            return false;
+        }
+
+        if let Some(relayed) = self.relayed_by_uid.get(&event.uid) {
+            if time <= relayed.time {
+                return false;
+            }
+        }
+
+        self.relayed_by_uid
+            .insert(event.uid.clone(), Relayed { time, stale });
+        true
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use chrono::{DateTime, Utc};
+
+    use crate::cursor_on_target::{Detail, Event, Point};
+
+    use super::RelayGuard;
+
+    fn event(uid: &str, time: &str, stale: &str) -> Event {
+        Event {
+            unit_type: "a-f-G-U-C".to_string(),
+            how: "h-e".to_string(),
+            uid: uid.to_string(),
+            time: time.to_string(),
+            stale: stale.to_string(),
+            point: Point {
+                lat: 41.6,
+                lon: 41.6,
+                hae: 0.0,
+            },
+            detail: Detail {
+                call_sign: None,
+                track: None,
+                link: None,
+                remarks: None,
+            },
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-03-01T12:00:30Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn given_new_uid_when_checked_then_event_is_relayed() {
+        // Arrange
+        let mut guard = RelayGuard::default();
+        let event = event("JTAC-1", "2024-03-01T12:00:00Z", "2024-03-01T12:05:00Z");
+
+        // Act
+        let result = guard.should_relay(&event, now());
+
+        // Assert
+        assert!(result);
+    }
+
+    #[test]
+    fn given_repeated_or_older_event_when_checked_then_event_is_not_relayed() {
+        // Arrange
+        let mut guard = RelayGuard::default();
+        let first = event("JTAC-1", "2024-03-01T12:00:10Z", "2024-03-01T12:05:00Z");
+        let older = event("JTAC-1", "2024-03-01T12:00:00Z", "2024-03-01T12:05:00Z");
+        guard.should_relay(&first, now());
+
+        // Act
+        let repeated_result = guard.should_relay(&first, now());
+        let older_result = guard.should_relay(&older, now());
+
+        // Assert
+        assert!(!repeated_result);
+        assert!(!older_result);
+    }
+
+    #[test]
+    fn given_newer_event_or_other_uid_when_checked_then_event_is_relayed() {
+        // Arrange
+        let mut guard = RelayGuard::default();
+        guard.should_relay(
+            &event("JTAC-1", "2024-03-01T12:00:00Z", "2024-03-01T12:05:00Z"),
+            now(),
+        );
+        let newer = event("JTAC-1", "2024-03-01T12:00:01Z", "2024-03-01T12:05:01Z");
+        let other = event("JTAC-2", "2024-03-01T12:00:00Z", "2024-03-01T12:05:00Z");
+
+        // Act
+        let newer_result = guard.should_relay(&newer, now());
+        let other_result = guard.should_relay(&other, now());
+
+        // Assert
+        assert!(newer_result);
+        assert!(other_result);
+    }
+
+    #[test]
+    fn given_stale_event_when_checked_then_event_is_not_relayed() {
+        // Arrange
+        let mut guard = RelayGuard::default();
+        let event = event("JTAC-1", "2024-03-01T12:00:00Z", "2024-03-01T12:00:20Z");
+
+        // Act
+        let result = guard.should_relay(&event, now());
+
+        // Assert
+        assert!(!result);
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::{BTreeMap, HashSet};
+
+use super::subscription_filter::{MessageSubject, SubscriptionFilter};
+
+/// The latest message sent about a unit.
+struct SnapshotEntry {
+    message: String,
+
+    /// The message deleting the unit from clients whose filter stops matching it
+    removal: String,
+
+    subject: MessageSubject,
+}
+
+/// The latest message sent about each unit, i.e. the current picture a newly connected client
+/// needs to catch up.
+#[derive(Default)]
+pub struct Snapshot {
+    messages_by_uid: BTreeMap<String, SnapshotEntry>,
+}
+
+impl Snapshot {
+    /// Replaces the message about `uid`, along with the message deleting it.
+    pub fn update(
+        &mut self,
+        uid: String,
+        message: String,
+        removal: String,
+        subject: MessageSubject,
+    ) {
+        self.messages_by_uid.insert(
+            uid,
+            SnapshotEntry {
+                message,
+                removal,
+                subject,
+            },
+        );
+    }
+
+    /// Forgets `uid`, e.g. once its removal has been sent.
+    pub fn remove(&mut self, uid: &str) {
+        self.messages_by_uid.remove(uid);
+    }
+
+    /// The messages matching `filter`, ordered by uid.
+    pub fn messages(&self, filter: &SubscriptionFilter) -> Vec<String> {
+        self.messages_by_uid
+            .values()
+            .filter(|entry| filter.matches(&entry.subject))
+            .map(|entry| entry.message.clone())
+            .collect()
+    }
+
+    /// The messages bringing a client that was sent the units in `sent_uids` up to date with
+    /// `filter`: the latest message about each unit matching it, and the removal of each unit
+    /// sent before that no longer does. `sent_uids` is updated to match.
+    pub fn sync(
+        &self,
+        filter: &SubscriptionFilter,
+        sent_uids: &mut HashSet<String>,
+    ) -> Vec<String> {
+        let mut messages = Vec::new();
+
+        for (uid, entry) in &self.messages_by_uid {
+            if filter.matches(&entry.subject) {
+                sent_uids.insert(uid.clone());
+                messages.push(entry.message.clone());
+            } else if sent_uids.remove(uid) {
+                messages.push(entry.removal.clone());
+            }
+        }
+
+        messages
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::collections::HashSet;
+
+    use crate::{
+        hub::subscription_filter::{Area, MessageSubject, SubscriptionFilter},
+        user_config::coalition_flag::CoalitionFlag,
+    };
+
+    use super::Snapshot;
+
+    fn position(latitude: f64) -> MessageSubject {
+        MessageSubject::Position {
+            latitude,
+            longitude: 42.0,
+        }
+    }
+
+    #[test]
+    fn given_updated_uid_when_messages_are_read_then_latest_message_is_returned() {
+        // Arrange
+        let mut snapshot = Snapshot::default();
+        snapshot.update(
+            "b".to_string(),
+            "b-1".to_string(),
+            "b-delete".to_string(),
+            position(42.0),
+        );
+        snapshot.update(
+            "a".to_string(),
+            "a-1".to_string(),
+            "a-delete".to_string(),
+            position(42.0),
+        );
+        snapshot.update(
+            "b".to_string(),
+            "b-2".to_string(),
+            "b-delete".to_string(),
+            position(42.0),
+        );
+
+        // Act
+        let result = snapshot.messages(&SubscriptionFilter::default());
+
+        // Assert
+        assert_eq!(result, vec!["a-1", "b-2"]);
+    }
+
+    #[test]
+    fn given_removed_uid_when_messages_are_read_then_it_is_not_returned() {
+        // Arrange
+        let mut snapshot = Snapshot::default();
+        snapshot.update(
+            "a".to_string(),
+            "a-1".to_string(),
+            "a-delete".to_string(),
+            position(42.0),
+        );
+        snapshot.update(
+            "b".to_string(),
+            "b-1".to_string(),
+            "b-delete".to_string(),
+            position(42.0),
+        );
+
+        // Act
+        snapshot.remove("a");
+        let result = snapshot.messages(&SubscriptionFilter::default());
+
+        // Assert
+        assert_eq!(result, vec!["b-1"]);
+    }
+
+    #[test]
+    fn given_filter_when_messages_are_read_then_only_matching_messages_are_returned() {
+        // Arrange
+        let mut snapshot = Snapshot::default();
+        snapshot.update(
+            "near".to_string(),
+            "near".to_string(),
+            "near-delete".to_string(),
+            position(42.0),
+        );
+        snapshot.update(
+            "far".to_string(),
+            "far".to_string(),
+            "far-delete".to_string(),
+            position(50.0),
+        );
+        let filter = SubscriptionFilter {
+            coalition_flag: Some(CoalitionFlag::REDFOR),
+            area: Some(Area::Radius {
+                latitude: 42.0,
+                longitude: 42.0,
+                meters: 1_000.0,
+            }),
+            ..Default::default()
+        };
+
+        // Act
+        let result = snapshot.messages(&filter);
+
+        // Assert
+        assert_eq!(result, vec!["near"]);
+    }
+
+    #[test]
+    fn given_client_sent_units_when_synced_with_new_filter_then_units_leaving_it_are_removed() {
+        // Arrange
+        let mut snapshot = Snapshot::default();
+        snapshot.update(
+            "near".to_string(),
+            "near-1".to_string(),
+            "near-delete".to_string(),
+            position(42.0),
+        );
+        snapshot.update(
+            "far".to_string(),
+            "far-1".to_string(),
+            "far-delete".to_string(),
+            position(50.0),
+        );
+        let mut sent_uids = HashSet::from(["far".to_string()]);
+        let filter = SubscriptionFilter {
+            area: Some(Area::Radius {
+                latitude: 42.0,
+                longitude: 42.0,
+                meters: 1_000.0,
+            }),
+            ..Default::default()
+        };
+
+        // Act
+        let result = snapshot.sync(&filter, &mut sent_uids);
+
+        // Assert
+        assert_eq!(result, vec!["far-delete", "near-1"]);
+        assert_eq!(sent_uids, HashSet::from(["near".to_string()]));
+    }
+}
@@ -0,0 +1,373 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::dcs_unit::DcsUnit,
+    registry::kinematics::haversine_distance,
+    user_config::{coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag},
+};
+
+/// What a message sent to clients is about, used to evaluate their subscription filters.
+#[derive(Debug, Clone)]
+pub enum MessageSubject {
+    /// A DCS unit
+    Unit(DcsUnit),
+
+    /// Something at a position that is not a DCS unit, e.g. a marker relayed from a client
+    Position { latitude: f64, longitude: f64 },
+}
+
+/// Geographic area a client is interested in.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Area {
+    /// Area between two parallels and two meridians, crossing the antimeridian if `west > east`
+    BoundingBox {
+        north: f64,
+        south: f64,
+        east: f64,
+        west: f64,
+    },
+
+    /// Area within a distance in meters of a point
+    Radius {
+        latitude: f64,
+        longitude: f64,
+        meters: f64,
+    },
+}
+
+impl Area {
+    /// Whether the position is within the area.
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        match *self {
+            Area::BoundingBox {
+                north,
+                south,
+                east,
+                west,
+            } => {
+                let is_within_longitude = match west <= east {
+                    true => (west..=east).contains(&longitude),
+                    false => longitude >= west || longitude <= east,
+                };
+
+                (south..=north).contains(&latitude) && is_within_longitude
+            }
+            Area::Radius {
+                latitude: center_latitude,
+                longitude: center_longitude,
+                meters,
+            } => {
+                haversine_distance(center_latitude, center_longitude, latitude, longitude) <= meters
+            }
+        }
+    }
+}
+
+/// The messages a client subscribed to. Criteria that are not set do not restrict anything.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Clone, Default)]
+pub struct SubscriptionFilter {
+    /// The coalition(s) of the units the client wants to see
+    #[serde(
+        default,
+        rename = "coalitions",
+        alias = "coalition_flag",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub coalition_flag: Option<CoalitionFlag>,
+
+    /// The unit type(s) the client wants to see
+    #[serde(
+        default,
+        rename = "unit_types",
+        alias = "unit_type_flag",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub unit_type_flag: Option<UnitTypeFlag>,
+
+    /// The area the client wants to see
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub area: Option<Area>,
+
+    /// Unit names the client wants to see, `*` and `?` being wildcards
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unit_name_patterns: Vec<String>,
+
+    /// Group names the client wants to see, `*` and `?` being wildcards
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_name_patterns: Vec<String>,
+}
+
+impl SubscriptionFilter {
+    /// Whether a message about `subject` should be sent to the client. Messages about positions
+    /// are only filtered by area, as the other criteria describe DCS units.
+    pub fn matches(&self, subject: &MessageSubject) -> bool {
+        match subject {
+            MessageSubject::Unit(unit) => self.matches_unit(unit),
+            MessageSubject::Position {
+                latitude,
+                longitude,
+            } => self.is_within_area(*latitude, *longitude),
+        }
+    }
+
+    /// Whether a DCS unit matches every criterion of the filter.
+    pub fn matches_unit(&self, unit: &DcsUnit) -> bool {
+        self.coalition_flag
+            .is_none_or(|flag| flag.includes(unit.coalition))
+            && self
+                .unit_type_flag
+                .is_none_or(|flag| flag.includes(unit.unit_type.level_1))
+            && self.is_within_area(unit.position.latitude, unit.position.longitude)
+            && matches_any(&self.unit_name_patterns, &unit.unit_name)
+            && matches_any(&self.group_name_patterns, &unit.group_name)
+    }
+
+    fn is_within_area(&self, latitude: f64, longitude: f64) -> bool {
+        self.area
+            .as_ref()
+            .is_none_or(|area| area.contains(latitude, longitude))
+    }
+}
+
+/// Whether `name` matches any of `patterns`, or there are no patterns.
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.is_empty()
+        || patterns
+            .iter()
+            .any(|pattern| matches_pattern(pattern, name))
+}
+
+/// Case-insensitive match of `name` against a pattern where `*` matches any number of
+/// characters and `?` matches exactly one.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p + 1, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            // Let the last `*` consume one more character
+            p = star_p;
+            n = star_n + 1;
+            backtrack = Some((star_p, n));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::{
+        common::{
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        user_config::{coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag},
+    };
+
+    use super::{matches_pattern, Area, MessageSubject, SubscriptionFilter};
+
+    #[test]
+    fn given_empty_filter_when_matched_then_everything_matches() {
+        // Arrange
+        let filter = SubscriptionFilter::default();
+
+        // Act
+        let result = filter.matches(&MessageSubject::Unit(build_dcs_unit()));
+
+        // Assert
+        assert!(result);
+    }
+
+    #[test]
+    fn given_coalition_and_unit_type_flags_when_matched_then_unit_must_be_included() {
+        // Arrange
+        let blufor_ground = SubscriptionFilter {
+            coalition_flag: Some(CoalitionFlag::BLUFOR),
+            unit_type_flag: Some(UnitTypeFlag::GROUND),
+            ..Default::default()
+        };
+        let redfor = SubscriptionFilter {
+            coalition_flag: Some(CoalitionFlag::REDFOR | CoalitionFlag::NEUTRAL),
+            ..Default::default()
+        };
+        let air = SubscriptionFilter {
+            unit_type_flag: Some(UnitTypeFlag::AIR),
+            ..Default::default()
+        };
+        let unit = MessageSubject::Unit(build_dcs_unit());
+
+        // Act
+        let blufor_ground_result = blufor_ground.matches(&unit);
+        let redfor_result = redfor.matches(&unit);
+        let air_result = air.matches(&unit);
+
+        // Assert
+        assert!(blufor_ground_result);
+        assert!(!redfor_result);
+        assert!(!air_result);
+    }
+
+    #[test]
+    fn given_bounding_box_when_matched_then_position_must_be_inside() {
+        // Arrange
+        let caucasus = Area::BoundingBox {
+            north: 45.0,
+            south: 40.0,
+            east: 45.0,
+            west: 37.0,
+        };
+        let pacific = Area::BoundingBox {
+            north: 10.0,
+            south: -10.0,
+            east: -170.0,
+            west: 170.0,
+        };
+
+        // Act / Assert
+        assert!(caucasus.contains(42.2, 42.7));
+        assert!(!caucasus.contains(42.2, 47.0));
+        assert!(!caucasus.contains(39.0, 42.7));
+        assert!(pacific.contains(0.0, 179.5));
+        assert!(pacific.contains(0.0, -175.0));
+        assert!(!pacific.contains(0.0, 0.0));
+    }
+
+    #[test]
+    fn given_radius_when_matched_then_position_must_be_within_distance() {
+        // Arrange
+        // One minute of latitude is one nautical mile
+        let area = Area::Radius {
+            latitude: 42.0,
+            longitude: 42.0,
+            meters: 2_000.0,
+        };
+
+        // Act / Assert
+        assert!(area.contains(42.0 + 1.0 / 60.0, 42.0));
+        assert!(!area.contains(42.0 + 2.0 / 60.0, 42.0));
+    }
+
+    #[test]
+    fn given_name_patterns_when_matched_then_unit_and_group_names_must_match() {
+        // Arrange
+        let filter = SubscriptionFilter {
+            unit_name_patterns: vec!["hawg*".to_string(), "Tank ?".to_string()],
+            group_name_patterns: vec!["*Armor*".to_string()],
+            ..Default::default()
+        };
+        let mut matching = build_dcs_unit();
+        matching.unit_name = "Tank 1".to_string();
+        let mut other_group = build_dcs_unit();
+        other_group.group_name = "Infantry".to_string();
+
+        // Act
+        let matching_result = filter.matches(&MessageSubject::Unit(matching));
+        let other_group_result = filter.matches(&MessageSubject::Unit(other_group));
+
+        // Assert
+        assert!(matching_result);
+        assert!(!other_group_result);
+    }
+
+    #[test]
+    fn given_patterns_when_matched_then_wildcards_are_expanded() {
+        assert!(matches_pattern("*", ""));
+        assert!(matches_pattern("HAWG*", "Hawg 1-1"));
+        assert!(matches_pattern("*-1", "Hawg 1-1"));
+        assert!(matches_pattern("h*g ?-*1", "Hawg 1-11"));
+        assert!(!matches_pattern("Hawg ?", "Hawg 12"));
+        assert!(!matches_pattern("*Uzi*", "Hawg 1-1"));
+    }
+
+    #[test]
+    fn given_position_subject_when_matched_then_only_area_is_evaluated() {
+        // Arrange
+        let filter = SubscriptionFilter {
+            coalition_flag: Some(CoalitionFlag::REDFOR),
+            area: Some(Area::Radius {
+                latitude: 42.0,
+                longitude: 42.0,
+                meters: 1_000.0,
+            }),
+            ..Default::default()
+        };
+
+        // Act
+        let inside = filter.matches(&MessageSubject::Position {
+            latitude: 42.0,
+            longitude: 42.0,
+        });
+        let outside = filter.matches(&MessageSubject::Position {
+            latitude: 43.0,
+            longitude: 42.0,
+        });
+
+        // Assert
+        assert!(inside);
+        assert!(!outside);
+    }
+
+    #[test]
+    fn given_subscribe_json_when_deserialized_then_filter_is_returned() {
+        // Arrange
+        let json = r#"{"coalition_flag":2,"area":{"radius":{"latitude":42.0,"longitude":41.5,"meters":5000.0}},"group_name_patterns":["SAM*"]}"#;
+
+        // Act
+        let result: SubscriptionFilter =
+            serde_json::from_str(json).expect("Failed to deserialize filter");
+
+        // Assert
+        assert_eq!(
+            result,
+            SubscriptionFilter {
+                coalition_flag: Some(CoalitionFlag::REDFOR),
+                unit_type_flag: None,
+                area: Some(Area::Radius {
+                    latitude: 42.0,
+                    longitude: 41.5,
+                    meters: 5000.0
+                }),
+                unit_name_patterns: vec![],
+                group_name_patterns: vec!["SAM*".to_string()],
+            }
+        );
+    }
+
+    fn build_dcs_unit() -> DcsUnit {
+        DcsUnit {
+            unit_name: "Hawg 1-1".to_string(),
+            group_name: "Armor Platoon".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 42.0,
+                longitude: 42.0,
+                altitude: 100.0,
+                heading: 0.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 17,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
+            },
+            mission_date: "2005-04-05".to_string(),
+            mission_start_time: 42_000,
+            mission_time_elapsed: 218,
+        }
+    }
+}
@@ -1,40 +1,116 @@
-use std::sync::{
-    atomic::{AtomicU32, Ordering},
-    Arc,
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 
-use futures_util::{future::join_all, lock::Mutex, SinkExt, StreamExt};
+use chrono::Utc;
+use futures_util::{lock::Mutex, StreamExt};
+use log::{debug, info, warn};
+use serde::Deserialize;
 use tokio::{
-    net::TcpListener,
-    sync::mpsc::{channel, Receiver, Sender},
+    net::{TcpListener, TcpStream},
+    sync::{
+        broadcast,
+        mpsc::{channel, error::TrySendError, Receiver, Sender},
+    },
+    time,
 };
 use tokio_tungstenite::{
     accept_async,
     tungstenite::{Error, Message},
 };
 
-use super::{client_session::ClientSession, ClientsByIdRead, ClientsByIdWrite};
+use crate::{
+    common::{dcs_unit::DcsUnit, socket::bind_tcp_listener},
+    cursor_on_target::{xml_deserializer::FromXml, Event},
+};
+
+use super::{
+    client_event::ClientEvent,
+    client_session::ClientSession,
+    client_write::ClientWrite,
+    relay_guard::RelayGuard,
+    snapshot::Snapshot,
+    subscription_filter::{MessageSubject, SubscriptionFilter},
+    ClientFilter, ClientSentUnits, ClientsByIdFilter, ClientsByIdRead, ClientsByIdSentUnits,
+    ClientsByIdWrite,
+};
+
+/// Number of messages queued for the broadcast task
+pub const WEB_SOCKET_BROADCAST_QUEUE_SIZE: usize = 1024;
+
+/// Time a client is given to complete the WebSocket handshake once connected
+pub const WEB_SOCKET_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A message queued for sending to clients
+struct OutboundMessage {
+    /// The message text
+    text: String,
+
+    /// A client the message is not sent to, e.g. the one it was received from
+    excluded_client_id: Option<u32>,
 
-/// Hub for managing web socket communication.
+    /// What the message is about; messages without a subject bypass subscription filters
+    subject: Option<MessageSubject>,
+
+    /// The DCS unit the message updates or removes, if any
+    unit: Option<UnitDelivery>,
+}
+
+/// How a message about a DCS unit is delivered, given the units each client was sent
+enum UnitDelivery {
+    /// The latest state of the unit, sent to the clients whose filter matches it. Clients that
+    /// were sent the unit but whose filter no longer matches it are sent `removal` instead.
+    Update { uid: String, removal: String },
+
+    /// The removal of the unit, sent to the clients that were sent the unit
+    Removal { uid: String },
+}
+
+/// JSON requests clients can send instead of CoT, e.g. `{"subscribe":{"coalitions":["redfor"]}}`
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ClientRequest {
+    /// Replaces the subscription filter of the client
+    Subscribe(SubscriptionFilter),
+}
+
+/// Hub for managing web socket communication. Clones share the clients of the hub.
+#[derive(Clone)]
 pub struct WebSocketHub {
     clients_by_id_read: ClientsByIdRead,
     clients_by_id_write: ClientsByIdWrite,
-    port: u16,
+    clients_by_id_filter: ClientsByIdFilter,
+    clients_by_id_sent_units: ClientsByIdSentUnits,
+    address: SocketAddr,
     next_client_id: Arc<AtomicU32>,
-    message_sender: Sender<String>,
+    message_sender: Sender<OutboundMessage>,
+    relay_guard: Arc<std::sync::Mutex<RelayGuard>>,
+    snapshot: Arc<std::sync::Mutex<Snapshot>>,
+    client_event_sender: broadcast::Sender<ClientEvent>,
 }
 
 impl WebSocketHub {
-    /// Instantiates a new `WebSocketHub` for a port.
-    pub fn new(port: u16) -> WebSocketHub {
-        let (message_sender, message_receiver) = channel(1024);
+    /// Instantiates a new `WebSocketHub` listening on `address`.
+    pub fn new(address: SocketAddr) -> WebSocketHub {
+        let (message_sender, message_receiver) = channel(WEB_SOCKET_BROADCAST_QUEUE_SIZE);
+        let (client_event_sender, _) = broadcast::channel(1024);
 
         let hub = WebSocketHub {
             clients_by_id_read: ClientsByIdRead::default(),
             clients_by_id_write: ClientsByIdWrite::default(),
-            port: port,
+            clients_by_id_filter: ClientsByIdFilter::default(),
+            clients_by_id_sent_units: ClientsByIdSentUnits::default(),
+            address,
             next_client_id: Arc::new(AtomicU32::new(0)),
-            message_sender: message_sender,
+            message_sender,
+            relay_guard: Arc::default(),
+            snapshot: Arc::default(),
+            client_event_sender,
         };
 
         hub.start_broadcast_task(message_receiver);
@@ -43,89 +119,323 @@ impl WebSocketHub {
 
     /// Initiates listening for subscribers.
     pub async fn start(&self) -> Result<(), Error> {
-        let listener = TcpListener::bind(format!("0.0.0.0:{}", self.port)).await?;
+        let listener = TcpListener::from_std(bind_tcp_listener(self.address)?)?;
 
         while let Ok((stream, _)) = listener.accept().await {
-            println!("Attempting to connect client...");
-            let ws_stream = match accept_async(stream).await {
-                Ok(stream) => stream,
-                Err(e) => {
-                    eprintln!("Failed to establish a WebSocket connection: {:?}", e);
-                    continue;
-                }
-            };
-
-            let clients_by_id_read = self.clients_by_id_read.clone();
-            let clients_by_id_write = self.clients_by_id_write.clone();
-            let client_id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
-            let (write_half, read_half) = ws_stream.split();
-            let client_read = Arc::new(Mutex::new(read_half));
-            let client_write = Arc::new(Mutex::new(write_half));
-            let client_session = ClientSession::new(
-                client_id,
-                clients_by_id_read,
-                clients_by_id_write,
-                client_read,
-                client_write,
-            )
-            .await;
-            Self::start_client_listen_task(client_session).await;
+            // Connected on its own, so a client stalling the handshake holds up no other client
+            tokio::spawn(self.clone().connect_client(stream));
         }
 
         Ok(())
     }
 
+    async fn connect_client(self, stream: TcpStream) {
+        debug!("Attempting to connect client...");
+        let ws_stream =
+            match time::timeout(WEB_SOCKET_HANDSHAKE_TIMEOUT, accept_async(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    warn!("Failed to establish a WebSocket connection: {:?}", e);
+                    return;
+                }
+                Err(_) => {
+                    warn!("Failed to establish a WebSocket connection: the handshake timed out");
+                    return;
+                }
+            };
+
+        let client_id = self.next_client_id.fetch_add(1, Ordering::Relaxed);
+        let (write_half, read_half) = ws_stream.split();
+        let client_read = Arc::new(Mutex::new(read_half));
+        let client_write = ClientWrite::start(client_id, write_half);
+        let client_session = ClientSession::new(
+            client_id,
+            self.clients_by_id_read.clone(),
+            self.clients_by_id_write.clone(),
+            self.clients_by_id_filter.clone(),
+            self.clients_by_id_sent_units.clone(),
+            client_read,
+            client_write,
+        )
+        .await;
+        self.listen_to_client(client_session).await;
+    }
+
     /// Sends a message to all subscribers.
     pub fn broadcast_message(&self, message: String) {
-        let message_sender = self.message_sender.clone();
-        tokio::spawn(async move {
-            match message_sender.send(message.clone()).await {
-                Ok(_) => println!("Message sent to clients: {}", message),
-                Err(err) => eprintln!("Failed to send message to clients: {}", err),
-            }
+        self.queue_message(OutboundMessage {
+            text: message,
+            excluded_client_id: None,
+            subject: None,
+            unit: None,
         });
     }
 
-    async fn start_client_listen_task(client_session: ClientSession) {
-        tokio::spawn(async move {
+    /// Sends a message about a DCS unit to the subscribers whose filter matches the unit, and
+    /// `removal` to the subscribers that were sent the unit but whose filter no longer matches
+    /// it. The message is kept as the unit's latest state for clients connecting later on.
+    pub fn broadcast_unit_message(&self, message: String, removal: String, unit: DcsUnit) {
+        let uid = unit.unit_name.clone();
+        let subject = MessageSubject::Unit(unit);
 
-            println!("Successfully connected client {}", client_session.client_id);
-            // Here we're just looping to detect disconnection.
-            while let Some(result) = client_session.client_read.lock().await.next().await {
-                match result {
-                    Ok(_) => {
-                        // TODO: handle incoming client messages
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "Error on WebSocket for client {:?}: {:?}",
-                            client_session.client_id, e
+        self.snapshot.lock().unwrap().update(
+            uid.clone(),
+            message.clone(),
+            removal.clone(),
+            subject.clone(),
+        );
+        self.queue_message(OutboundMessage {
+            text: message,
+            excluded_client_id: None,
+            subject: Some(subject),
+            unit: Some(UnitDelivery::Update { uid, removal }),
+        });
+    }
+
+    /// Sends the message removing a DCS unit to the subscribers that were sent the unit, and
+    /// drops the unit from the state sent to clients connecting later on.
+    pub fn broadcast_unit_removal(&self, message: String, unit: DcsUnit) {
+        let uid = unit.unit_name.clone();
+
+        self.snapshot.lock().unwrap().remove(&uid);
+        self.queue_message(OutboundMessage {
+            text: message,
+            excluded_client_id: None,
+            subject: Some(MessageSubject::Unit(unit)),
+            unit: Some(UnitDelivery::Removal { uid }),
+        });
+    }
+
+    /// Queues a message for the broadcast task, keeping the order the messages were queued in.
+    /// Messages are dropped while the queue is full.
+    fn queue_message(&self, outbound_message: OutboundMessage) {
+        match self.message_sender.try_send(outbound_message) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!("WebSocket broadcast queue is full, dropping message")
+            }
+            Err(TrySendError::Closed(_)) => {
+                warn!("Failed to send message to clients: the broadcast task ended")
+            }
+        }
+    }
+
+    /// Receives the CoT events sent by clients from now on.
+    pub fn subscribe_client_events(&self) -> broadcast::Receiver<ClientEvent> {
+        self.client_event_sender.subscribe()
+    }
+
+    /// Handles the messages received from a client until it disconnected, or was disconnected
+    /// for falling behind.
+    async fn listen_to_client(&self, client_session: ClientSession) {
+        info!("Successfully connected client {}", client_session.client_id);
+
+        // Queued ahead of the live updates following it
+        let filter = client_session.client_filter.lock().await.clone();
+        Self::send_snapshot(&client_session, &self.snapshot, &filter).await;
+
+        loop {
+            let next_message = async { client_session.client_read.lock().await.next().await };
+            let received = tokio::select! {
+                received = next_message => received,
+                // The connection failed, or the client fell behind
+                _ = client_session.client_write.closed() => None,
+            };
+            let Some(result) = received else {
+                break;
+            };
+            match result {
+                Ok(Message::Text(text)) if text.trim_start().starts_with('{') => {
+                    Self::handle_client_request(&client_session, &self.snapshot, &text).await
+                }
+                Ok(Message::Text(xml)) => match Event::from_xml(&xml) {
+                    Ok(event) => {
+                        info!(
+                            "Received {} '{}' from client {}",
+                            event.unit_type, event.uid, client_session.client_id
                         );
-                        break; // Exit gracefully
+
+                        let should_relay = self
+                            .relay_guard
+                            .lock()
+                            .unwrap()
+                            .should_relay(&event, Utc::now());
+                        if should_relay {
+                            let outbound_message = OutboundMessage {
+                                text: xml.clone(),
+                                excluded_client_id: Some(client_session.client_id),
+                                subject: Some(MessageSubject::Position {
+                                    latitude: event.point.lat,
+                                    longitude: event.point.lon,
+                                }),
+                                unit: None,
+                            };
+                            if let Err(err) = self.message_sender.send(outbound_message).await {
+                                warn!("Failed to relay message to clients: {}", err);
+                            }
+                        }
+
+                        // Nobody listening for client events is not an error
+                        let _ = self.client_event_sender.send(ClientEvent {
+                            client_id: client_session.client_id,
+                            event,
+                            xml,
+                        });
                     }
+                    Err(err) => warn!(
+                        "Ignoring invalid CoT from client {}: {}",
+                        client_session.client_id, err
+                    ),
+                },
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(
+                        "Error on WebSocket for client {:?}: {:?}",
+                        client_session.client_id, e
+                    );
+                    break; // Exit gracefully
                 }
             }
-        });
+        }
+    }
+
+    /// Queues the latest message about each unit matching `filter` for the client, and the
+    /// removal of the units it was sent that no longer match.
+    async fn send_snapshot(
+        client_session: &ClientSession,
+        snapshot: &std::sync::Mutex<Snapshot>,
+        filter: &SubscriptionFilter,
+    ) {
+        // Queued while the units sent are locked, so no live update about them gets in between
+        let mut sent_units = client_session.client_sent_units.lock().await;
+        let messages = snapshot.lock().unwrap().sync(filter, &mut sent_units);
+        let message_count = messages.len();
+
+        for message in messages {
+            if !client_session.client_write.queue(Message::text(message)) {
+                warn!(
+                    "Failed to send snapshot to client {}: the client disconnected",
+                    client_session.client_id
+                );
+                return;
+            }
+        }
+
+        info!(
+            "Sent snapshot of {} messages to client {}",
+            message_count, client_session.client_id
+        );
+    }
+
+    async fn handle_client_request(
+        client_session: &ClientSession,
+        snapshot: &std::sync::Mutex<Snapshot>,
+        text: &str,
+    ) {
+        match serde_json::from_str::<ClientRequest>(text) {
+            Ok(ClientRequest::Subscribe(filter)) => {
+                info!(
+                    "Client {} subscribed to {:?}",
+                    client_session.client_id, filter
+                );
+                // Resync the picture, as the filters may differ in the units they include
+                *client_session.client_filter.lock().await = filter.clone();
+                Self::send_snapshot(client_session, snapshot, &filter).await;
+            }
+            Err(err) => warn!(
+                "Ignoring invalid request from client {}: {}",
+                client_session.client_id, err
+            ),
+        }
+    }
+
+    /// The text to send to the client for `message`, if any, keeping track of the units the
+    /// client was sent.
+    async fn client_message(
+        filter: &ClientFilter,
+        sent_units: &ClientSentUnits,
+        message: &OutboundMessage,
+    ) -> Option<String> {
+        let is_subscribed = match &message.subject {
+            Some(subject) => filter.lock().await.matches(subject),
+            None => true,
+        };
+
+        match &message.unit {
+            None => is_subscribed.then(|| message.text.clone()),
+            Some(UnitDelivery::Update { uid, removal }) => {
+                let mut sent_units = sent_units.lock().await;
+                if is_subscribed {
+                    sent_units.insert(uid.clone());
+                    Some(message.text.clone())
+                } else if sent_units.remove(uid) {
+                    Some(removal.clone())
+                } else {
+                    None
+                }
+            }
+            Some(UnitDelivery::Removal { uid }) => sent_units
+                .lock()
+                .await
+                .remove(uid)
+                .then(|| message.text.clone()),
+        }
+    }
+
+    /// The clients to send `message` to, along with the text each is sent. The client tables are
+    /// only locked while the clients are looked up, not while the messages are written.
+    async fn collect_targets(
+        clients_by_id_write: &ClientsByIdWrite,
+        clients_by_id_filter: &ClientsByIdFilter,
+        clients_by_id_sent_units: &ClientsByIdSentUnits,
+        message: &OutboundMessage,
+    ) -> Vec<(ClientWrite, String)> {
+        let clients: Vec<_> = {
+            let clients = clients_by_id_write.lock().await;
+            let filters = clients_by_id_filter.lock().await;
+            let sent_units = clients_by_id_sent_units.lock().await;
+
+            clients
+                .iter()
+                .filter(|(client_id, _)| Some(**client_id) != message.excluded_client_id)
+                .filter_map(|(client_id, client)| {
+                    Some((
+                        client.clone(),
+                        filters.get(client_id)?.clone(),
+                        sent_units.get(client_id)?.clone(),
+                    ))
+                })
+                .collect()
+        };
+
+        let mut targets = Vec::new();
+        for (client, filter, sent_units) in clients {
+            if let Some(text) = Self::client_message(&filter, &sent_units, message).await {
+                targets.push((client, text));
+            }
+        }
+
+        targets
     }
 
-    fn start_broadcast_task(&self, mut message_receiver: Receiver<String>) {
+    fn start_broadcast_task(&self, mut message_receiver: Receiver<OutboundMessage>) {
         let clients_by_id_write = self.clients_by_id_write.clone();
+        let clients_by_id_filter = self.clients_by_id_filter.clone();
+        let clients_by_id_sent_units = self.clients_by_id_sent_units.clone();
         tokio::spawn(async move {
-            // Send messages to each client in parallel
+            // Queue the messages for each client, which writes them on its own
             while let Some(message) = message_receiver.recv().await {
-                let clients = clients_by_id_write.lock().await;
-                let futures: Vec<_> = clients
-                    .iter()
-                    .map(|(_, client)| {
-                        let message = message.clone();
-                        let client = client.clone();
-                        async move {
-                            let mut client = client.lock().await;
-                            client.send(Message::text(message)).await
-                        }
-                    })
-                    .collect();
-                join_all(futures).await; // Ignoring errors for simplicity, handle as needed
+                let targets = Self::collect_targets(
+                    &clients_by_id_write,
+                    &clients_by_id_filter,
+                    &clients_by_id_sent_units,
+                    &message,
+                )
+                .await;
+
+                for (client, text) in targets {
+                    client.queue(Message::text(text));
+                }
             }
         });
     }
@@ -134,6 +444,10 @@ impl WebSocketHub {
 #[cfg(test)]
 mod integration_tests {
     use super::*;
+    use crate::common::{
+        dcs_unit::{Coalition, Position3D, UnitType},
+        unit_type::Level1UnitType,
+    };
     use futures_util::sink::SinkExt;
     use futures_util::stream::StreamExt;
     use std::time::Duration;
@@ -143,9 +457,9 @@ mod integration_tests {
     #[tokio::test]
     async fn test_client_connect_broadcast_and_disconnect() {
         // Start WebSocketHub on an available port (e.g., 0 lets the OS choose the port).
-        let hub = Arc::new(WebSocketHub::new(6655));
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6655))));
         let hub_clone = hub.clone();
-        let port = hub.port;
+        let port = hub.address.port();
         tokio::spawn(async move {
             hub.start().await.expect("Failed to start the WebSocketHub");
         });
@@ -189,4 +503,340 @@ mod integration_tests {
             .await
             .expect("Failed to close the WebSocket stream");
     }
+
+    #[tokio::test]
+    async fn test_client_cot_is_parsed_and_invalid_messages_are_ignored() {
+        // Arrange
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6656))));
+        let mut client_events = hub.subscribe_client_events();
+        let port = hub.address.port();
+        tokio::spawn(async move {
+            hub.start().await.expect("Failed to start the WebSocketHub");
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (mut ws_stream, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        let cot = r#"<event version="2.0" uid="JTAC-1" type="b-m-p-s-p-i" how="h-g-i-g-o" time="2024-03-01T12:00:00Z" start="2024-03-01T12:00:00Z" stale="2024-03-01T12:05:00Z"><point lat="41.6" lon="41.6" hae="12.0" ce="0.0" le="0.0"/><detail><contact callsign="Target 1"/></detail></event>"#;
+
+        // Act
+        ws_stream
+            .send(Message::Text("<event>not CoT".to_string()))
+            .await
+            .expect("Failed to send message from client");
+        ws_stream
+            .send(Message::Text(cot.to_string()))
+            .await
+            .expect("Failed to send message from client");
+
+        // Assert
+        let client_event = timeout(Duration::from_secs(5), client_events.recv())
+            .await
+            .expect("Did not receive client event in time.")
+            .expect("Failed to receive client event");
+        assert_eq!(client_event.event.uid, "JTAC-1");
+        assert_eq!(
+            client_event.event.detail.call_sign,
+            Some("Target 1".to_string())
+        );
+        assert_eq!(client_event.xml, cot);
+    }
+
+    #[tokio::test]
+    async fn test_client_cot_is_relayed_to_other_clients_only_once() {
+        // Arrange
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6657))));
+        let port = hub.address.port();
+        tokio::spawn(async move {
+            hub.start().await.expect("Failed to start the WebSocketHub");
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let url = format!("ws://127.0.0.1:{}", port);
+        let (mut sender, _) = connect_async(&url)
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        let (mut receiver, _) = connect_async(&url)
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let stale = (Utc::now() + chrono::Duration::try_minutes(5).unwrap())
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        let cot = |time: &str| {
+            format!(
+                r#"<event version="2.0" uid="JTAC-1" type="a-f-G-U-C" how="h-e" time="{0}" start="{0}" stale="{1}"><point lat="41.6" lon="41.6" hae="12.0" ce="0.0" le="0.0"/><detail/></event>"#,
+                time, stale
+            )
+        };
+        let first = cot("2024-03-01T12:00:00Z");
+        let second = cot("2024-03-01T12:00:05Z");
+
+        // Act
+        for message in [&first, &first, &second] {
+            sender
+                .send(Message::Text(message.to_string()))
+                .await
+                .expect("Failed to send message from client");
+        }
+
+        // Assert
+        for expected in [&first, &second] {
+            match timeout(Duration::from_secs(5), receiver.next()).await {
+                Ok(Some(Ok(Message::Text(text)))) => assert_eq!(&text, expected),
+                other => panic!("Did not receive relayed message: {:?}", other),
+            }
+        }
+        let echo = timeout(Duration::from_millis(200), sender.next()).await;
+        assert!(echo.is_err(), "Sender received its own message: {:?}", echo);
+    }
+
+    #[tokio::test]
+    async fn test_unit_messages_are_filtered_per_client() {
+        // Arrange
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6658))));
+        let hub_clone = hub.clone();
+        let port = hub.address.port();
+        tokio::spawn(async move {
+            hub.start().await.expect("Failed to start the WebSocketHub");
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let url = format!("ws://127.0.0.1:{}", port);
+        let (mut redfor_client, _) = connect_async(&url)
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        let (mut unfiltered_client, _) = connect_async(&url)
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        redfor_client
+            .send(Message::Text(
+                r#"{"subscribe":{"coalition_flag":2}}"#.to_string(),
+            ))
+            .await
+            .expect("Failed to send subscribe request");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Act
+        hub_clone.broadcast_unit_message(
+            "blufor".to_string(),
+            "blufor-delete".to_string(),
+            build_dcs_unit(Coalition::BLUFOR),
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        hub_clone.broadcast_unit_message(
+            "redfor".to_string(),
+            "redfor-delete".to_string(),
+            build_dcs_unit(Coalition::REDFOR),
+        );
+
+        // Assert
+        for (client, expected) in [
+            (&mut redfor_client, vec!["redfor"]),
+            (&mut unfiltered_client, vec!["blufor", "redfor"]),
+        ] {
+            for expected in expected {
+                match timeout(Duration::from_secs(5), client.next()).await {
+                    Ok(Some(Ok(Message::Text(text)))) => assert_eq!(text, expected),
+                    other => panic!("Did not receive unit message: {:?}", other),
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_client_receives_snapshot_before_live_updates() {
+        // Arrange
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6659))));
+        let hub_clone = hub.clone();
+        let port = hub.address.port();
+        tokio::spawn(async move {
+            hub.start().await.expect("Failed to start the WebSocketHub");
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut removed_unit = build_dcs_unit(Coalition::REDFOR);
+        removed_unit.unit_name = "Unit 2".to_string();
+        let mut other_unit = build_dcs_unit(Coalition::REDFOR);
+        other_unit.unit_name = "Unit 3".to_string();
+        hub_clone.broadcast_unit_message(
+            "unit-1-old".to_string(),
+            "unit-1-old-delete".to_string(),
+            build_dcs_unit(Coalition::BLUFOR),
+        );
+        hub_clone.broadcast_unit_message(
+            "unit-1".to_string(),
+            "unit-1-delete".to_string(),
+            build_dcs_unit(Coalition::BLUFOR),
+        );
+        hub_clone.broadcast_unit_message(
+            "unit-2".to_string(),
+            "unit-2-delete".to_string(),
+            removed_unit.clone(),
+        );
+        hub_clone.broadcast_unit_removal("unit-2-delete".to_string(), removed_unit);
+        hub_clone.broadcast_unit_message(
+            "unit-3".to_string(),
+            "unit-3-delete".to_string(),
+            other_unit,
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Act
+        let (mut client, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        hub_clone.broadcast_message("live".to_string());
+
+        // Assert
+        for expected in ["unit-1", "unit-3", "live"] {
+            match timeout(Duration::from_secs(5), client.next()).await {
+                Ok(Some(Ok(Message::Text(text)))) => assert_eq!(text, expected),
+                other => panic!("Did not receive message: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unit_leaving_client_filter_is_deleted_for_that_client() {
+        // Arrange
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6660))));
+        let hub_clone = hub.clone();
+        let port = hub.address.port();
+        tokio::spawn(async move {
+            hub.start().await.expect("Failed to start the WebSocketHub");
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (mut client, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        client
+            .send(Message::Text(
+                r#"{"subscribe":{"area":{"radius":{"latitude":42.0,"longitude":42.0,"meters":5000.0}}}}"#
+                    .to_string(),
+            ))
+            .await
+            .expect("Failed to send subscribe request");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let mut left_unit = build_dcs_unit(Coalition::REDFOR);
+        left_unit.position.latitude = 43.0;
+
+        // Act
+        hub_clone.broadcast_unit_message(
+            "inside".to_string(),
+            "delete".to_string(),
+            build_dcs_unit(Coalition::REDFOR),
+        );
+        hub_clone.broadcast_unit_message(
+            "outside".to_string(),
+            "delete".to_string(),
+            left_unit.clone(),
+        );
+        hub_clone.broadcast_unit_message(
+            "still-outside".to_string(),
+            "delete".to_string(),
+            left_unit.clone(),
+        );
+        hub_clone.broadcast_unit_removal("removed".to_string(), left_unit);
+        hub_clone.broadcast_message("live".to_string());
+
+        // Assert
+        for expected in ["inside", "delete", "live"] {
+            match timeout(Duration::from_secs(5), client.next()).await {
+                Ok(Some(Ok(Message::Text(text)))) => assert_eq!(text, expected),
+                other => panic!("Did not receive message: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_messages_broadcast_back_to_back_are_received_in_order() {
+        // Arrange
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6661))));
+        let hub_clone = hub.clone();
+        let port = hub.address.port();
+        tokio::spawn(async move {
+            hub.start().await.expect("Failed to start the WebSocketHub");
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (mut client, _) = connect_async(format!("ws://127.0.0.1:{}", port))
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Act
+        for index in 0..100 {
+            hub_clone.broadcast_message(index.to_string());
+        }
+
+        // Assert
+        for expected in 0..100 {
+            match timeout(Duration::from_secs(5), client.next()).await {
+                Ok(Some(Ok(Message::Text(text)))) => assert_eq!(text, expected.to_string()),
+                other => panic!("Did not receive message: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_stalling_the_handshake_does_not_hold_up_other_clients() {
+        // Arrange
+        let hub = Arc::new(WebSocketHub::new(SocketAddr::from(([127, 0, 0, 1], 6662))));
+        let hub_clone = hub.clone();
+        let port = hub.address.port();
+        tokio::spawn(async move {
+            hub.start().await.expect("Failed to start the WebSocketHub");
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let _stalled_client = TcpStream::connect(("127.0.0.1", port))
+            .await
+            .expect("Failed to connect to WebSocketHub");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Act
+        let (mut client, _) = timeout(
+            Duration::from_secs(2),
+            connect_async(format!("ws://127.0.0.1:{}", port)),
+        )
+        .await
+        .expect("Did not connect in time.")
+        .expect("Failed to connect to WebSocketHub");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        hub_clone.broadcast_message("live".to_string());
+
+        // Assert
+        match timeout(Duration::from_secs(2), client.next()).await {
+            Ok(Some(Ok(Message::Text(text)))) => assert_eq!(text, "live"),
+            other => panic!("Did not receive message: {:?}", other),
+        }
+    }
+
+    fn build_dcs_unit(coalition: Coalition) -> DcsUnit {
+        DcsUnit {
+            unit_name: "Unit 1".to_string(),
+            group_name: "Group 1".to_string(),
+            coalition,
+            position: Position3D {
+                latitude: 42.0,
+                longitude: 42.0,
+                altitude: 100.0,
+                heading: 0.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 17,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
+            },
+            mission_date: "2005-04-05".to_string(),
+            mission_start_time: 42_000,
+            mission_time_elapsed: 218,
+        }
+    }
 }
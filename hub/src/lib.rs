@@ -0,0 +1,13 @@
+pub mod cli;
+pub mod common;
+pub mod cursor_on_target;
+pub mod geoid;
+pub mod hub;
+pub mod recording;
+pub mod registry;
+pub mod sink;
+pub mod tacview;
+pub mod tak;
+pub mod traffic;
+pub mod udp_listener;
+pub mod user_config;
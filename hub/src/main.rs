@@ -1,61 +1,340 @@
-use std::{error::Error, sync::Arc};
+use std::{
+    error::Error,
+    io,
+    net::SocketAddr,
+    path::Path,
+    process::ExitCode,
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::Instant,
+};
 
-use common::dcs_unit::DcsUnit;
-use hub::web_socket_hub::WebSocketHub;
-use udp_listener::listen;
-use user_config::{
-    coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag, user_config::UserConfig,
+use clap::Parser;
+use hub::{
+    cli::{
+        cli_args::{CliArgs, Command, ReplayArgs, DEFAULT_CONFIG_PATH, LEGACY_CONFIG_PATH},
+        config_override::ConfigOverride,
+        logger, EXIT_CONFIG_ERROR, EXIT_FAILURE,
+    },
+    common::dcs_unit::DcsUnit,
+    cursor_on_target::{cot_type_map::CotTypeMap, xml_serializer::XmlSerializer},
+    geoid::geoid_grid::GeoidGrid,
+    hub::subscription_filter::SubscriptionFilter,
+    recording::{
+        replay::{replay, ReplayCommand},
+        session_reader::SessionReader,
+    },
+    registry::{
+        track_event::TrackEvent,
+        unit_registry::{UnitRegistry, REGISTRY_SWEEP_INTERVAL},
+    },
+    sink::{log_sink::LogSink, recorder_sink::RecorderSink, sink_set::SinkSet},
+    udp_listener::{export_control::ExportSettings, listen},
+    user_config::{
+        config_watcher::{watch_config, SharedConfig, CONFIG_POLL_INTERVAL},
+        user_config::UserConfig,
+    },
+};
+use log::{error, info, warn};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver},
+    time,
 };
 
-use crate::cursor_on_target::xml_serializer::XmlSerializer;
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = CliArgs::parse();
+    logger::init(args.log_level());
 
-mod common;
-mod cursor_on_target;
-mod hub;
-mod udp_listener;
-mod user_config;
+    if args.print_default_config {
+        return match UserConfig::default().to_toml() {
+            Ok(toml) => {
+                print!("{}", toml);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                error!("Failed to serialize the default configuration: {}", err);
+                ExitCode::from(EXIT_FAILURE)
+            }
+        };
+    }
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let user_config = load_config().unwrap();
-    let hub = Arc::new(WebSocketHub::new(9345));
-    let hub_clone = hub.clone();
-    tokio::spawn(async move { hub.start().await });
+    let loaded = load_config(&args).and_then(|user_config| {
+        let xml_serializer = build_serializer(&user_config)?;
+        Ok((user_config, xml_serializer))
+    });
+    let (user_config, xml_serializer) = match loaded {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            error!("Invalid configuration '{}': {}", args.config, err);
+            return ExitCode::from(EXIT_CONFIG_ERROR);
+        }
+    };
+
+    if args.validate_config {
+        info!("Configuration '{}' is valid", args.config);
+        return ExitCode::SUCCESS;
+    }
+
+    let shared_config = SharedConfig::new(user_config);
+    let watched_args = args.clone();
+    watch_config(
+        args.config.clone(),
+        CONFIG_POLL_INTERVAL,
+        shared_config.clone(),
+        move |file_path| read_config(file_path, &watched_args),
+    );
+
+    match run(shared_config, xml_serializer, &args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            error!("{}", err);
+            ExitCode::from(EXIT_FAILURE)
+        }
+    }
+}
+
+async fn run(
+    shared_config: SharedConfig,
+    xml_serializer: XmlSerializer,
+    args: &CliArgs,
+) -> Result<(), Box<dyn Error>> {
+    let replay_args = args
+        .command
+        .as_ref()
+        .map(|Command::Replay(replay_args)| replay_args);
+
+    let initial_config = shared_config.current();
+    let mut sinks = match args.dry_run {
+        true => {
+            info!("Dry run, units are logged instead of published");
+            let mut sinks = SinkSet::default();
+            sinks.add(Box::<LogSink>::default(), SubscriptionFilter::default());
+            sinks
+        }
+        false => {
+            let mut sinks = SinkSet::from_config(&initial_config.sinks);
+            // Replays are recorded already
+            if initial_config.recording.enabled && replay_args.is_none() {
+                let recorder = RecorderSink::new(initial_config.recording.clone());
+                sinks.add(Box::new(recorder), SubscriptionFilter::default());
+            }
+            sinks
+        }
+    };
+    sinks.start()?;
+    let sinks = Arc::new(RwLock::new(sinks));
+    let publishing_sinks = sinks.clone();
+
+    let listener_address = initial_config.listener_address;
+    let registry = Arc::new(Mutex::new(UnitRegistry::new(
+        initial_config.stale_after_cycles,
+        initial_config.remove_after_cycles,
+    )?));
+    let xml_serializer = Arc::new(xml_serializer);
+    let sweeper = tokio::spawn(sweep_registry(
+        registry.clone(),
+        xml_serializer.clone(),
+        sinks.clone(),
+    ));
 
+    let control_config = shared_config.clone();
+    let export_settings = move || ExportSettings::from_config(&control_config.current());
+
+    let filtered_config = Mutex::new(initial_config.clone());
     let unit_handler = move |unit: DcsUnit| {
+        // Settings may change between units, but stay the same while handling one
+        let user_config = shared_config.current();
+        let mut filtered_config = filtered_config.lock().unwrap();
+        if !Arc::ptr_eq(&filtered_config, &user_config) {
+            publishing_sinks
+                .write()
+                .unwrap()
+                .update_filters(&user_config.sinks);
+            *filtered_config = user_config.clone();
+        }
+        drop(filtered_config);
+
+        // Units are recorded as received, before the settings restrict them
+        let sinks = publishing_sinks.read().unwrap();
+        sinks.record_raw(&unit);
+
         if !user_config.is_unit_configured(&unit) {
             return;
         }
 
-        match XmlSerializer::serialize_dcs_unit(&unit) {
-            Ok(xml) => {
-                hub_clone.broadcast_message(xml);
-            }
-            Err(err) => eprintln!("Failed to serialize DCS unit: {:?}", err),
+        sinks.publish_unit(&unit);
+
+        let mut registry = registry.lock().unwrap();
+        // The configuration was validated, the lifecycle is valid
+        let _ = registry.set_lifecycle(
+            user_config.stale_after_cycles,
+            user_config.remove_after_cycles,
+        );
+        let track_events = registry.observe(unit);
+        publish_track_events(&sinks, &xml_serializer, track_events);
+    };
+
+    let result = tokio::select! {
+        result = receive_units(replay_args, listener_address, export_settings, unit_handler) => result,
+        _ = tokio::signal::ctrl_c() => {
+            info!("Shutting down");
+            Ok(())
         }
     };
 
-    if let Err(e) = listen(unit_handler).await {
-        eprintln!("Listener error: {}", e);
-    }
+    sweeper.abort();
+    sinks.write().unwrap().shutdown();
 
-    Ok(())
+    result
+}
+
+/// Reports the units that stopped reporting while no export cycle arrives, e.g. because DCS
+/// stopped exporting.
+async fn sweep_registry(
+    registry: Arc<Mutex<UnitRegistry>>,
+    xml_serializer: Arc<XmlSerializer>,
+    sinks: Arc<RwLock<SinkSet>>,
+) {
+    let mut interval = time::interval(REGISTRY_SWEEP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        let track_events = registry.lock().unwrap().sweep_idle(Instant::now());
+        publish_track_events(&sinks.read().unwrap(), &xml_serializer, track_events);
+    }
 }
 
-fn load_config() -> Result<UserConfig, Box<dyn Error>> {
-    const CONFIG_FILE_PATH: &str = "hub.config";
-    let user_config = match UserConfig::from_file(CONFIG_FILE_PATH) {
-        Ok(config_from_file) => config_from_file,
-        Err(_) => {
-            let new_config = UserConfig {
-                coalition_flag: CoalitionFlag::BLUFOR,
-                unit_type_flag: UnitTypeFlag::GROUND | UnitTypeFlag::AIR | UnitTypeFlag::SEA,
-                export_frequency_frames: 100,
-            };
-            new_config.to_file(CONFIG_FILE_PATH)?;
-            new_config
+fn publish_track_events(
+    sinks: &SinkSet,
+    xml_serializer: &XmlSerializer,
+    track_events: Vec<TrackEvent>,
+) {
+    for track_event in track_events {
+        match xml_serializer.build_track_event(&track_event) {
+            Ok(event) => sinks.publish_event(&track_event, &event),
+            Err(err) => error!("Failed to serialize DCS unit: {:?}", err),
         }
+    }
+}
+
+/// Feeds `unit_handler` the units exported from DCS, or the units of a recorded session when
+/// replaying one.
+async fn receive_units<S, F>(
+    replay_args: Option<&ReplayArgs>,
+    listener_address: SocketAddr,
+    export_settings: S,
+    unit_handler: F,
+) -> Result<(), Box<dyn Error>>
+where
+    S: Fn() -> ExportSettings + Send + Sync + 'static,
+    F: Fn(DcsUnit) + Send + Sync + 'static,
+{
+    let Some(replay_args) = replay_args else {
+        return listen(listener_address, export_settings, unit_handler).await;
     };
 
+    replay(
+        || SessionReader::open(&replay_args.session_path),
+        replay_args.options(),
+        read_replay_commands(),
+        unit_handler,
+    )
+    .await
+}
+
+/// Reads the commands controlling a replay from the standard input, one per line. A dedicated
+/// thread is used, as a blocking read would keep the runtime from shutting down.
+fn read_replay_commands() -> UnboundedReceiver<ReplayCommand> {
+    let (commands_tx, commands) = mpsc::unbounded_channel();
+
+    thread::spawn(move || {
+        for line in io::stdin().lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match line.parse() {
+                Ok(command) => {
+                    if commands_tx.send(command).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => warn!("{}", err),
+            }
+        }
+    });
+
+    commands
+}
+
+/// Reads the configuration file, after bringing it up to date with `prepare_config_file`.
+fn load_config(args: &CliArgs) -> Result<UserConfig, Box<dyn Error>> {
+    if !args.validate_config {
+        prepare_config_file(&args.config)?;
+    }
+
+    read_config(&args.config, args)
+}
+
+/// Migrates a configuration file written by an earlier version, or creates it from the legacy
+/// configuration file or the default settings if it does not exist.
+fn prepare_config_file(file_path: &str) -> Result<(), Box<dyn Error>> {
+    if Path::new(file_path).exists() {
+        if UserConfig::migrate_file(file_path)? {
+            info!(
+                "Migrated configuration '{}' to the current version, the previous one is kept as '{}.bak'",
+                file_path, file_path
+            );
+        }
+    } else if file_path == DEFAULT_CONFIG_PATH && Path::new(LEGACY_CONFIG_PATH).exists() {
+        UserConfig::from_file(LEGACY_CONFIG_PATH)?.to_file(file_path)?;
+        info!(
+            "Migrated configuration '{}' to '{}'",
+            LEGACY_CONFIG_PATH, file_path
+        );
+    } else {
+        UserConfig::default().to_file(file_path)?;
+        info!("Created configuration '{}'", file_path);
+    }
+
+    Ok(())
+}
+
+/// Reads a configuration file and applies the settings given on the command line.
+fn read_config(file_path: &str, args: &CliArgs) -> Result<UserConfig, Box<dyn Error>> {
+    let user_config = UserConfig::from_file(file_path)?;
+    let mut user_config = ConfigOverride::apply_all(&args.overrides, &user_config)?;
+    args.apply(&mut user_config);
+    user_config.validate()?;
+
     Ok(user_config)
 }
+
+fn build_serializer(user_config: &UserConfig) -> Result<XmlSerializer, Box<dyn Error>> {
+    Ok(XmlSerializer::new(
+        load_cot_type_map(user_config)?,
+        load_geoid(user_config)?,
+    ))
+}
+
+fn load_cot_type_map(user_config: &UserConfig) -> Result<CotTypeMap, Box<dyn Error>> {
+    let mut cot_type_map = CotTypeMap::default();
+
+    if let Some(file_path) = &user_config.cot_type_map_path {
+        cot_type_map.extend(CotTypeMap::from_file(file_path)?);
+    }
+
+    Ok(cot_type_map)
+}
+
+fn load_geoid(user_config: &UserConfig) -> Result<Option<GeoidGrid>, Box<dyn Error>> {
+    match &user_config.geoid_model_path {
+        Some(file_path) => Ok(Some(GeoidGrid::from_file(file_path)?)),
+        None => {
+            info!("No geoid model configured, altitudes are converted to HAE with a coarse EGM96 grid");
+            Ok(Some(GeoidGrid::default()))
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod recorded_unit;
+pub mod replay;
+pub mod session_reader;
+pub mod session_recorder;
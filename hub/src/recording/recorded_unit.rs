@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::common::dcs_unit::DcsUnit;
+
+/// A unit as received from the DCS export, stored as one line of a session file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RecordedUnit {
+    /// When the hub received the unit
+    pub received_at: DateTime<Utc>,
+
+    /// The unit as exported from DCS
+    pub unit: DcsUnit,
+}
+
+/// Identifies the mission a unit was exported from. Units of different missions are recorded to
+/// different session files.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MissionKey {
+    /// The date of the mission, e.g. `2024-03-08`
+    pub mission_date: String,
+
+    /// The start time of the mission, in seconds since midnight
+    pub mission_start_time: i32,
+}
+
+impl MissionKey {
+    /// The mission `unit` was exported from.
+    pub fn of(unit: &DcsUnit) -> MissionKey {
+        MissionKey {
+            mission_date: unit.mission_date.clone(),
+            mission_start_time: unit.mission_start_time,
+        }
+    }
+
+    /// Name of a session file of the mission, e.g.
+    /// `2024-03-08_080000_20260317T190512Z.jsonl.gz` for a mission starting at 08:00:00 recorded
+    /// from 19:05:12 UTC. The recording start keeps sessions of the same mission apart.
+    pub fn session_file_name(&self, recorded_from: DateTime<Utc>) -> String {
+        self.file_name(recorded_from, "jsonl.gz")
+    }
+
+    /// Name of a file of the mission written from `recorded_from`, ending with `extension`.
+    pub fn file_name(&self, recorded_from: DateTime<Utc>, extension: &str) -> String {
+        let start = self.mission_start_time.max(0);
+        let mission_date: String = self
+            .mission_date
+            .chars()
+            .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+                true => c,
+                false => '_',
+            })
+            .collect();
+
+        format!(
+            "{}_{:02}{:02}{:02}_{}.{}",
+            mission_date,
+            start / 3600,
+            start / 60 % 60,
+            start % 60,
+            recorded_from.format("%Y%m%dT%H%M%SZ"),
+            extension
+        )
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::MissionKey;
+
+    #[test]
+    fn given_mission_key_when_named_then_mission_and_recording_start_are_included() {
+        // Arrange
+        let mission_key = MissionKey {
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 8 * 3600 + 30 * 60 + 5,
+        };
+        let recorded_from = Utc.with_ymd_and_hms(2026, 3, 17, 19, 5, 12).unwrap();
+
+        // Act
+        let result = mission_key.session_file_name(recorded_from);
+
+        // Assert
+        assert_eq!(result, "2024-03-08_083005_20260317T190512Z.jsonl.gz");
+    }
+}
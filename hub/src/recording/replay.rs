@@ -0,0 +1,535 @@
+use std::{
+    error::Error,
+    io,
+    iter::Peekable,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use log::info;
+use tokio::{
+    sync::mpsc::UnboundedReceiver,
+    time::{sleep_until, Instant as TokioInstant},
+};
+
+use crate::common::dcs_unit::DcsUnit;
+
+use super::recorded_unit::RecordedUnit;
+
+/// How a session is replayed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayOptions {
+    /// How much faster than real time the session is replayed
+    pub speed: f64,
+
+    /// Mission time to start from, in seconds since the start of the mission
+    pub start: Option<i32>,
+
+    /// Whether the session starts over once it is replayed
+    pub looping: bool,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        ReplayOptions {
+            speed: 1.0,
+            start: None,
+            looping: false,
+        }
+    }
+}
+
+/// Controls a replay while it runs, typed as e.g. `pause`, `resume`, `seek 1:30:00` or
+/// `speed 4`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayCommand {
+    Pause,
+    Resume,
+
+    /// Continues from a mission time, in seconds since the start of the mission
+    Seek(i32),
+
+    /// Changes how much faster than real time the session is replayed
+    Speed(f64),
+}
+
+impl FromStr for ReplayCommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (Some("pause" | "p"), None) => ReplayCommand::Pause,
+            (Some("resume" | "r"), None) => ReplayCommand::Resume,
+            (Some("seek"), Some(mission_time)) => {
+                ReplayCommand::Seek(parse_mission_time(mission_time)?)
+            }
+            (Some("speed"), Some(speed)) => ReplayCommand::Speed(parse_speed(speed)?),
+            _ => {
+                return Err(format!(
+                    "unknown command '{}', expected pause, resume, seek TIME or speed FACTOR",
+                    s.trim()
+                ))
+            }
+        };
+
+        match words.next() {
+            Some(_) => Err(format!("unexpected arguments in '{}'", s.trim())),
+            None => Ok(command),
+        }
+    }
+}
+
+/// Reads a mission time as seconds, `MM:SS` or `HH:MM:SS`, e.g. `1:30:00` for 5400 seconds.
+pub fn parse_mission_time(s: &str) -> Result<i32, String> {
+    let invalid = || format!("invalid mission time '{}', expected HH:MM:SS or seconds", s);
+
+    let mut seconds: i32 = 0;
+    for part in s.split(':') {
+        let value: i32 = part.parse().map_err(|_| invalid())?;
+        if value < 0 {
+            return Err(invalid());
+        }
+        seconds = seconds.checked_mul(60).ok_or_else(invalid)? + value;
+    }
+
+    match s.split(':').count() {
+        1..=3 => Ok(seconds),
+        _ => Err(invalid()),
+    }
+}
+
+/// Reads a replay speed, which must be positive, e.g. `0.5` or `4`.
+pub fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        _ => Err(format!("invalid speed '{}', expected a positive number", s)),
+    }
+}
+
+/// The units of an export cycle, sharing a mission time
+struct Frame {
+    mission_time: i32,
+    units: Vec<DcsUnit>,
+}
+
+/// The frames of a recorded session, read as they are replayed. Seeking backwards reads the
+/// session again from its start.
+struct Playback<S, I>
+where
+    S: Fn() -> io::Result<I>,
+    I: Iterator<Item = io::Result<RecordedUnit>>,
+{
+    open_session: S,
+    recorded_units: Peekable<I>,
+    next_frame: Option<Frame>,
+}
+
+impl<S, I> Playback<S, I>
+where
+    S: Fn() -> io::Result<I>,
+    I: Iterator<Item = io::Result<RecordedUnit>>,
+{
+    /// Reads the session returned by `open_session`, positioned on its first frame.
+    fn open(open_session: S) -> io::Result<Playback<S, I>> {
+        let recorded_units = open_session()?.peekable();
+        let mut playback = Playback {
+            open_session,
+            recorded_units,
+            next_frame: None,
+        };
+        playback.advance()?;
+
+        Ok(playback)
+    }
+
+    /// The frame due next, if the session did not end
+    fn next_frame(&self) -> Option<&Frame> {
+        self.next_frame.as_ref()
+    }
+
+    /// Moves on to the frame after the one due next, grouping consecutive units with the same
+    /// mission time.
+    fn advance(&mut self) -> io::Result<()> {
+        let unit = match self.recorded_units.next() {
+            Some(recorded_unit) => recorded_unit?.unit,
+            None => {
+                self.next_frame = None;
+                return Ok(());
+            }
+        };
+        let mission_time = unit.mission_time_elapsed;
+        let mut units = vec![unit];
+
+        let is_same_frame = |recorded_unit: &io::Result<RecordedUnit>| match recorded_unit {
+            Ok(recorded_unit) => recorded_unit.unit.mission_time_elapsed == mission_time,
+            Err(_) => false,
+        };
+        while let Some(Ok(recorded_unit)) = self.recorded_units.next_if(is_same_frame) {
+            units.push(recorded_unit.unit);
+        }
+
+        self.next_frame = Some(Frame {
+            mission_time,
+            units,
+        });
+        Ok(())
+    }
+
+    /// Reads the session again, from its first frame.
+    fn rewind(&mut self) -> io::Result<()> {
+        self.recorded_units = (self.open_session)()?.peekable();
+        self.advance()
+    }
+
+    /// Positions on the first frame at or after `mission_time`.
+    fn seek(&mut self, mission_time: i32) -> io::Result<()> {
+        if self
+            .next_frame()
+            .is_none_or(|frame| frame.mission_time > mission_time)
+        {
+            self.rewind()?;
+        }
+        while self
+            .next_frame()
+            .is_some_and(|frame| frame.mission_time < mission_time)
+        {
+            self.advance()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps the mission time being replayed to the wall clock.
+struct PlaybackClock {
+    anchored_at: Instant,
+    mission_time: f64,
+    speed: f64,
+}
+
+impl PlaybackClock {
+    fn new(mission_time: i32, speed: f64) -> PlaybackClock {
+        PlaybackClock {
+            anchored_at: Instant::now(),
+            mission_time: mission_time as f64,
+            speed,
+        }
+    }
+
+    /// The mission time being replayed
+    fn position(&self) -> f64 {
+        self.mission_time + self.anchored_at.elapsed().as_secs_f64() * self.speed
+    }
+
+    /// When `mission_time` is due to be replayed
+    fn due(&self, mission_time: i32) -> Instant {
+        let ahead = (mission_time as f64 - self.mission_time).max(0.0);
+        self.anchored_at + Duration::from_secs_f64(ahead / self.speed)
+    }
+
+    /// Continues from `mission_time`, now
+    fn restart_from(&mut self, mission_time: f64) {
+        self.anchored_at = Instant::now();
+        self.mission_time = mission_time;
+    }
+}
+
+/// Feeds the units of a recorded session to `unit_handler` at the pace they were exported, by
+/// their mission time, until the session ends or `commands` is closed while paused. The session
+/// is read from `open_session` as it is replayed, and opened again to replay it from an earlier
+/// mission time.
+pub async fn replay<S, I, F>(
+    open_session: S,
+    options: ReplayOptions,
+    mut commands: UnboundedReceiver<ReplayCommand>,
+    unit_handler: F,
+) -> Result<(), Box<dyn Error>>
+where
+    S: Fn() -> io::Result<I>,
+    I: Iterator<Item = io::Result<RecordedUnit>>,
+    F: Fn(DcsUnit),
+{
+    let mut playback = Playback::open(open_session)?;
+    let first = match playback.next_frame() {
+        Some(frame) => frame.mission_time,
+        None => return Err("The session has no units to replay".into()),
+    };
+    info!(
+        "Replaying from mission time {} at {}x speed",
+        format_mission_time(first),
+        options.speed
+    );
+
+    let start = options.start.unwrap_or(first);
+    playback.seek(start)?;
+    let mut clock = PlaybackClock::new(start, options.speed);
+    let mut paused = false;
+    let mut commands_open = true;
+
+    loop {
+        let Some(frame) = playback.next_frame() else {
+            if !options.looping {
+                info!("Replay finished");
+                return Ok(());
+            }
+            info!("Replaying from the start");
+            playback.rewind()?;
+            clock.restart_from(first as f64);
+            continue;
+        };
+
+        if paused {
+            match commands.recv().await {
+                Some(command) => apply(command, &mut playback, &mut clock, &mut paused)?,
+                None => return Ok(()),
+            }
+            continue;
+        }
+
+        let due = TokioInstant::from_std(clock.due(frame.mission_time));
+        tokio::select! {
+            biased;
+
+            command = commands.recv(), if commands_open => match command {
+                Some(command) => apply(command, &mut playback, &mut clock, &mut paused)?,
+                None => commands_open = false,
+            },
+            _ = sleep_until(due) => {
+                if let Some(frame) = playback.next_frame() {
+                    frame.units.iter().cloned().for_each(&unit_handler);
+                }
+                playback.advance()?;
+            }
+        }
+    }
+}
+
+fn apply<S, I>(
+    command: ReplayCommand,
+    playback: &mut Playback<S, I>,
+    clock: &mut PlaybackClock,
+    paused: &mut bool,
+) -> io::Result<()>
+where
+    S: Fn() -> io::Result<I>,
+    I: Iterator<Item = io::Result<RecordedUnit>>,
+{
+    match command {
+        ReplayCommand::Pause if !*paused => {
+            // The mission time reached, short of the frame due next
+            let position = playback
+                .next_frame()
+                .map_or(clock.position(), |frame| frame.mission_time as f64);
+            clock.restart_from(clock.position().min(position));
+            *paused = true;
+            info!(
+                "Paused at {}",
+                format_mission_time(clock.mission_time as i32)
+            );
+        }
+        ReplayCommand::Resume if *paused => {
+            clock.restart_from(clock.mission_time);
+            *paused = false;
+            info!("Resumed");
+        }
+        ReplayCommand::Seek(mission_time) => {
+            playback.seek(mission_time)?;
+            clock.restart_from(mission_time as f64);
+            info!("Seeked to {}", format_mission_time(mission_time));
+        }
+        ReplayCommand::Speed(speed) => {
+            if !*paused {
+                clock.restart_from(clock.position());
+            }
+            clock.speed = speed;
+            info!("Replaying at {}x speed", speed);
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn format_mission_time(seconds: i32) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{parse_mission_time, ReplayCommand};
+
+    #[test]
+    fn given_command_lines_when_parsed_then_commands_are_returned() {
+        assert_eq!("pause".parse(), Ok(ReplayCommand::Pause));
+        assert_eq!(" r ".parse(), Ok(ReplayCommand::Resume));
+        assert_eq!("seek 1:30:00".parse(), Ok(ReplayCommand::Seek(5400)));
+        assert_eq!("speed 0.5".parse(), Ok(ReplayCommand::Speed(0.5)));
+        assert!("speed 0".parse::<ReplayCommand>().is_err());
+        assert!("rewind".parse::<ReplayCommand>().is_err());
+        assert!("pause now".parse::<ReplayCommand>().is_err());
+    }
+
+    #[test]
+    fn given_mission_times_when_parsed_then_seconds_are_returned() {
+        assert_eq!(parse_mission_time("90"), Ok(90));
+        assert_eq!(parse_mission_time("01:30"), Ok(90));
+        assert_eq!(parse_mission_time("2:00:05"), Ok(7205));
+        assert!(parse_mission_time("1:-1").is_err());
+        assert!(parse_mission_time("1:2:3:4").is_err());
+        assert!(parse_mission_time("").is_err());
+    }
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use chrono::Utc;
+    use tokio::{sync::mpsc, time::timeout};
+
+    use crate::{
+        common::{
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        recording::recorded_unit::RecordedUnit,
+    };
+
+    use super::{replay, ReplayCommand, ReplayOptions};
+
+    #[tokio::test]
+    async fn test_replay_from_start_time() {
+        let open_session = build_session(&[0, 0, 10, 20]);
+        let (received, unit_handler) = build_unit_handler();
+        let (_commands_tx, commands) = mpsc::unbounded_channel();
+        let options = ReplayOptions {
+            speed: 1000.0,
+            start: Some(10),
+            looping: false,
+        };
+
+        replay(open_session, options, commands, unit_handler)
+            .await
+            .expect("Failed to replay");
+
+        assert_eq!(*received.lock().unwrap(), vec![10, 20]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_seeks_back_before_start_time() {
+        let open_session = build_session(&[0, 10, 20]);
+        let (received, unit_handler) = build_unit_handler();
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+        let options = ReplayOptions {
+            speed: 1000.0,
+            start: Some(20),
+            looping: false,
+        };
+
+        commands_tx.send(ReplayCommand::Seek(0)).unwrap();
+        replay(open_session, options, commands, unit_handler)
+            .await
+            .expect("Failed to replay");
+
+        assert_eq!(*received.lock().unwrap(), vec![0, 10, 20]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_commands_and_looping() {
+        let open_session = build_session(&[0, 10, 20]);
+        let (received, unit_handler) = build_unit_handler();
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+        let options = ReplayOptions {
+            speed: 1000.0,
+            start: None,
+            looping: true,
+        };
+
+        // Queued commands are handled before the first frame is replayed
+        commands_tx.send(ReplayCommand::Pause).unwrap();
+        commands_tx.send(ReplayCommand::Seek(20)).unwrap();
+        commands_tx.send(ReplayCommand::Speed(2000.0)).unwrap();
+        commands_tx.send(ReplayCommand::Resume).unwrap();
+
+        let replaying = tokio::spawn(async move {
+            let _ = replay(open_session, options, commands, unit_handler).await;
+        });
+        let looped = timeout(Duration::from_secs(5), async {
+            while received.lock().unwrap().len() < 5 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await;
+        replaying.abort();
+
+        assert!(looped.is_ok(), "Replay did not loop in time");
+        assert_eq!(received.lock().unwrap()[..5], [20, 0, 10, 20, 0]);
+    }
+
+    fn build_unit_handler() -> (
+        Arc<Mutex<Vec<i32>>>,
+        impl Fn(DcsUnit) + Send + Sync + 'static,
+    ) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let unit_handler = {
+            let received = received.clone();
+            move |unit: DcsUnit| received.lock().unwrap().push(unit.mission_time_elapsed)
+        };
+
+        (received, unit_handler)
+    }
+
+    /// Opens a session of one unit per mission time, as many times as it is replayed
+    fn build_session(
+        mission_times: &[i32],
+    ) -> impl Fn() -> io::Result<std::vec::IntoIter<io::Result<RecordedUnit>>> + Send + 'static
+    {
+        let recorded_units: Vec<RecordedUnit> = mission_times
+            .iter()
+            .enumerate()
+            .map(|(index, mission_time)| RecordedUnit {
+                received_at: Utc::now(),
+                unit: build_dcs_unit(index, *mission_time),
+            })
+            .collect();
+
+        move || {
+            let recorded_units: Vec<_> = recorded_units.iter().cloned().map(Ok).collect();
+            Ok(recorded_units.into_iter())
+        }
+    }
+
+    fn build_dcs_unit(index: usize, mission_time: i32) -> DcsUnit {
+        DcsUnit {
+            unit_name: format!("UNIT-{}", index),
+            group_name: "GROUP-1".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 30.0,
+                longitude: -85.9,
+                altitude: 132.67,
+                heading: 0.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::AIR,
+                level_2: 1,
+                level_3: 0,
+                level_4: 0,
+                type_name: "F-16C_50".to_string(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 28800,
+            mission_time_elapsed: mission_time,
+        }
+    }
+}
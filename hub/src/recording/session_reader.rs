@@ -0,0 +1,142 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, ErrorKind, Lines},
+};
+
+use flate2::read::MultiGzDecoder;
+use log::warn;
+
+use super::recorded_unit::RecordedUnit;
+
+/// Reads the units of a session file one at a time, in the order they were recorded, so
+/// sessions of any length are replayed without loading them. The file of a session whose
+/// recording was interrupted ends unexpectedly; the units flushed before are kept.
+pub struct SessionReader {
+    file_path: String,
+    lines: Lines<BufReader<MultiGzDecoder<File>>>,
+    units_read: usize,
+    is_finished: bool,
+}
+
+impl SessionReader {
+    /// Opens the session file at `file_path`, positioned on its first unit.
+    pub fn open(file_path: &str) -> io::Result<SessionReader> {
+        let decoder = MultiGzDecoder::new(File::open(file_path)?);
+
+        Ok(SessionReader {
+            file_path: file_path.to_string(),
+            lines: BufReader::new(decoder).lines(),
+            units_read: 0,
+            is_finished: false,
+        })
+    }
+}
+
+impl Iterator for SessionReader {
+    type Item = io::Result<RecordedUnit>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished {
+            return None;
+        }
+
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            Err(err) => {
+                warn!(
+                    "Session '{}' ends unexpectedly after {} units: {}",
+                    self.file_path, self.units_read, err
+                );
+                self.is_finished = true;
+                return None;
+            }
+        };
+        self.units_read += 1;
+
+        let recorded_unit = serde_json::from_str(&line).map_err(|err| {
+            self.is_finished = true;
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Line {} of '{}': {}", self.units_read, self.file_path, err),
+            )
+        });
+        Some(recorded_unit)
+    }
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::{fs, io};
+
+    use chrono::Utc;
+
+    use crate::{
+        common::{
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        recording::{recorded_unit::MissionKey, session_recorder::SessionRecorder},
+    };
+
+    use super::SessionReader;
+
+    #[test]
+    fn test_read_interrupted_session() {
+        let directory =
+            std::env::temp_dir().join(format!("hub-interrupted-recordings-{}", std::process::id()));
+        let units: Vec<DcsUnit> = (0..3).map(build_dcs_unit).collect();
+
+        let mut recorder = SessionRecorder::new(&directory).expect("Failed to create recorder");
+        for unit in &units {
+            recorder.record(unit, Utc::now()).expect("Failed to record");
+        }
+        let file_path = recorder
+            .session_path(&MissionKey::of(&units[0]))
+            .unwrap()
+            .to_path_buf();
+        recorder.finish().expect("Failed to finish recording");
+
+        // Cut off the gzip trailer, as if the hub was killed
+        let contents = fs::read(&file_path).unwrap();
+        fs::write(&file_path, &contents[..contents.len() - 8]).unwrap();
+
+        let recorded_units = SessionReader::open(file_path.to_str().unwrap())
+            .expect("Failed to open session")
+            .collect::<io::Result<Vec<_>>>()
+            .expect("Failed to read session");
+
+        assert_eq!(
+            recorded_units
+                .into_iter()
+                .map(|recorded_unit| recorded_unit.unit)
+                .collect::<Vec<_>>(),
+            units
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    fn build_dcs_unit(index: usize) -> DcsUnit {
+        DcsUnit {
+            unit_name: format!("UNIT-{}", index),
+            group_name: "GROUP-1".to_string(),
+            coalition: Coalition::REDFOR,
+            position: Position3D {
+                latitude: 42.0,
+                longitude: 41.5,
+                altitude: 120.0,
+                heading: 1.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 16,
+                level_3: 27,
+                level_4: 0,
+                type_name: "SA-11 Buk LN 9A310M1".to_string(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 28800,
+            mission_time_elapsed: 60,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use flate2::{write::GzEncoder, Compression};
+use log::info;
+
+use crate::common::dcs_unit::DcsUnit;
+
+use super::recorded_unit::{MissionKey, RecordedUnit};
+
+/// How often the recorded units are flushed to the session files, bounding what is lost if the
+/// hub is killed
+pub const RECORDING_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long the session file of a mission stays open once no unit of the mission is received.
+/// A stray unit of another mission is recorded to its own file without ending the current one.
+pub const RECORDING_SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A session file being recorded to
+struct Session {
+    path: PathBuf,
+    encoder: GzEncoder<File>,
+    recorded_at: Instant,
+    is_flushed: bool,
+}
+
+/// Appends the units received from DCS to a gzip-compressed JSON Lines file per mission. The
+/// file of a mission is completed once its units stop being received.
+pub struct SessionRecorder {
+    directory: PathBuf,
+    sessions: HashMap<MissionKey, Session>,
+}
+
+impl SessionRecorder {
+    /// Records the sessions to `directory`, which is created if needed.
+    pub fn new(directory: impl AsRef<Path>) -> io::Result<SessionRecorder> {
+        fs::create_dir_all(&directory)?;
+
+        Ok(SessionRecorder {
+            directory: directory.as_ref().to_path_buf(),
+            sessions: HashMap::new(),
+        })
+    }
+
+    /// Records a unit received at `received_at`, to the session file of its mission.
+    pub fn record(&mut self, unit: &DcsUnit, received_at: DateTime<Utc>) -> io::Result<()> {
+        let session = match self.sessions.entry(MissionKey::of(unit)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let session = start_session(&self.directory, entry.key(), received_at)?;
+                entry.insert(session)
+            }
+        };
+
+        let recorded_unit = RecordedUnit {
+            received_at,
+            unit: unit.clone(),
+        };
+        serde_json::to_writer(&mut session.encoder, &recorded_unit)?;
+        session.encoder.write_all(b"\n")?;
+        session.recorded_at = Instant::now();
+        session.is_flushed = false;
+
+        Ok(())
+    }
+
+    /// Flushes the units recorded since the last flush, and completes the session files of the
+    /// missions whose units stopped being received by `now`.
+    pub fn flush(&mut self, now: Instant) -> io::Result<()> {
+        let timed_out: Vec<MissionKey> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| {
+                now.saturating_duration_since(session.recorded_at) >= RECORDING_SESSION_TIMEOUT
+            })
+            .map(|(mission_key, _)| mission_key.clone())
+            .collect();
+        for mission_key in timed_out {
+            if let Some(session) = self.sessions.remove(&mission_key) {
+                info!("Completed recording '{}'", session.path.display());
+                session.encoder.finish()?;
+            }
+        }
+
+        for session in self.sessions.values_mut() {
+            if !session.is_flushed {
+                session.encoder.flush()?;
+                session.is_flushed = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The session file the units of `mission_key` are recorded to, if any.
+    pub fn session_path(&self, mission_key: &MissionKey) -> Option<&Path> {
+        self.sessions
+            .get(mission_key)
+            .map(|session| session.path.as_path())
+    }
+
+    /// Completes every session file being recorded to.
+    pub fn finish(&mut self) -> io::Result<()> {
+        for (_, session) in self.sessions.drain() {
+            session.encoder.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+fn start_session(
+    directory: &Path,
+    mission_key: &MissionKey,
+    received_at: DateTime<Utc>,
+) -> io::Result<Session> {
+    let path = directory.join(mission_key.session_file_name(received_at));
+    // Appending keeps what was recorded if the name is reused, as a new gzip member
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    info!("Recording mission to '{}'", path.display());
+
+    Ok(Session {
+        path,
+        encoder: GzEncoder::new(file, Compression::default()),
+        recorded_at: Instant::now(),
+        is_flushed: true,
+    })
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::{
+        fs::{self, File},
+        io::{BufRead, BufReader},
+        path::Path,
+        time::Instant,
+    };
+
+    use chrono::{TimeZone, Utc};
+    use flate2::read::MultiGzDecoder;
+
+    use crate::{
+        common::{
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        recording::recorded_unit::{MissionKey, RecordedUnit},
+    };
+
+    use super::{SessionRecorder, RECORDING_SESSION_TIMEOUT};
+
+    #[test]
+    fn test_record_sessions_per_mission() {
+        let directory = std::env::temp_dir().join(format!("hub-recordings-{}", std::process::id()));
+        let received_at = Utc.with_ymd_and_hms(2026, 3, 17, 19, 5, 12).unwrap();
+        let first_mission = [
+            build_dcs_unit("UNIT-1", 28800),
+            build_dcs_unit("UNIT-2", 28800),
+        ];
+        let second_mission = build_dcs_unit("UNIT-3", 36000);
+
+        let mut recorder = SessionRecorder::new(&directory).expect("Failed to create recorder");
+        // The mission flips to the second one and back, without ending the first session
+        recorder
+            .record(&first_mission[0], received_at)
+            .expect("Failed to record");
+        recorder
+            .record(&second_mission, received_at)
+            .expect("Failed to record");
+        recorder
+            .record(&first_mission[1], received_at)
+            .expect("Failed to record");
+        let first_path = recorder
+            .session_path(&MissionKey::of(&first_mission[0]))
+            .unwrap()
+            .to_path_buf();
+        let second_path = recorder
+            .session_path(&MissionKey::of(&second_mission))
+            .unwrap()
+            .to_path_buf();
+        recorder
+            .flush(Instant::now() + RECORDING_SESSION_TIMEOUT)
+            .expect("Failed to flush recording");
+        let timed_out = recorder.session_path(&MissionKey::of(&second_mission));
+
+        assert!(timed_out.is_none());
+        assert_eq!(
+            first_path,
+            directory.join("2024-03-08_080000_20260317T190512Z.jsonl.gz")
+        );
+        assert_eq!(
+            second_path,
+            directory.join("2024-03-08_100000_20260317T190512Z.jsonl.gz")
+        );
+        let first_session = read_session(&first_path);
+        assert_eq!(first_session.len(), 2);
+        assert_eq!(first_session[1].unit, first_mission[1]);
+        assert_eq!(first_session[1].received_at, received_at);
+        assert_eq!(read_session(&second_path)[0].unit, second_mission);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    fn read_session(path: &Path) -> Vec<RecordedUnit> {
+        let file = File::open(path).expect("Failed to open session file");
+        BufReader::new(MultiGzDecoder::new(file))
+            .lines()
+            .map(|line| serde_json::from_str(&line.unwrap()).expect("Invalid recorded unit"))
+            .collect()
+    }
+
+    fn build_dcs_unit(unit_name: &str, mission_start_time: i32) -> DcsUnit {
+        DcsUnit {
+            unit_name: unit_name.to_string(),
+            group_name: "GROUP-1".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 30.0090027,
+                longitude: -85.9578735,
+                altitude: 132.67,
+                heading: 0.0568,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 1,
+                level_3: 0,
+                level_4: 0,
+                type_name: "M1A2".to_string(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time,
+            mission_time_elapsed: 3600,
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::VecDeque;
+
+use crate::common::dcs_unit::DcsUnit;
+
+/// Mean radius of the earth in meters
+pub const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// Minimum mission time spanned by the samples used to derive kinematics. DCS reports the
+/// elapsed mission time in whole seconds, so deriving speed from consecutive export cycles
+/// alone would make it jump by up to a factor of two.
+pub const KINEMATICS_WINDOW_SECONDS: i32 = 4;
+
+/// Position of a unit at a point in mission time
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PositionSample {
+    /// The time elapsed since the start of the mission
+    pub mission_time_elapsed: i32,
+
+    /// Latitudinal position in degrees
+    pub latitude: f64,
+
+    /// Longitudinal position in degrees
+    pub longitude: f64,
+
+    /// Elevation in meters
+    pub altitude: f32,
+}
+
+impl PositionSample {
+    pub fn from(unit: &DcsUnit) -> PositionSample {
+        PositionSample {
+            mission_time_elapsed: unit.mission_time_elapsed,
+            latitude: unit.position.latitude,
+            longitude: unit.position.longitude,
+            altitude: unit.position.altitude,
+        }
+    }
+}
+
+/// Motion of a unit derived from its successive positions. This is the movement over the ground,
+/// which may differ from the heading reported by DCS (e.g. for aircraft in a crosswind).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Kinematics {
+    /// Ground speed in meters per second
+    pub speed: f64,
+
+    /// Course over ground in degrees clockwise from true north
+    pub course: f64,
+
+    /// Rate of climb (positive) or descent (negative) in meters per second
+    pub vertical_rate: f64,
+}
+
+impl Kinematics {
+    /// Derives the motion between two samples, if `later` is actually later than `earlier`.
+    pub fn between(earlier: &PositionSample, later: &PositionSample) -> Option<Kinematics> {
+        let elapsed = (later.mission_time_elapsed - earlier.mission_time_elapsed) as f64;
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        let distance = haversine_distance(
+            earlier.latitude,
+            earlier.longitude,
+            later.latitude,
+            later.longitude,
+        );
+
+        let (lat_1, lat_2) = (earlier.latitude.to_radians(), later.latitude.to_radians());
+        let delta_lon = (later.longitude - earlier.longitude).to_radians();
+
+        // Initial bearing
+        let y = delta_lon.sin() * lat_2.cos();
+        let x = lat_1.cos() * lat_2.sin() - lat_1.sin() * lat_2.cos() * delta_lon.cos();
+        let course = y.atan2(x).to_degrees().rem_euclid(360.0);
+
+        Some(Kinematics {
+            speed: distance / elapsed,
+            course,
+            vertical_rate: (later.altitude - earlier.altitude) as f64 / elapsed,
+        })
+    }
+}
+
+/// Great-circle distance in meters between two positions given in degrees.
+pub fn haversine_distance(
+    latitude_1: f64,
+    longitude_1: f64,
+    latitude_2: f64,
+    longitude_2: f64,
+) -> f64 {
+    let (lat_1, lat_2) = (latitude_1.to_radians(), latitude_2.to_radians());
+    let delta_lat = lat_2 - lat_1;
+    let delta_lon = (longitude_2 - longitude_1).to_radians();
+
+    let a = (delta_lat / 2.0).sin().powi(2)
+        + lat_1.cos() * lat_2.cos() * (delta_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+/// Recent positions of a unit spanning at least `KINEMATICS_WINDOW_SECONDS`.
+#[derive(Default)]
+pub struct SampleHistory {
+    samples: VecDeque<PositionSample>,
+}
+
+impl SampleHistory {
+    /// Adds a sample and returns the motion over the retained samples, if any time has passed.
+    pub fn push(&mut self, sample: PositionSample) -> Option<Kinematics> {
+        match self.samples.back() {
+            Some(last) if last.mission_time_elapsed > sample.mission_time_elapsed => {
+                self.samples.clear()
+            }
+            Some(last) if last.mission_time_elapsed == sample.mission_time_elapsed => {
+                self.samples.pop_back();
+            }
+            _ => {}
+        }
+        self.samples.push_back(sample);
+
+        // Drop the oldest sample as long as the remaining ones still span the window
+        while self.samples.len() > 2
+            && sample.mission_time_elapsed - self.samples[1].mission_time_elapsed
+                >= KINEMATICS_WINDOW_SECONDS
+        {
+            self.samples.pop_front();
+        }
+
+        Kinematics::between(self.samples.front()?, &sample)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{Kinematics, PositionSample, SampleHistory};
+
+    fn sample(
+        mission_time_elapsed: i32,
+        latitude: f64,
+        longitude: f64,
+        altitude: f32,
+    ) -> PositionSample {
+        PositionSample {
+            mission_time_elapsed,
+            latitude,
+            longitude,
+            altitude,
+        }
+    }
+
+    #[test]
+    fn given_unit_moving_north_when_derived_then_course_is_north_and_speed_matches_distance() {
+        // Arrange
+        // One minute of latitude is one nautical mile
+        let earlier = sample(0, 42.0, 41.0, 1000.0);
+        let later = sample(10, 42.0 + 1.0 / 60.0, 41.0, 1000.0);
+
+        // Act
+        let result = Kinematics::between(&earlier, &later).expect("Kinematics not derived");
+
+        // Assert
+        assert!(
+            (result.speed - 185.3).abs() < 0.5,
+            "speed was {}",
+            result.speed
+        );
+        assert!(result.course.abs() < 0.01, "course was {}", result.course);
+        assert_eq!(result.vertical_rate, 0.0);
+    }
+
+    #[test]
+    fn given_unit_moving_west_and_climbing_when_derived_then_course_is_west_and_rate_positive() {
+        // Arrange
+        let earlier = sample(100, 25.0, 55.0, 500.0);
+        let later = sample(104, 25.0, 54.99, 520.0);
+
+        // Act
+        let result = Kinematics::between(&earlier, &later).expect("Kinematics not derived");
+
+        // Assert
+        assert!(
+            (result.course - 270.0).abs() < 0.01,
+            "course was {}",
+            result.course
+        );
+        assert_eq!(result.vertical_rate, 5.0);
+    }
+
+    #[test]
+    fn given_samples_at_same_time_when_derived_then_no_kinematics() {
+        // Arrange
+        let earlier = sample(10, 25.0, 55.0, 500.0);
+        let later = sample(10, 25.1, 55.0, 500.0);
+
+        // Act
+        let result = Kinematics::between(&earlier, &later);
+
+        // Assert
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn given_samples_pushed_when_window_is_exceeded_then_oldest_samples_are_dropped() {
+        // Arrange
+        let mut history = SampleHistory::default();
+
+        // Act
+        let first = history.push(sample(0, 0.0, 0.0, 0.0));
+        let second = history.push(sample(1, 0.0, 0.0, 10.0));
+        history.push(sample(3, 0.0, 0.0, 30.0));
+        history.push(sample(5, 0.0, 0.0, 50.0));
+        let last = history.push(sample(8, 0.0, 0.0, 160.0));
+
+        // Assert
+        assert_eq!(first, None);
+        assert_eq!(
+            second.map(|kinematics| kinematics.vertical_rate),
+            Some(10.0)
+        );
+        // Derived from the sample at 3 seconds, the newest one at least 4 seconds old
+        assert_eq!(last.map(|kinematics| kinematics.vertical_rate), Some(26.0));
+    }
+
+    #[test]
+    fn given_mission_time_going_backwards_when_pushed_then_history_starts_over() {
+        // Arrange
+        let mut history = SampleHistory::default();
+        history.push(sample(100, 0.0, 0.0, 0.0));
+
+        // Act
+        let result = history.push(sample(0, 0.0, 0.0, 0.0));
+
+        // Assert
+        assert_eq!(result, None);
+    }
+}
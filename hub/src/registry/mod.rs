@@ -0,0 +1,3 @@
+pub mod kinematics;
+pub mod track_event;
+pub mod unit_registry;
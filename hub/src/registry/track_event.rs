@@ -0,0 +1,46 @@
+use crate::common::dcs_unit::DcsUnit;
+
+use super::kinematics::Kinematics;
+
+/// A unit report together with the motion derived from the unit's previous reports.
+#[derive(Debug, PartialEq, Clone)]
+pub struct TrackedUnit {
+    /// The last-known state of the unit
+    pub unit: DcsUnit,
+
+    /// The unit's motion, once it has reported at least twice
+    pub kinematics: Option<Kinematics>,
+}
+
+/// Lifecycle change of a unit tracked by the `UnitRegistry`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TrackEvent {
+    /// The unit reported for the first time
+    New(TrackedUnit),
+
+    /// A known unit reported again
+    Updated(TrackedUnit),
+
+    /// The unit stopped reporting and its last-known state is no longer current
+    Stale(TrackedUnit),
+
+    /// The unit stopped reporting long enough to be considered destroyed or despawned
+    Removed(TrackedUnit),
+}
+
+impl TrackEvent {
+    /// The tracked unit the event refers to.
+    pub fn tracked_unit(&self) -> &TrackedUnit {
+        match self {
+            TrackEvent::New(tracked_unit)
+            | TrackEvent::Updated(tracked_unit)
+            | TrackEvent::Stale(tracked_unit)
+            | TrackEvent::Removed(tracked_unit) => tracked_unit,
+        }
+    }
+
+    /// The unit state the event refers to.
+    pub fn unit(&self) -> &DcsUnit {
+        &self.tracked_unit().unit
+    }
+}
@@ -0,0 +1,511 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{common::dcs_unit::DcsUnit, user_config::config_error::ConfigError};
+
+use super::{
+    kinematics::{PositionSample, SampleHistory},
+    track_event::{TrackEvent, TrackedUnit},
+};
+
+/// How far, in seconds, the mission clock may go back before the mission is considered restarted.
+/// Units reporting a time less far back are late units of an earlier cycle.
+pub const MISSION_RESTART_THRESHOLD: i32 = 60;
+
+/// How often the registry should be swept for units that stopped reporting while no export
+/// cycle arrives
+pub const REGISTRY_SWEEP_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Identifies an export cycle by the mission clock carried by its units.
+#[derive(Debug, PartialEq)]
+struct CycleKey {
+    mission_date: String,
+    mission_start_time: i32,
+    mission_time_elapsed: i32,
+}
+
+impl CycleKey {
+    fn from(unit: &DcsUnit) -> CycleKey {
+        CycleKey {
+            mission_date: unit.mission_date.clone(),
+            mission_start_time: unit.mission_start_time,
+            mission_time_elapsed: unit.mission_time_elapsed,
+        }
+    }
+
+    /// Whether the cycle belongs to a different (or restarted) mission than `previous`.
+    fn is_new_mission(&self, previous: &CycleKey) -> bool {
+        self.mission_date != previous.mission_date
+            || self.mission_start_time != previous.mission_start_time
+            || self.mission_time_elapsed < previous.mission_time_elapsed - MISSION_RESTART_THRESHOLD
+    }
+
+    /// Whether the cycle is older than `previous` without belonging to a restarted mission.
+    fn is_late(&self, previous: &CycleKey) -> bool {
+        self.mission_time_elapsed < previous.mission_time_elapsed
+    }
+}
+
+/// Last-known state of a unit.
+struct Track {
+    tracked_unit: TrackedUnit,
+    history: SampleHistory,
+    last_seen_cycle: u64,
+    is_stale: bool,
+}
+
+/// Keeps the last-known state of every unit by `unit_name` and detects units that stop reporting.
+pub struct UnitRegistry {
+    tracks: HashMap<String, Track>,
+    stale_after_cycles: u32,
+    remove_after_cycles: u32,
+    current_cycle_key: Option<CycleKey>,
+    current_cycle: u64,
+
+    /// When the current cycle arrived, how long the previous one lasted, and how many cycles
+    /// were counted as missed since
+    cycle_started_at: Instant,
+    cycle_duration: Option<Duration>,
+    idle_cycles: u32,
+}
+
+impl UnitRegistry {
+    /// Creates a new `UnitRegistry`.
+    ///
+    /// # Arguments
+    /// * `stale_after_cycles` - Number of export cycles a unit may miss before it is reported as stale.
+    /// * `remove_after_cycles` - Number of export cycles a unit may miss before it is removed,
+    ///   no less than `stale_after_cycles`.
+    pub fn new(
+        stale_after_cycles: u32,
+        remove_after_cycles: u32,
+    ) -> Result<UnitRegistry, ConfigError> {
+        validate_lifecycle(stale_after_cycles, remove_after_cycles)?;
+
+        Ok(UnitRegistry {
+            tracks: HashMap::new(),
+            stale_after_cycles,
+            remove_after_cycles,
+            current_cycle_key: None,
+            current_cycle: 0,
+            cycle_started_at: Instant::now(),
+            cycle_duration: None,
+            idle_cycles: 0,
+        })
+    }
+
+    /// Changes the number of export cycles units may miss, e.g. after the configuration changed.
+    /// Units are evaluated against the new numbers from the next export cycle on. Invalid numbers
+    /// are rejected, leaving the current ones in effect.
+    pub fn set_lifecycle(
+        &mut self,
+        stale_after_cycles: u32,
+        remove_after_cycles: u32,
+    ) -> Result<(), ConfigError> {
+        validate_lifecycle(stale_after_cycles, remove_after_cycles)?;
+
+        self.stale_after_cycles = stale_after_cycles;
+        self.remove_after_cycles = remove_after_cycles;
+        Ok(())
+    }
+
+    /// Records a unit report and returns the resulting lifecycle events. A unit carrying a new
+    /// mission time starts a new export cycle, which reports units missing from previous cycles.
+    pub fn observe(&mut self, unit: DcsUnit) -> Vec<TrackEvent> {
+        let mut events = self.advance_cycle(&unit);
+
+        let current_cycle = self.current_cycle;
+        let mut is_new = false;
+        let track = self
+            .tracks
+            .entry(unit.unit_name.clone())
+            .or_insert_with(|| {
+                is_new = true;
+                Track {
+                    tracked_unit: TrackedUnit {
+                        unit: unit.clone(),
+                        kinematics: None,
+                    },
+                    history: SampleHistory::default(),
+                    last_seen_cycle: current_cycle,
+                    is_stale: false,
+                }
+            });
+
+        track.tracked_unit = TrackedUnit {
+            kinematics: track.history.push(PositionSample::from(&unit)),
+            unit,
+        };
+        track.last_seen_cycle = current_cycle;
+        track.is_stale = false;
+
+        let event = if is_new {
+            TrackEvent::New(track.tracked_unit.clone())
+        } else {
+            TrackEvent::Updated(track.tracked_unit.clone())
+        };
+
+        events.push(event);
+        events
+    }
+
+    /// Removes every tracked unit, returning a `TrackEvent::Removed` for each.
+    pub fn clear(&mut self) -> Vec<TrackEvent> {
+        let mut events: Vec<_> = self
+            .tracks
+            .drain()
+            .map(|(_, track)| TrackEvent::Removed(track.tracked_unit))
+            .collect();
+
+        events.sort_by(|a, b| a.unit().unit_name.cmp(&b.unit().unit_name));
+        events
+    }
+
+    /// Counts the export cycles that did not arrive in time as missed by every unit, so units
+    /// are reported even when DCS stops exporting altogether. A cycle is expected every time the
+    /// previous cycle lasted.
+    pub fn sweep_idle(&mut self, now: Instant) -> Vec<TrackEvent> {
+        let Some(cycle_duration) = self.cycle_duration.filter(|duration| !duration.is_zero())
+        else {
+            return Vec::new();
+        };
+
+        // The cycle expected last may still be on its way
+        let elapsed_cycles = now
+            .saturating_duration_since(self.cycle_started_at)
+            .as_secs_f64()
+            / cycle_duration.as_secs_f64();
+        let mut events = Vec::new();
+        while ((self.idle_cycles + 1) as f64) < elapsed_cycles {
+            self.idle_cycles += 1;
+            self.current_cycle += 1;
+            events.extend(self.sweep());
+        }
+
+        events
+    }
+
+    fn advance_cycle(&mut self, unit: &DcsUnit) -> Vec<TrackEvent> {
+        let cycle_key = CycleKey::from(unit);
+
+        let events = match &self.current_cycle_key {
+            Some(current) if *current == cycle_key => return Vec::new(),
+            Some(current) if cycle_key.is_new_mission(current) => {
+                self.cycle_duration = None;
+                self.clear()
+            }
+            Some(current) if cycle_key.is_late(current) => return Vec::new(),
+            Some(_) => {
+                // Cycles that arrived late measure the time DCS was silent, not the interval
+                if self.idle_cycles == 0 {
+                    self.cycle_duration = Some(self.cycle_started_at.elapsed());
+                }
+                self.current_cycle += 1;
+                self.sweep()
+            }
+            None => Vec::new(),
+        };
+
+        self.cycle_started_at = Instant::now();
+        self.idle_cycles = 0;
+        self.current_cycle_key = Some(cycle_key);
+        events
+    }
+
+    /// Reports units that did not report during the cycles completed so far.
+    fn sweep(&mut self) -> Vec<TrackEvent> {
+        let last_completed_cycle = self.current_cycle - 1;
+        let mut events = Vec::new();
+
+        self.tracks.retain(|_, track| {
+            let missed_cycles = last_completed_cycle - track.last_seen_cycle;
+
+            if missed_cycles >= self.remove_after_cycles as u64 {
+                events.push(TrackEvent::Removed(track.tracked_unit.clone()));
+                return false;
+            }
+
+            if missed_cycles >= self.stale_after_cycles as u64 && !track.is_stale {
+                track.is_stale = true;
+                events.push(TrackEvent::Stale(track.tracked_unit.clone()));
+            }
+
+            true
+        });
+
+        events.sort_by(|a, b| a.unit().unit_name.cmp(&b.unit().unit_name));
+        events
+    }
+}
+
+/// Units must be reported as stale before they are removed.
+pub fn validate_lifecycle(
+    stale_after_cycles: u32,
+    remove_after_cycles: u32,
+) -> Result<(), ConfigError> {
+    if remove_after_cycles < stale_after_cycles {
+        return Err(ConfigError::InvalidSetting {
+            key: "remove_after_cycles".to_string(),
+            reason: format!(
+                "{} is less than stale_after_cycles {}",
+                remove_after_cycles, stale_after_cycles
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::{
+        common::{
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        registry::{
+            kinematics::Kinematics,
+            track_event::{TrackEvent, TrackedUnit},
+        },
+    };
+
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::UnitRegistry;
+
+    #[test]
+    fn given_unknown_unit_when_observed_then_new_event_is_emitted() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+        let unit = build_dcs_unit("UNIT-1", 0);
+
+        // Act
+        let result = registry.observe(unit.clone());
+
+        // Assert
+        assert_eq!(result, vec![TrackEvent::New(tracked(unit, None))]);
+    }
+
+    #[test]
+    fn given_known_unit_when_observed_again_then_updated_event_is_emitted() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+        registry.observe(build_dcs_unit("UNIT-1", 0));
+        let unit = build_dcs_unit("UNIT-1", 1);
+
+        // Act
+        let result = registry.observe(unit.clone());
+
+        // Assert
+        assert_eq!(
+            result,
+            vec![TrackEvent::Updated(tracked(unit, stationary()))]
+        );
+    }
+
+    #[test]
+    fn given_unit_missing_from_cycles_when_cycles_advance_then_unit_becomes_stale_then_removed() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+        let lost_unit = build_dcs_unit("LOST", 0);
+        registry.observe(lost_unit.clone());
+        registry.observe(build_dcs_unit("ALIVE", 0));
+
+        // Act
+        let first_cycle = registry.observe(build_dcs_unit("ALIVE", 1));
+        let second_cycle = registry.observe(build_dcs_unit("ALIVE", 2));
+        let third_cycle = registry.observe(build_dcs_unit("ALIVE", 3));
+
+        // Assert
+        assert_eq!(
+            first_cycle,
+            vec![TrackEvent::Updated(tracked(
+                build_dcs_unit("ALIVE", 1),
+                stationary()
+            ))]
+        );
+        assert_eq!(
+            second_cycle,
+            vec![
+                TrackEvent::Stale(tracked(lost_unit.clone(), None)),
+                TrackEvent::Updated(tracked(build_dcs_unit("ALIVE", 2), stationary()))
+            ]
+        );
+        assert_eq!(
+            third_cycle,
+            vec![
+                TrackEvent::Removed(tracked(lost_unit, None)),
+                TrackEvent::Updated(tracked(build_dcs_unit("ALIVE", 3), stationary()))
+            ]
+        );
+        assert_eq!(
+            registry.observe(build_dcs_unit("LOST", 3)),
+            vec![TrackEvent::New(tracked(build_dcs_unit("LOST", 3), None))]
+        );
+    }
+
+    #[test]
+    fn given_stale_unit_when_it_reports_again_then_it_is_tracked_again() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 5).unwrap();
+        registry.observe(build_dcs_unit("LOST", 0));
+        registry.observe(build_dcs_unit("ALIVE", 1));
+        registry.observe(build_dcs_unit("ALIVE", 2));
+
+        // Act
+        let result = registry.observe(build_dcs_unit("LOST", 2));
+
+        // Assert
+        assert_eq!(
+            result,
+            vec![TrackEvent::Updated(tracked(
+                build_dcs_unit("LOST", 2),
+                stationary()
+            ))]
+        );
+    }
+
+    #[test]
+    fn given_mission_restart_when_observed_then_all_units_are_removed() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+        let old_unit = build_dcs_unit("OLD", 600);
+        registry.observe(old_unit.clone());
+        let restarted_unit = build_dcs_unit("NEW", 0);
+
+        // Act
+        let result = registry.observe(restarted_unit.clone());
+
+        // Assert
+        assert_eq!(
+            result,
+            vec![
+                TrackEvent::Removed(tracked(old_unit, None)),
+                TrackEvent::New(tracked(restarted_unit, None))
+            ]
+        );
+    }
+
+    #[test]
+    fn given_late_unit_of_earlier_cycle_when_observed_then_units_are_kept() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+        registry.observe(build_dcs_unit("CURRENT", 600));
+        let late_unit = build_dcs_unit("LATE", 599);
+
+        // Act
+        let result = registry.observe(late_unit.clone());
+
+        // Assert
+        assert_eq!(result, vec![TrackEvent::New(tracked(late_unit, None))]);
+        assert_eq!(
+            registry.observe(build_dcs_unit("CURRENT", 601)),
+            vec![TrackEvent::Updated(tracked(
+                build_dcs_unit("CURRENT", 601),
+                stationary()
+            ))]
+        );
+    }
+
+    #[test]
+    fn given_export_stopped_when_swept_then_units_become_stale_then_removed() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+        let unit = build_dcs_unit("UNIT-1", 1);
+        registry.observe(build_dcs_unit("UNIT-1", 0));
+        thread::sleep(Duration::from_millis(20));
+        registry.observe(unit.clone());
+
+        // Act
+        let result = registry.sweep_idle(Instant::now() + Duration::from_secs(60));
+
+        // Assert
+        let tracked_unit = tracked(unit, stationary());
+        assert_eq!(
+            result,
+            vec![
+                TrackEvent::Stale(tracked_unit.clone()),
+                TrackEvent::Removed(tracked_unit)
+            ]
+        );
+        assert_eq!(registry.sweep_idle(Instant::now()), vec![]);
+    }
+
+    #[test]
+    fn given_removal_before_stale_when_created_or_changed_then_lifecycle_is_rejected() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+
+        // Act
+        let created = UnitRegistry::new(3, 2);
+        let changed = registry.set_lifecycle(3, 2);
+
+        // Assert
+        assert!(created.is_err());
+        assert!(changed.is_err());
+        assert_eq!(registry.set_lifecycle(2, 2), Ok(()));
+    }
+
+    #[test]
+    fn given_moving_unit_when_observed_again_then_kinematics_are_derived() {
+        // Arrange
+        let mut registry = UnitRegistry::new(1, 2).unwrap();
+        registry.observe(build_dcs_unit("UNIT-1", 0));
+        let mut unit = build_dcs_unit("UNIT-1", 10);
+        unit.position.latitude += 1.0 / 60.0;
+        unit.position.altitude += 50.0;
+
+        // Act
+        let result = registry.observe(unit);
+
+        // Assert
+        let kinematics = result[0]
+            .tracked_unit()
+            .kinematics
+            .expect("Kinematics not derived");
+        assert!((kinematics.speed - 185.3).abs() < 0.5);
+        assert!(kinematics.course.abs() < 0.01);
+        assert!((kinematics.vertical_rate - 5.0).abs() < 0.001);
+    }
+
+    fn tracked(unit: DcsUnit, kinematics: Option<Kinematics>) -> TrackedUnit {
+        TrackedUnit { unit, kinematics }
+    }
+
+    fn stationary() -> Option<Kinematics> {
+        Some(Kinematics {
+            speed: 0.0,
+            course: 0.0,
+            vertical_rate: 0.0,
+        })
+    }
+
+    fn build_dcs_unit(unit_name: &str, mission_time_elapsed: i32) -> DcsUnit {
+        DcsUnit {
+            unit_name: unit_name.to_string(),
+            group_name: "GROUP-1".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 30.0090027,
+                longitude: -85.9578735,
+                altitude: 132.67,
+                heading: 2.0034,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::AIR,
+                level_2: 1,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 28800,
+            mission_time_elapsed,
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::io;
+
+use log::info;
+
+use crate::{cursor_on_target::Event, registry::track_event::TrackEvent};
+
+use super::output_sink::OutputSink;
+
+/// Logs the lifecycle changes of the units instead of publishing them, for dry runs.
+#[derive(Default)]
+pub struct LogSink;
+
+impl OutputSink for LogSink {
+    fn name(&self) -> String {
+        "log".to_string()
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn publish_event(&self, track_event: &TrackEvent, event: &Event) {
+        let change = match track_event {
+            TrackEvent::New(_) => "New",
+            TrackEvent::Updated(_) => "Updated",
+            TrackEvent::Stale(_) => "Stale",
+            TrackEvent::Removed(_) => "Removed",
+        };
+
+        info!(
+            "{} {} ({}) at {:.5}, {:.5}, {:.0} m",
+            change,
+            track_event.unit().unit_name,
+            event.unit_type,
+            event.point.lat,
+            event.point.lon,
+            event.point.hae
+        );
+    }
+
+    fn shutdown(&mut self) {}
+}
@@ -0,0 +1,10 @@
+pub mod log_sink;
+pub mod output_sink;
+pub mod recorder_sink;
+pub mod sink_config;
+pub mod sink_set;
+pub mod tacview_file_sink;
+pub mod tacview_server_sink;
+pub mod tak_mesh_sink;
+pub mod tak_server_sink;
+pub mod web_socket_sink;
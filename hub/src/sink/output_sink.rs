@@ -0,0 +1,29 @@
+use std::io;
+
+use crate::{
+    common::dcs_unit::DcsUnit, cursor_on_target::Event, registry::track_event::TrackEvent,
+};
+
+/// A destination the units exported from DCS are published to, e.g. WebSocket clients or a TAK
+/// Server. Publishing is done from the UDP listener and must not block.
+pub trait OutputSink: Send + Sync {
+    /// Describes the sink in logs.
+    fn name(&self) -> String;
+
+    /// Starts the sink, e.g. binds its socket or starts connecting. Called once, from within the
+    /// Tokio runtime.
+    fn start(&mut self) -> io::Result<()>;
+
+    /// Publishes a unit as received from DCS. Most sinks only need its lifecycle changes.
+    fn publish_unit(&self, _unit: &DcsUnit) {}
+
+    /// Records a unit as received from DCS, before the coalitions and unit types of the
+    /// configuration are applied. Only recorders need every unit.
+    fn record_raw(&self, _unit: &DcsUnit) {}
+
+    /// Publishes a lifecycle change of a unit, along with the CoT event describing it.
+    fn publish_event(&self, track_event: &TrackEvent, event: &Event);
+
+    /// Stops the sink, releasing its sockets and files.
+    fn shutdown(&mut self);
+}
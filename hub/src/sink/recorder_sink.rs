@@ -0,0 +1,106 @@
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use chrono::Utc;
+use log::{error, warn};
+
+use crate::{
+    common::dcs_unit::DcsUnit,
+    cursor_on_target::Event,
+    recording::{
+        recorded_unit::RecordedUnit,
+        session_recorder::{SessionRecorder, RECORDING_FLUSH_INTERVAL},
+    },
+    registry::track_event::TrackEvent,
+};
+
+use super::{output_sink::OutputSink, sink_config::RecordingConfig};
+
+/// Records the units as received from DCS, with the time they were received. The session files
+/// are written by a dedicated thread, so the UDP listener never waits for the disk.
+pub struct RecorderSink {
+    config: RecordingConfig,
+    recorded_units: Option<Sender<RecordedUnit>>,
+    writer_thread: Option<JoinHandle<()>>,
+}
+
+impl RecorderSink {
+    pub fn new(config: RecordingConfig) -> RecorderSink {
+        RecorderSink {
+            config,
+            recorded_units: None,
+            writer_thread: None,
+        }
+    }
+}
+
+impl OutputSink for RecorderSink {
+    fn name(&self) -> String {
+        format!("recorder in '{}'", self.config.directory)
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        let recorder = SessionRecorder::new(&self.config.directory)?;
+        let (recorded_units, receiver) = mpsc::channel();
+        let writer_thread = thread::Builder::new()
+            .name("session-recorder".to_string())
+            .spawn(move || write_sessions(recorder, receiver))?;
+
+        self.recorded_units = Some(recorded_units);
+        self.writer_thread = Some(writer_thread);
+
+        Ok(())
+    }
+
+    fn record_raw(&self, unit: &DcsUnit) {
+        if let Some(recorded_units) = &self.recorded_units {
+            let _ = recorded_units.send(RecordedUnit {
+                received_at: Utc::now(),
+                unit: unit.clone(),
+            });
+        }
+    }
+
+    fn publish_event(&self, _track_event: &TrackEvent, _event: &Event) {}
+
+    fn shutdown(&mut self) {
+        // Lets the writer thread complete the sessions once it recorded the queued units
+        self.recorded_units = None;
+        if let Some(writer_thread) = self.writer_thread.take() {
+            let _ = writer_thread.join();
+        }
+    }
+}
+
+/// Records the units until the sink shuts down, flushing the session files on a timer.
+fn write_sessions(mut recorder: SessionRecorder, recorded_units: Receiver<RecordedUnit>) {
+    let mut flushed_at = Instant::now();
+
+    loop {
+        let until_flush = RECORDING_FLUSH_INTERVAL.saturating_sub(flushed_at.elapsed());
+        match recorded_units.recv_timeout(until_flush) {
+            Ok(recorded_unit) => {
+                if let Err(err) = recorder.record(&recorded_unit.unit, recorded_unit.received_at) {
+                    warn!("Failed to record {}: {}", recorded_unit.unit.unit_name, err);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if flushed_at.elapsed() >= RECORDING_FLUSH_INTERVAL {
+            if let Err(err) = recorder.flush(Instant::now()) {
+                warn!("Failed to flush the recording: {}", err);
+            }
+            flushed_at = Instant::now();
+        }
+    }
+
+    if let Err(err) = recorder.finish() {
+        error!("Failed to complete the recording: {}", err);
+    }
+}
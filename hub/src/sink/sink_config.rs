@@ -0,0 +1,173 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    hub::subscription_filter::SubscriptionFilter,
+    tacview::{tacview_file_config::TacviewFileConfig, tacview_server_config::TacviewServerConfig},
+    tak::{tak_mesh_config::TakMeshConfig, tak_server_config::TakServerConfig},
+};
+
+/// Default port WebSocket clients connect to
+pub const WEB_SOCKET_PORT: u16 = 9345;
+
+/// An output the units are published to, and the units it is restricted to.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SinkConfig {
+    /// Where and in which format the units are published
+    #[serde(flatten)]
+    pub output: OutputConfig,
+
+    /// The units published to this output, among the ones exported from DCS
+    #[serde(default)]
+    pub filter: SubscriptionFilter,
+}
+
+impl SinkConfig {
+    /// Publishes every unit to `output`.
+    pub fn new(output: OutputConfig) -> SinkConfig {
+        SinkConfig {
+            output,
+            filter: SubscriptionFilter::default(),
+        }
+    }
+}
+
+/// The supported outputs, tagged by `type`, e.g. `{"type":"tak_mesh","protocol":"protobuf"}`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputConfig {
+    /// CoT sent to WebSocket clients
+    WebSocket(WebSocketSinkConfig),
+
+    /// CoT streamed to a TAK Server
+    TakServer(TakServerConfig),
+
+    /// CoT sent to the multicast group of a TAK mesh network
+    TakMesh(TakMeshConfig),
+
+    /// Tacview ACMI files written for debriefs
+    TacviewFile(TacviewFileConfig),
+
+    /// Tacview clients connecting live
+    TacviewServer(TacviewServerConfig),
+}
+
+/// WebSocket clients receiving CoT.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct WebSocketSinkConfig {
+    /// Address clients connect to, e.g. `0.0.0.0:9345`, or `[::]:9345` to accept IPv6 as well
+    #[serde(default = "default_web_socket_address")]
+    pub address: SocketAddr,
+
+    /// Encoding of the messages sent to the clients
+    #[serde(default)]
+    pub format: WebSocketFormat,
+}
+
+impl Default for WebSocketSinkConfig {
+    fn default() -> Self {
+        WebSocketSinkConfig {
+            address: default_web_socket_address(),
+            format: WebSocketFormat::default(),
+        }
+    }
+}
+
+/// Encoding of the CoT sent to WebSocket clients, as text messages.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum WebSocketFormat {
+    /// CoT XML, understood by every TAK client
+    #[default]
+    Xml,
+
+    /// The same events as JSON objects, for web clients
+    Json,
+}
+
+fn default_web_socket_address() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::UNSPECIFIED, WEB_SOCKET_PORT))
+}
+
+/// Records every unit received from DCS to session files, for after-action reviews and replays.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RecordingConfig {
+    /// Whether the units are recorded
+    #[serde(default = "default_recording_enabled")]
+    pub enabled: bool,
+
+    /// Directory the session files are written to, one per mission
+    #[serde(default = "default_recording_directory")]
+    pub directory: String,
+}
+
+impl Default for RecordingConfig {
+    fn default() -> Self {
+        RecordingConfig {
+            enabled: default_recording_enabled(),
+            directory: default_recording_directory(),
+        }
+    }
+}
+
+fn default_recording_enabled() -> bool {
+    true
+}
+
+fn default_recording_directory() -> String {
+    "recordings".to_string()
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::{
+        tak::{tak_mesh_config::TakMeshConfig, tak_protocol::TakProtocol},
+        user_config::coalition_flag::CoalitionFlag,
+    };
+
+    use super::{OutputConfig, SinkConfig, WebSocketFormat, WebSocketSinkConfig};
+
+    #[test]
+    fn given_sinks_json_when_deserialized_then_outputs_and_filters_are_read() {
+        // Arrange
+        let json = r#"[{"type":"web_socket"},{"type":"tak_mesh","interface":"192.168.1.10","protocol":"protobuf","filter":{"coalition_flag":4}}]"#;
+
+        // Act
+        let result: Vec<SinkConfig> =
+            serde_json::from_str(json).expect("Failed to deserialize sinks");
+
+        // Assert
+        assert_eq!(
+            result[0],
+            SinkConfig::new(OutputConfig::WebSocket(WebSocketSinkConfig::default()))
+        );
+        assert_eq!(
+            result[1].output,
+            OutputConfig::TakMesh(TakMeshConfig {
+                interface: Some("192.168.1.10".parse().unwrap()),
+                protocol: TakProtocol::Protobuf,
+                ..Default::default()
+            })
+        );
+        assert_eq!(result[1].filter.coalition_flag, Some(CoalitionFlag::BLUFOR));
+    }
+
+    #[test]
+    fn given_web_socket_sink_with_format_when_deserialized_then_format_is_read() {
+        // Arrange
+        let json = r#"{"type":"web_socket","format":"json"}"#;
+
+        // Act
+        let result: SinkConfig = serde_json::from_str(json).expect("Failed to deserialize sink");
+
+        // Assert
+        assert_eq!(
+            result.output,
+            OutputConfig::WebSocket(WebSocketSinkConfig {
+                format: WebSocketFormat::Json,
+                ..Default::default()
+            })
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use std::{collections::HashSet, io, sync::Mutex};
+
+use log::{info, warn};
+
+use crate::{
+    common::dcs_unit::DcsUnit, cursor_on_target::Event,
+    hub::subscription_filter::SubscriptionFilter, registry::track_event::TrackEvent,
+};
+
+use super::{
+    output_sink::OutputSink,
+    sink_config::{OutputConfig, SinkConfig},
+    tacview_file_sink::TacviewFileSink,
+    tacview_server_sink::TacviewServerSink,
+    tak_mesh_sink::TakMeshSink,
+    tak_server_sink::TakServerSink,
+    web_socket_sink::WebSocketSink,
+};
+
+/// A sink and the units it is restricted to
+struct FilteredSink {
+    sink: Box<dyn OutputSink>,
+    filter: SubscriptionFilter,
+
+    /// The configured output the sink was instantiated from, if any
+    output: Option<OutputConfig>,
+
+    /// The units the sink was sent and not told to remove since
+    sent_units: Mutex<HashSet<String>>,
+}
+
+impl FilteredSink {
+    fn new(
+        sink: Box<dyn OutputSink>,
+        filter: SubscriptionFilter,
+        output: Option<OutputConfig>,
+    ) -> FilteredSink {
+        FilteredSink {
+            sink,
+            filter,
+            output,
+            sent_units: Mutex::default(),
+        }
+    }
+
+    /// Publishes a lifecycle change of a unit if the filter matches the unit, or the removal of
+    /// the unit if the sink was sent it before the unit or the filter changed.
+    fn publish_event(&self, track_event: &TrackEvent, event: &Event) {
+        let uid = &track_event.unit().unit_name;
+        let mut sent_units = self.sent_units.lock().unwrap();
+
+        if let TrackEvent::Removed(_) = track_event {
+            if sent_units.remove(uid) {
+                self.sink.publish_event(track_event, event);
+            }
+        } else if self.filter.matches_unit(track_event.unit()) {
+            sent_units.insert(uid.clone());
+            self.sink.publish_event(track_event, event);
+        } else if sent_units.remove(uid) {
+            let removal = TrackEvent::Removed(track_event.tracked_unit().clone());
+            self.sink.publish_event(&removal, &event.to_removal());
+        }
+    }
+}
+
+/// The sinks the units exported from DCS are published to, all at once.
+#[derive(Default)]
+pub struct SinkSet {
+    sinks: Vec<FilteredSink>,
+}
+
+impl SinkSet {
+    /// Instantiates the sinks described by `configs`, without starting them.
+    pub fn from_config(configs: &[SinkConfig]) -> SinkSet {
+        let mut sink_set = SinkSet::default();
+
+        for config in configs {
+            let sink: Box<dyn OutputSink> = match &config.output {
+                OutputConfig::WebSocket(config) => Box::new(WebSocketSink::new(config.clone())),
+                OutputConfig::TakServer(config) => Box::new(TakServerSink::new(config.clone())),
+                OutputConfig::TakMesh(config) => Box::new(TakMeshSink::new(config.clone())),
+                OutputConfig::TacviewFile(config) => Box::new(TacviewFileSink::new(config.clone())),
+                OutputConfig::TacviewServer(config) => {
+                    Box::new(TacviewServerSink::new(config.clone()))
+                }
+            };
+            sink_set.sinks.push(FilteredSink::new(
+                sink,
+                config.filter.clone(),
+                Some(config.output.clone()),
+            ));
+        }
+
+        sink_set
+    }
+
+    /// Adds a sink receiving the units matching `filter`.
+    pub fn add(&mut self, sink: Box<dyn OutputSink>, filter: SubscriptionFilter) {
+        self.sinks.push(FilteredSink::new(sink, filter, None));
+    }
+
+    /// Swaps in the filters of `configs`, the changed configuration of the sinks, matching each
+    /// sink to the config with the same output wherever it moved to. A sink whose output is no
+    /// longer configured keeps its filter, as its changed output only takes effect after a
+    /// restart.
+    pub fn update_filters(&mut self, configs: &[SinkConfig]) {
+        let mut unmatched_configs: Vec<&SinkConfig> = configs.iter().collect();
+
+        for filtered_sink in &mut self.sinks {
+            let Some(output) = &filtered_sink.output else {
+                continue;
+            };
+
+            match unmatched_configs
+                .iter()
+                .position(|config| &config.output == output)
+            {
+                Some(index) => {
+                    filtered_sink.filter = unmatched_configs.remove(index).filter.clone()
+                }
+                None => warn!(
+                    "Keeping the filter of {} until the hub is restarted, as its output changed",
+                    filtered_sink.sink.name()
+                ),
+            }
+        }
+    }
+
+    /// Starts every sink, failing on the first one that cannot be started.
+    pub fn start(&mut self) -> io::Result<()> {
+        for filtered_sink in &mut self.sinks {
+            filtered_sink.sink.start().map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!("Failed to start {}: {}", filtered_sink.sink.name(), err),
+                )
+            })?;
+            info!("Started {}", filtered_sink.sink.name());
+        }
+
+        Ok(())
+    }
+
+    /// Hands a unit as received from DCS to the sinks recording every unit, whatever their filter.
+    pub fn record_raw(&self, unit: &DcsUnit) {
+        for filtered_sink in &self.sinks {
+            filtered_sink.sink.record_raw(unit);
+        }
+    }
+
+    /// Publishes a unit as received from DCS to the sinks whose filter matches it.
+    pub fn publish_unit(&self, unit: &DcsUnit) {
+        for filtered_sink in self.sinks_matching(unit) {
+            filtered_sink.sink.publish_unit(unit);
+        }
+    }
+
+    /// Publishes a lifecycle change of a unit to the sinks whose filter matches the unit. The
+    /// sinks that were sent the unit but whose filter no longer matches it are sent its removal.
+    pub fn publish_event(&self, track_event: &TrackEvent, event: &Event) {
+        for filtered_sink in &self.sinks {
+            filtered_sink.publish_event(track_event, event);
+        }
+    }
+
+    /// Stops every sink.
+    pub fn shutdown(&mut self) {
+        for filtered_sink in &mut self.sinks {
+            filtered_sink.sink.shutdown();
+            info!("Stopped {}", filtered_sink.sink.name());
+        }
+    }
+
+    fn sinks_matching<'a>(&'a self, unit: &'a DcsUnit) -> impl Iterator<Item = &'a FilteredSink> {
+        self.sinks
+            .iter()
+            .filter(move |filtered_sink| filtered_sink.filter.matches_unit(unit))
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::{
+        common::{
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        cursor_on_target::{xml_serializer::XmlSerializer, Event},
+        hub::subscription_filter::{Area, SubscriptionFilter},
+        registry::track_event::{TrackEvent, TrackedUnit},
+        sink::sink_config::{OutputConfig, SinkConfig, WebSocketSinkConfig},
+        tak::tak_mesh_config::TakMeshConfig,
+        user_config::coalition_flag::CoalitionFlag,
+    };
+
+    use super::{OutputSink, SinkSet};
+
+    /// Records the calls made to it
+    struct RecordingSink {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl OutputSink for RecordingSink {
+        fn name(&self) -> String {
+            "recording sink".to_string()
+        }
+
+        fn start(&mut self) -> io::Result<()> {
+            self.calls.lock().unwrap().push("start".to_string());
+            Ok(())
+        }
+
+        fn publish_unit(&self, unit: &DcsUnit) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("unit {}", unit.unit_name));
+        }
+
+        fn record_raw(&self, unit: &DcsUnit) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("raw {}", unit.unit_name));
+        }
+
+        fn publish_event(&self, _track_event: &TrackEvent, event: &Event) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("event {}", event.uid));
+        }
+
+        fn shutdown(&mut self) {
+            self.calls.lock().unwrap().push("shutdown".to_string());
+        }
+    }
+
+    #[test]
+    fn given_changed_filters_when_updated_then_sinks_with_same_output_use_them() {
+        // Arrange
+        let web_socket = SinkConfig::new(OutputConfig::WebSocket(WebSocketSinkConfig::default()));
+        let tak_mesh = SinkConfig::new(OutputConfig::TakMesh(TakMeshConfig::default()));
+        let mut sink_set = SinkSet::from_config(&[web_socket.clone(), tak_mesh.clone()]);
+        let redfor = SubscriptionFilter {
+            coalition_flag: Some(CoalitionFlag::REDFOR),
+            ..Default::default()
+        };
+        let changed = vec![
+            SinkConfig {
+                filter: redfor.clone(),
+                ..web_socket
+            },
+            SinkConfig {
+                output: OutputConfig::TakMesh(TakMeshConfig {
+                    ttl: Some(2),
+                    ..Default::default()
+                }),
+                filter: redfor.clone(),
+            },
+        ];
+
+        // Act
+        sink_set.update_filters(&changed);
+
+        // Assert
+        assert_eq!(sink_set.sinks[0].filter, redfor);
+        assert_eq!(sink_set.sinks[1].filter, SubscriptionFilter::default());
+    }
+
+    #[test]
+    fn given_reordered_sinks_when_filters_updated_then_each_sink_uses_filter_of_its_output() {
+        // Arrange
+        let web_socket = SinkConfig::new(OutputConfig::WebSocket(WebSocketSinkConfig::default()));
+        let tak_mesh = SinkConfig::new(OutputConfig::TakMesh(TakMeshConfig::default()));
+        let mut sink_set = SinkSet::from_config(&[web_socket.clone(), tak_mesh.clone()]);
+        let redfor = SubscriptionFilter {
+            coalition_flag: Some(CoalitionFlag::REDFOR),
+            ..Default::default()
+        };
+        let changed = vec![
+            SinkConfig {
+                filter: redfor.clone(),
+                ..tak_mesh
+            },
+            web_socket,
+        ];
+
+        // Act
+        sink_set.update_filters(&changed);
+
+        // Assert
+        assert_eq!(sink_set.sinks[0].filter, SubscriptionFilter::default());
+        assert_eq!(sink_set.sinks[1].filter, redfor);
+    }
+
+    #[test]
+    fn given_sinks_with_filters_when_published_then_only_matching_sinks_receive_units() {
+        // Arrange
+        let blufor_calls = Arc::new(Mutex::new(Vec::new()));
+        let redfor_calls = Arc::new(Mutex::new(Vec::new()));
+        let mut sink_set = SinkSet::default();
+        sink_set.add(
+            Box::new(RecordingSink {
+                calls: blufor_calls.clone(),
+            }),
+            SubscriptionFilter {
+                coalition_flag: Some(CoalitionFlag::BLUFOR),
+                ..Default::default()
+            },
+        );
+        sink_set.add(
+            Box::new(RecordingSink {
+                calls: redfor_calls.clone(),
+            }),
+            SubscriptionFilter {
+                coalition_flag: Some(CoalitionFlag::REDFOR),
+                ..Default::default()
+            },
+        );
+        let unit = build_dcs_unit();
+        let track_event = TrackEvent::New(TrackedUnit {
+            unit: unit.clone(),
+            kinematics: None,
+        });
+        let event = XmlSerializer::default()
+            .build_track_event(&track_event)
+            .unwrap();
+
+        // Act
+        sink_set.start().expect("Failed to start sinks");
+        sink_set.record_raw(&unit);
+        sink_set.publish_unit(&unit);
+        sink_set.publish_event(&track_event, &event);
+        sink_set.shutdown();
+
+        // Assert
+        assert_eq!(
+            *blufor_calls.lock().unwrap(),
+            vec![
+                "start",
+                "raw Hawg 1-1",
+                "unit Hawg 1-1",
+                "event Hawg 1-1",
+                "shutdown"
+            ]
+        );
+        assert_eq!(
+            *redfor_calls.lock().unwrap(),
+            vec!["start", "raw Hawg 1-1", "shutdown"]
+        );
+    }
+
+    #[test]
+    fn given_unit_moving_out_of_sink_area_when_published_then_sink_receives_removal_once() {
+        // Arrange
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut sink_set = SinkSet::default();
+        sink_set.add(
+            Box::new(RecordingSink {
+                calls: calls.clone(),
+            }),
+            SubscriptionFilter {
+                area: Some(Area::Radius {
+                    latitude: 42.0,
+                    longitude: 42.0,
+                    meters: 5_000.0,
+                }),
+                ..Default::default()
+            },
+        );
+        let inside = build_dcs_unit();
+        let mut outside = build_dcs_unit();
+        outside.position.latitude = 43.0;
+        let serializer = XmlSerializer::default();
+        let track_events = [
+            TrackEvent::New(TrackedUnit {
+                unit: inside,
+                kinematics: None,
+            }),
+            TrackEvent::Updated(TrackedUnit {
+                unit: outside.clone(),
+                kinematics: None,
+            }),
+            TrackEvent::Updated(TrackedUnit {
+                unit: outside.clone(),
+                kinematics: None,
+            }),
+            TrackEvent::Removed(TrackedUnit {
+                unit: outside,
+                kinematics: None,
+            }),
+        ];
+
+        // Act
+        for track_event in &track_events {
+            let event = serializer.build_track_event(track_event).unwrap();
+            sink_set.publish_event(track_event, &event);
+        }
+
+        // Assert
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["event Hawg 1-1", "event Hawg 1-1-delete"]
+        );
+    }
+
+    fn build_dcs_unit() -> DcsUnit {
+        DcsUnit {
+            unit_name: "Hawg 1-1".to_string(),
+            group_name: "Armor Platoon".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 42.0,
+                longitude: 42.0,
+                altitude: 100.0,
+                heading: 0.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 17,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
+            },
+            mission_date: "2005-04-05".to_string(),
+            mission_start_time: 42_000,
+            mission_time_elapsed: 218,
+        }
+    }
+}
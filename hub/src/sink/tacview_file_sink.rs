@@ -0,0 +1,121 @@
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+
+use log::{error, warn};
+
+use crate::{
+    common::dcs_unit::DcsUnit,
+    cursor_on_target::Event,
+    registry::track_event::TrackEvent,
+    tacview::{
+        acmi_file_writer::{AcmiFileWriter, ACMI_FLUSH_INTERVAL},
+        tacview_file_config::TacviewFileConfig,
+    },
+};
+
+use super::output_sink::OutputSink;
+
+/// A change of the units shown by Tacview, queued for the writer thread
+enum AcmiUpdate {
+    Unit(DcsUnit),
+    Removal(DcsUnit),
+}
+
+/// Writes the units tracked by the registry to Tacview ACMI files, for debriefs. The files are
+/// written by a dedicated thread, so the UDP listener never waits for the disk.
+pub struct TacviewFileSink {
+    config: TacviewFileConfig,
+    updates: Option<Sender<AcmiUpdate>>,
+    writer_thread: Option<JoinHandle<()>>,
+}
+
+impl TacviewFileSink {
+    pub fn new(config: TacviewFileConfig) -> TacviewFileSink {
+        TacviewFileSink {
+            config,
+            updates: None,
+            writer_thread: None,
+        }
+    }
+}
+
+impl OutputSink for TacviewFileSink {
+    fn name(&self) -> String {
+        format!("Tacview files in '{}'", self.config.directory)
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        let writer = AcmiFileWriter::new(&self.config.directory)?;
+        let (updates, receiver) = mpsc::channel();
+        let writer_thread = thread::Builder::new()
+            .name("tacview-file".to_string())
+            .spawn(move || write_acmi_files(writer, receiver))?;
+
+        self.updates = Some(updates);
+        self.writer_thread = Some(writer_thread);
+
+        Ok(())
+    }
+
+    fn publish_event(&self, track_event: &TrackEvent, _event: &Event) {
+        let Some(updates) = &self.updates else {
+            return;
+        };
+
+        let update = match track_event {
+            TrackEvent::New(tracked_unit) | TrackEvent::Updated(tracked_unit) => {
+                AcmiUpdate::Unit(tracked_unit.unit.clone())
+            }
+            TrackEvent::Removed(tracked_unit) => AcmiUpdate::Removal(tracked_unit.unit.clone()),
+            // Tacview keeps showing the last-known position
+            TrackEvent::Stale(_) => return,
+        };
+        let _ = updates.send(update);
+    }
+
+    fn shutdown(&mut self) {
+        // Lets the writer thread complete the file once it wrote the queued updates
+        self.updates = None;
+        if let Some(writer_thread) = self.writer_thread.take() {
+            let _ = writer_thread.join();
+        }
+    }
+}
+
+/// Writes the updates until the sink shuts down, flushing the ACMI file on a timer.
+fn write_acmi_files(mut writer: AcmiFileWriter, updates: Receiver<AcmiUpdate>) {
+    let mut flushed_at = Instant::now();
+
+    loop {
+        let until_flush = ACMI_FLUSH_INTERVAL.saturating_sub(flushed_at.elapsed());
+        match updates.recv_timeout(until_flush) {
+            Ok(AcmiUpdate::Unit(unit)) => {
+                if let Err(err) = writer.write_unit(&unit) {
+                    warn!("Failed to write {} to Tacview: {}", unit.unit_name, err);
+                }
+            }
+            Ok(AcmiUpdate::Removal(unit)) => {
+                if let Err(err) = writer.remove_unit(&unit) {
+                    warn!("Failed to remove {} from Tacview: {}", unit.unit_name, err);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        if flushed_at.elapsed() >= ACMI_FLUSH_INTERVAL {
+            if let Err(err) = writer.flush() {
+                warn!("Failed to flush the Tacview file: {}", err);
+            }
+            flushed_at = Instant::now();
+        }
+    }
+
+    if let Err(err) = writer.finish() {
+        error!("Failed to complete the Tacview file: {}", err);
+    }
+}
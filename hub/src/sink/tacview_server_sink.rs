@@ -0,0 +1,70 @@
+use std::{io, sync::Arc};
+
+use tokio::task::JoinHandle;
+
+use crate::{
+    cursor_on_target::Event,
+    registry::track_event::TrackEvent,
+    tacview::{
+        tacview_server_config::TacviewServerConfig,
+        telemetry_server::{self, TelemetryServer},
+    },
+};
+
+use super::output_sink::OutputSink;
+
+/// Streams the units JTAC clients see to Tacview clients connecting live, e.g. for an
+/// instructor.
+pub struct TacviewServerSink {
+    config: TacviewServerConfig,
+    server: Option<Arc<TelemetryServer>>,
+    server_task: Option<JoinHandle<()>>,
+}
+
+impl TacviewServerSink {
+    pub fn new(config: TacviewServerConfig) -> TacviewServerSink {
+        TacviewServerSink {
+            config,
+            server: None,
+            server_task: None,
+        }
+    }
+}
+
+impl OutputSink for TacviewServerSink {
+    fn name(&self) -> String {
+        format!("Tacview telemetry server on {}", self.config.address)
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        let listener = telemetry_server::bind(self.config.address)?;
+        let server = Arc::new(TelemetryServer::new(self.config.host_name.clone()));
+        self.server_task = Some(tokio::spawn(server.clone().serve(listener)));
+        self.server = Some(server);
+
+        Ok(())
+    }
+
+    fn publish_event(&self, track_event: &TrackEvent, _event: &Event) {
+        let server = match &self.server {
+            Some(server) => server,
+            None => return,
+        };
+
+        match track_event {
+            TrackEvent::New(tracked_unit) | TrackEvent::Updated(tracked_unit) => {
+                server.publish_unit(&tracked_unit.unit)
+            }
+            TrackEvent::Removed(tracked_unit) => server.publish_removal(&tracked_unit.unit),
+            // Tacview keeps showing the last-known position
+            TrackEvent::Stale(_) => {}
+        }
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(server_task) = self.server_task.take() {
+            server_task.abort();
+        }
+        self.server = None;
+    }
+}
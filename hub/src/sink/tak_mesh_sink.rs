@@ -0,0 +1,45 @@
+use std::io;
+
+use crate::{
+    cursor_on_target::Event,
+    registry::track_event::TrackEvent,
+    tak::{tak_mesh::TakMesh, tak_mesh_config::TakMeshConfig},
+};
+
+use super::output_sink::OutputSink;
+
+/// Publishes CoT to the multicast group of a TAK mesh network.
+pub struct TakMeshSink {
+    config: TakMeshConfig,
+    tak_mesh: Option<TakMesh>,
+}
+
+impl TakMeshSink {
+    pub fn new(config: TakMeshConfig) -> TakMeshSink {
+        TakMeshSink {
+            config,
+            tak_mesh: None,
+        }
+    }
+}
+
+impl OutputSink for TakMeshSink {
+    fn name(&self) -> String {
+        format!("TAK mesh {}", self.config.address())
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        self.tak_mesh = Some(TakMesh::start(self.config.clone())?);
+        Ok(())
+    }
+
+    fn publish_event(&self, _track_event: &TrackEvent, event: &Event) {
+        if let Some(tak_mesh) = &self.tak_mesh {
+            tak_mesh.publish(event);
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.tak_mesh = None;
+    }
+}
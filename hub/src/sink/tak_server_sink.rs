@@ -0,0 +1,46 @@
+use std::io;
+
+use crate::{
+    cursor_on_target::Event,
+    registry::track_event::TrackEvent,
+    tak::{tak_server_config::TakServerConfig, tak_stream::TakStream},
+};
+
+use super::output_sink::OutputSink;
+
+/// Streams CoT to a TAK Server.
+pub struct TakServerSink {
+    config: TakServerConfig,
+    tak_stream: Option<TakStream>,
+}
+
+impl TakServerSink {
+    pub fn new(config: TakServerConfig) -> TakServerSink {
+        TakServerSink {
+            config,
+            tak_stream: None,
+        }
+    }
+}
+
+impl OutputSink for TakServerSink {
+    fn name(&self) -> String {
+        format!("TAK Server {}:{}", self.config.host, self.config.port())
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        self.tak_stream = Some(TakStream::start(self.config.clone())?);
+        Ok(())
+    }
+
+    fn publish_event(&self, _track_event: &TrackEvent, event: &Event) {
+        if let Some(tak_stream) = &self.tak_stream {
+            tak_stream.publish(event.clone());
+        }
+    }
+
+    fn shutdown(&mut self) {
+        // The queued events are written for a little while longer, without waiting for them
+        self.tak_stream = None;
+    }
+}
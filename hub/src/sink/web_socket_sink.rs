@@ -0,0 +1,91 @@
+use std::{io, sync::Arc};
+
+use log::error;
+use tokio::task::JoinHandle;
+
+use crate::{
+    cursor_on_target::{xml_serializer::ToXml, Event},
+    hub::web_socket_hub::WebSocketHub,
+    registry::track_event::TrackEvent,
+};
+
+use super::{
+    output_sink::OutputSink,
+    sink_config::{WebSocketFormat, WebSocketSinkConfig},
+};
+
+/// Publishes CoT to the clients of a `WebSocketHub`, in the configured format.
+pub struct WebSocketSink {
+    config: WebSocketSinkConfig,
+    hub: Option<Arc<WebSocketHub>>,
+    server_task: Option<JoinHandle<()>>,
+}
+
+impl WebSocketSink {
+    pub fn new(config: WebSocketSinkConfig) -> WebSocketSink {
+        WebSocketSink {
+            config,
+            hub: None,
+            server_task: None,
+        }
+    }
+
+    /// Encodes `event` in the configured format, logging the events that cannot be.
+    fn encode(&self, event: &Event) -> Option<String> {
+        let message = match self.config.format {
+            WebSocketFormat::Xml => event.to_xml().map_err(|err| err.to_string()),
+            WebSocketFormat::Json => serde_json::to_string(event).map_err(|err| err.to_string()),
+        };
+
+        message
+            .map_err(|err| error!("Failed to serialize DCS unit: {}", err))
+            .ok()
+    }
+}
+
+impl OutputSink for WebSocketSink {
+    fn name(&self) -> String {
+        format!("WebSocket hub on {}", self.config.address)
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        let hub = Arc::new(WebSocketHub::new(self.config.address));
+        let server = hub.clone();
+        self.server_task = Some(tokio::spawn(async move {
+            if let Err(err) = server.start().await {
+                error!("WebSocket hub error: {}", err);
+            }
+        }));
+        self.hub = Some(hub);
+
+        Ok(())
+    }
+
+    fn publish_event(&self, track_event: &TrackEvent, event: &Event) {
+        let hub = match &self.hub {
+            Some(hub) => hub,
+            None => return,
+        };
+        let Some(message) = self.encode(event) else {
+            return;
+        };
+
+        match track_event {
+            TrackEvent::Removed(tracked_unit) => {
+                hub.broadcast_unit_removal(message, tracked_unit.unit.clone())
+            }
+            _ => {
+                if let Some(removal) = self.encode(&event.to_removal()) {
+                    hub.broadcast_unit_message(message, removal, track_event.unit().clone())
+                }
+            }
+        }
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(server_task) = self.server_task.take() {
+            server_task.abort();
+        }
+        self.hub = None;
+    }
+}
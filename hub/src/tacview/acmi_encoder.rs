@@ -0,0 +1,273 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    common::{
+        dcs_unit::{Coalition, DcsUnit, UnitType},
+        unit_type::Level1UnitType,
+    },
+    recording::recorded_unit::MissionKey,
+};
+
+/// The version of the ACMI format written
+pub const ACMI_FILE_VERSION: &str = "2.2";
+
+/// DCS level-2 type of helicopters, among air units
+const HELICOPTER_LEVEL_2: u8 = 2;
+
+/// A unit of the stream, as last encoded
+struct AcmiObject {
+    object_id: u64,
+
+    /// The position, e.g. `T=41.6200000|41.6000000|7500.0|0|0|90.0`
+    transform: String,
+
+    /// The properties that do not change, e.g. `Type=Air+FixedWing,Name=F-16C_50`
+    properties: String,
+}
+
+/// Encodes the units received from DCS as the lines of a Tacview ACMI 2.x text stream. Units
+/// get an object ID the first time they are encoded, along with the properties that do not
+/// change; later lines only carry their position.
+#[derive(Default)]
+pub struct AcmiEncoder {
+    objects: HashMap<String, AcmiObject>,
+    next_object_id: u64,
+    frame_time: Option<i32>,
+}
+
+impl AcmiEncoder {
+    /// The lines starting a stream of the mission, its reference time being the start of the
+    /// mission.
+    pub fn header(mission_key: &MissionKey) -> String {
+        let start = mission_key.mission_start_time.max(0);
+
+        format!(
+            "FileType=text/acmi/tacview\n\
+             FileVersion={}\n\
+             0,ReferenceTime={}T{:02}:{:02}:{:02}Z\n\
+             0,DataSource=DCS World\n\
+             0,Recorder=DCS JTAC Tools\n",
+            ACMI_FILE_VERSION,
+            mission_key.mission_date,
+            start / 3600,
+            start / 60 % 60,
+            start % 60
+        )
+    }
+
+    /// The lines placing `unit` at its position, preceded by a time frame if the mission time
+    /// moved on.
+    pub fn encode_unit(&mut self, unit: &DcsUnit) -> String {
+        let mut lines = self.time_frame(unit.mission_time_elapsed);
+        let position = &unit.position;
+        let transform = format!(
+            "T={:.7}|{:.7}|{:.1}|0|0|{:.1}",
+            position.longitude,
+            position.latitude,
+            position.altitude,
+            position.heading.to_degrees()
+        );
+
+        match self.objects.get_mut(&unit.unit_name) {
+            Some(object) => {
+                let _ = writeln!(lines, "{:x},{}", object.object_id, transform);
+                object.transform = transform;
+            }
+            None => {
+                self.next_object_id += 1;
+                let object = AcmiObject {
+                    object_id: self.next_object_id,
+                    transform,
+                    properties: properties(unit),
+                };
+                let _ = writeln!(
+                    lines,
+                    "{:x},{},{}",
+                    object.object_id, object.transform, object.properties
+                );
+                self.objects.insert(unit.unit_name.clone(), object);
+            }
+        }
+
+        lines
+    }
+
+    /// The line removing `unit` from the stream, if it was encoded before. The unit is removed
+    /// in the current time frame, its last report being older.
+    pub fn encode_removal(&mut self, unit: &DcsUnit) -> Option<String> {
+        let object = self.objects.remove(&unit.unit_name)?;
+
+        Some(format!("-{:x}\n", object.object_id))
+    }
+
+    /// The lines placing every unit of the stream where it was last encoded, for a client
+    /// joining the stream.
+    pub fn snapshot(&self) -> String {
+        let mut objects: Vec<_> = self.objects.values().collect();
+        objects.sort_by_key(|object| object.object_id);
+
+        let mut lines = match self.frame_time {
+            Some(frame_time) => format!("#{}\n", frame_time),
+            None => String::new(),
+        };
+        for object in objects {
+            let _ = writeln!(
+                lines,
+                "{:x},{},{}",
+                object.object_id, object.transform, object.properties
+            );
+        }
+
+        lines
+    }
+
+    fn time_frame(&mut self, mission_time_elapsed: i32) -> String {
+        if self.frame_time == Some(mission_time_elapsed) {
+            return String::new();
+        }
+
+        self.frame_time = Some(mission_time_elapsed);
+        format!("#{}\n", mission_time_elapsed)
+    }
+}
+
+/// The properties of a unit that do not change.
+fn properties(unit: &DcsUnit) -> String {
+    let (color, coalition) = coalition_properties(unit.coalition);
+    let name = match unit.unit_type.type_name.is_empty() {
+        true => &unit.unit_name,
+        false => &unit.unit_type.type_name,
+    };
+
+    format!(
+        "Type={},Name={},CallSign={},Group={},Color={},Coalition={}",
+        type_tags(&unit.unit_type),
+        escape(name),
+        escape(&unit.unit_name),
+        escape(&unit.group_name),
+        color,
+        coalition
+    )
+}
+
+/// The Tacview tags of a unit type, e.g. `Air+FixedWing`.
+fn type_tags(unit_type: &UnitType) -> &'static str {
+    match unit_type.level_1 {
+        Level1UnitType::AIR if unit_type.level_2 == HELICOPTER_LEVEL_2 => "Air+Rotorcraft",
+        Level1UnitType::AIR => "Air+FixedWing",
+        Level1UnitType::GROUND => "Ground+Vehicle",
+        Level1UnitType::SEA => "Sea+Watercraft",
+    }
+}
+
+/// The Tacview color and coalition name of a DCS coalition.
+fn coalition_properties(coalition: Coalition) -> (&'static str, &'static str) {
+    match coalition {
+        Coalition::NEUTRAL => ("Grey", "Neutrals"),
+        Coalition::REDFOR => ("Red", "Enemies"),
+        Coalition::BLUFOR => ("Blue", "Allies"),
+    }
+}
+
+/// Escapes the characters ending a property value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('\n', "\\\n")
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::{
+        common::{
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        recording::recorded_unit::MissionKey,
+    };
+
+    use super::AcmiEncoder;
+
+    #[test]
+    fn given_mission_when_header_then_reference_time_is_mission_start() {
+        // Arrange
+        let mission_key = MissionKey {
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 8 * 3600 + 30 * 60 + 5,
+        };
+
+        // Act
+        let result = AcmiEncoder::header(&mission_key);
+
+        // Assert
+        assert!(result.starts_with("FileType=text/acmi/tacview\nFileVersion=2.2\n"));
+        assert!(result.contains("\n0,ReferenceTime=2024-03-08T08:30:05Z\n"));
+    }
+
+    #[test]
+    fn given_units_when_encoded_then_properties_are_written_once_and_removal_frees_the_object() {
+        // Arrange
+        let mut encoder = AcmiEncoder::default();
+        let first = build_dcs_unit("Viper, 1-1", 60, 0.0);
+        let moved = build_dcs_unit("Viper, 1-1", 61, 1.0);
+
+        // Act
+        let created = encoder.encode_unit(&first);
+        let updated = encoder.encode_unit(&moved);
+        let removed = encoder.encode_removal(&moved);
+        let removed_again = encoder.encode_removal(&moved);
+
+        // Assert
+        assert_eq!(
+            created,
+            "#60\n1,T=41.6200000|41.6000000|7500.0|0|0|0.0,Type=Air+FixedWing,Name=F-16C_50,CallSign=Viper\\, 1-1,Group=CAP,Color=Blue,Coalition=Allies\n"
+        );
+        assert_eq!(updated, "#61\n1,T=41.6200000|41.6000000|7500.0|0|0|90.0\n");
+        assert_eq!(removed.as_deref(), Some("-1\n"));
+        assert_eq!(removed_again, None);
+    }
+
+    #[test]
+    fn given_encoded_units_when_snapshot_then_last_positions_are_written_with_properties() {
+        // Arrange
+        let mut encoder = AcmiEncoder::default();
+        encoder.encode_unit(&build_dcs_unit("Viper 1-1", 60, 0.0));
+        encoder.encode_unit(&build_dcs_unit("Viper 1-2", 60, 0.0));
+        encoder.encode_unit(&build_dcs_unit("Viper 1-1", 61, 1.0));
+        encoder.encode_removal(&build_dcs_unit("Viper 1-2", 61, 0.0));
+
+        // Act
+        let result = encoder.snapshot();
+
+        // Assert
+        assert_eq!(
+            result,
+            "#61\n1,T=41.6200000|41.6000000|7500.0|0|0|90.0,Type=Air+FixedWing,Name=F-16C_50,CallSign=Viper 1-1,Group=CAP,Color=Blue,Coalition=Allies\n"
+        );
+    }
+
+    fn build_dcs_unit(unit_name: &str, mission_time_elapsed: i32, heading: f64) -> DcsUnit {
+        DcsUnit {
+            unit_name: unit_name.to_string(),
+            group_name: "CAP".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 41.6,
+                longitude: 41.62,
+                altitude: 7500.0,
+                heading: heading * std::f64::consts::FRAC_PI_2,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::AIR,
+                level_2: 1,
+                level_3: 1,
+                level_4: 0,
+                type_name: "F-16C_50".to_string(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 28800,
+            mission_time_elapsed,
+        }
+    }
+}
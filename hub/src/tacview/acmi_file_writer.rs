@@ -0,0 +1,187 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use log::info;
+
+use crate::{common::dcs_unit::DcsUnit, recording::recorded_unit::MissionKey};
+
+use super::acmi_encoder::AcmiEncoder;
+
+/// How often the ACMI file is flushed, bounding what is lost if the hub is killed
+pub const ACMI_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The ACMI file being written to
+struct AcmiFile {
+    mission_key: MissionKey,
+    path: PathBuf,
+    writer: BufWriter<File>,
+    encoder: AcmiEncoder,
+}
+
+/// Writes the units received from DCS to a Tacview ACMI text file per mission, starting a new
+/// file whenever the mission changes. The lines are buffered until flushed.
+pub struct AcmiFileWriter {
+    directory: PathBuf,
+    file: Option<AcmiFile>,
+}
+
+impl AcmiFileWriter {
+    /// Writes the ACMI files to `directory`, which is created if needed.
+    pub fn new(directory: impl AsRef<Path>) -> io::Result<AcmiFileWriter> {
+        fs::create_dir_all(&directory)?;
+
+        Ok(AcmiFileWriter {
+            directory: directory.as_ref().to_path_buf(),
+            file: None,
+        })
+    }
+
+    /// Writes the position of a unit.
+    pub fn write_unit(&mut self, unit: &DcsUnit) -> io::Result<()> {
+        let mission_key = MissionKey::of(unit);
+        let file = match self.file.take() {
+            Some(file) if file.mission_key == mission_key => file,
+            previous => {
+                if let Some(mut previous) = previous {
+                    previous.writer.flush()?;
+                }
+                self.start_file(mission_key)?
+            }
+        };
+        let file = self.file.insert(file);
+
+        let lines = file.encoder.encode_unit(unit);
+        file.writer.write_all(lines.as_bytes())
+    }
+
+    /// Removes a unit that stopped reporting from the file of its mission.
+    pub fn remove_unit(&mut self, unit: &DcsUnit) -> io::Result<()> {
+        let file = match self.file.as_mut() {
+            Some(file) if file.mission_key == MissionKey::of(unit) => file,
+            _ => return Ok(()),
+        };
+
+        match file.encoder.encode_removal(unit) {
+            Some(lines) => file.writer.write_all(lines.as_bytes()),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the buffered lines to the ACMI file being written to.
+    pub fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// The ACMI file being written to, if any.
+    pub fn file_path(&self) -> Option<&Path> {
+        self.file.as_ref().map(|file| file.path.as_path())
+    }
+
+    /// Flushes and closes the ACMI file being written to.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(mut file) => file.writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn start_file(&self, mission_key: MissionKey) -> io::Result<AcmiFile> {
+        let path = self
+            .directory
+            .join(mission_key.file_name(Utc::now(), "txt.acmi"));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writer.write_all(AcmiEncoder::header(&mission_key).as_bytes())?;
+        info!("Writing Tacview file '{}'", path.display());
+
+        Ok(AcmiFile {
+            mission_key,
+            path,
+            writer,
+            encoder: AcmiEncoder::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::fs;
+
+    use crate::common::{
+        dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+        unit_type::Level1UnitType,
+    };
+
+    use super::AcmiFileWriter;
+
+    #[test]
+    fn test_write_acmi_file_per_mission() {
+        let directory = std::env::temp_dir().join(format!("hub-tacview-{}", std::process::id()));
+        let first_mission = [
+            build_dcs_unit("UNIT-1", 28800, 0),
+            build_dcs_unit("UNIT-1", 28800, 1),
+        ];
+        let second_mission = build_dcs_unit("UNIT-1", 36000, 0);
+
+        let mut writer = AcmiFileWriter::new(&directory).expect("Failed to create writer");
+        for unit in &first_mission {
+            writer.write_unit(unit).expect("Failed to write unit");
+        }
+        // Removed once it stopped reporting, in the time frame reached meanwhile
+        writer
+            .remove_unit(&first_mission[0])
+            .expect("Failed to remove unit");
+        let first_path = writer.file_path().unwrap().to_path_buf();
+        writer
+            .write_unit(&second_mission)
+            .expect("Failed to write unit");
+        let second_path = writer.file_path().unwrap().to_path_buf();
+        writer.finish().expect("Failed to finish writing");
+
+        let first_file = fs::read_to_string(&first_path).unwrap();
+        let second_file = fs::read_to_string(&second_path).unwrap();
+        assert_ne!(first_path, second_path);
+        assert!(first_path.to_str().unwrap().ends_with(".txt.acmi"));
+        assert!(first_file.contains("\n0,ReferenceTime=2024-03-08T08:00:00Z\n"));
+        assert!(first_file.contains("\n#0\n1,T=-85.9578735|30.0090027|132.7|0|0|0.0,Type="));
+        assert!(first_file.ends_with("#1\n1,T=-85.9578735|30.0090027|132.7|0|0|0.0\n-1\n"));
+        assert!(second_file.contains("\n0,ReferenceTime=2024-03-08T10:00:00Z\n"));
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    fn build_dcs_unit(
+        unit_name: &str,
+        mission_start_time: i32,
+        mission_time_elapsed: i32,
+    ) -> DcsUnit {
+        DcsUnit {
+            unit_name: unit_name.to_string(),
+            group_name: "GROUP-1".to_string(),
+            coalition: Coalition::REDFOR,
+            position: Position3D {
+                latitude: 30.0090027,
+                longitude: -85.9578735,
+                altitude: 132.67,
+                heading: 0.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 17,
+                level_3: 0,
+                level_4: 0,
+                type_name: "BTR-80".to_string(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time,
+            mission_time_elapsed,
+        }
+    }
+}
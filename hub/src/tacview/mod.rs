@@ -0,0 +1,5 @@
+pub mod acmi_encoder;
+pub mod acmi_file_writer;
+pub mod tacview_file_config;
+pub mod tacview_server_config;
+pub mod telemetry_server;
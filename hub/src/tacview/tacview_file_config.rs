@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// Writing the units received from DCS to Tacview ACMI files, one per mission.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TacviewFileConfig {
+    /// Directory the ACMI files are written to
+    #[serde(default = "default_tacview_directory")]
+    pub directory: String,
+}
+
+impl Default for TacviewFileConfig {
+    fn default() -> Self {
+        TacviewFileConfig {
+            directory: default_tacview_directory(),
+        }
+    }
+}
+
+fn default_tacview_directory() -> String {
+    "tacview".to_string()
+}
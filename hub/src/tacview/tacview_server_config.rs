@@ -0,0 +1,36 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+/// Default port Tacview connects to for real-time telemetry
+pub const TACVIEW_TELEMETRY_PORT: u16 = 42674;
+
+/// Streaming the units to Tacview clients connecting live.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TacviewServerConfig {
+    /// Address Tacview connects to; defaults to `127.0.0.1:42674`, as clients connect without a
+    /// password. `0.0.0.0:42674`, or `[::]:42674` to accept IPv6 as well, lets other machines in.
+    #[serde(default = "default_tacview_address")]
+    pub address: SocketAddr,
+
+    /// Name of the server shown by Tacview
+    #[serde(default = "default_tacview_host_name")]
+    pub host_name: String,
+}
+
+impl Default for TacviewServerConfig {
+    fn default() -> Self {
+        TacviewServerConfig {
+            address: default_tacview_address(),
+            host_name: default_tacview_host_name(),
+        }
+    }
+}
+
+fn default_tacview_address() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, TACVIEW_TELEMETRY_PORT))
+}
+
+fn default_tacview_host_name() -> String {
+    "DCS JTAC Tools".to_string()
+}
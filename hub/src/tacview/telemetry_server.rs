@@ -0,0 +1,353 @@
+use std::{
+    io::{self, ErrorKind},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use log::{info, warn};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::broadcast::{self, error::RecvError, Receiver},
+    time,
+};
+
+use crate::{
+    common::{dcs_unit::DcsUnit, socket::bind_tcp_listener},
+    recording::recorded_unit::MissionKey,
+};
+
+use super::acmi_encoder::AcmiEncoder;
+
+/// The lines both ends of a connection start their handshake with
+const TELEMETRY_PROTOCOL: &str = "XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\n";
+
+/// How long a client has to answer the handshake of the server
+pub const TELEMETRY_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest handshake read from a client, whose name and password hash are short
+const TELEMETRY_HANDSHAKE_MAX_SIZE: u64 = 4096;
+
+/// Messages a client may fall behind by before it is disconnected
+const TELEMETRY_CHANNEL_CAPACITY: usize = 16_384;
+
+/// A message streamed to the clients
+#[derive(Clone)]
+enum TelemetryMessage {
+    /// The header starting the stream of a new mission
+    Mission(Arc<str>),
+
+    /// Lines of the stream of the current mission
+    Lines(Arc<str>),
+}
+
+/// The ACMI stream of the current mission
+#[derive(Default)]
+struct TelemetryStream {
+    mission_key: Option<MissionKey>,
+    encoder: AcmiEncoder,
+}
+
+/// Streams the units to Tacview clients connecting live, following the Tacview real-time
+/// telemetry protocol. Clients joining get the current position of every unit once they
+/// completed the handshake. Clients connect without a password.
+pub struct TelemetryServer {
+    host_name: String,
+    stream: Mutex<TelemetryStream>,
+    sender: broadcast::Sender<TelemetryMessage>,
+}
+
+impl TelemetryServer {
+    /// Instantiates a server introducing itself to clients as `host_name`.
+    pub fn new(host_name: String) -> TelemetryServer {
+        let (sender, _) = broadcast::channel(TELEMETRY_CHANNEL_CAPACITY);
+
+        TelemetryServer {
+            host_name,
+            stream: Mutex::default(),
+            sender,
+        }
+    }
+
+    /// Accepts clients on `listener` until the task is aborted. The clients are disconnected
+    /// once the server is dropped.
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            let (connection, peer) = match listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(err) => {
+                    warn!("Failed to accept a Tacview client: {}", err);
+                    continue;
+                }
+            };
+
+            let server = self.clone();
+            tokio::spawn(async move {
+                match serve_client(connection, &server).await {
+                    Ok(()) => info!("Tacview client {} disconnected", peer),
+                    Err(err) => warn!("Tacview client {} disconnected: {}", peer, err),
+                }
+            });
+        }
+    }
+
+    /// Streams the position of a unit, starting the stream over if its mission is a new one.
+    pub fn publish_unit(&self, unit: &DcsUnit) {
+        let mut stream = self.stream.lock().unwrap();
+        let mission_key = MissionKey::of(unit);
+
+        if stream.mission_key.as_ref() != Some(&mission_key) {
+            let header = AcmiEncoder::header(&mission_key);
+            *stream = TelemetryStream {
+                mission_key: Some(mission_key),
+                encoder: AcmiEncoder::default(),
+            };
+            self.send(TelemetryMessage::Mission(header.into()));
+        }
+
+        let lines = stream.encoder.encode_unit(unit);
+        self.send(TelemetryMessage::Lines(lines.into()));
+    }
+
+    /// Removes a unit that stopped reporting from the stream of its mission.
+    pub fn publish_removal(&self, unit: &DcsUnit) {
+        let mut stream = self.stream.lock().unwrap();
+        if stream.mission_key.as_ref() != Some(&MissionKey::of(unit)) {
+            return;
+        }
+
+        if let Some(line) = stream.encoder.encode_removal(unit) {
+            self.send(TelemetryMessage::Lines(line.into()));
+        }
+    }
+
+    /// The stream so far, if a mission started, and the messages following it.
+    fn join(&self) -> (Option<String>, Receiver<TelemetryMessage>) {
+        let stream = self.stream.lock().unwrap();
+        let joined = stream
+            .mission_key
+            .as_ref()
+            .map(|mission_key| AcmiEncoder::header(mission_key) + &stream.encoder.snapshot());
+
+        (joined, self.sender.subscribe())
+    }
+
+    fn send(&self, message: TelemetryMessage) {
+        // Fails only when no client is connected
+        let _ = self.sender.send(message);
+    }
+}
+
+/// Exchanges the handshake with a client, then streams to it until either end stops.
+async fn serve_client(connection: TcpStream, server: &TelemetryServer) -> io::Result<()> {
+    let (read_half, mut write_half) = connection.into_split();
+    let mut reader = BufReader::new(read_half);
+
+    write_half
+        .write_all(format!("{}{}\n\0", TELEMETRY_PROTOCOL, server.host_name).as_bytes())
+        .await?;
+    let client_name = time::timeout(TELEMETRY_HANDSHAKE_TIMEOUT, read_handshake(&mut reader))
+        .await
+        .map_err(|_| io::Error::new(ErrorKind::TimedOut, "No handshake received"))??;
+    info!("Tacview client '{}' connected", client_name);
+
+    // Joined only now, so the stream does not pile up while the client answers
+    let (joined, mut receiver) = server.join();
+
+    let mut started = joined.is_some();
+    if let Some(joined) = joined {
+        write_half.write_all(joined.as_bytes()).await?;
+    }
+
+    let mut discarded = [0u8; 256];
+    loop {
+        let lines = tokio::select! {
+            message = receiver.recv() => match message {
+                Ok(TelemetryMessage::Mission(header)) if !started => {
+                    started = true;
+                    header
+                }
+                Ok(TelemetryMessage::Mission(_)) => {
+                    // Tacview cannot start a stream over, the client reconnects instead
+                    return Err(io::Error::other("The mission changed"));
+                }
+                Ok(TelemetryMessage::Lines(lines)) => lines,
+                Err(RecvError::Lagged(missed)) => {
+                    return Err(io::Error::other(format!("Fell {} messages behind", missed)))
+                }
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            read = reader.read(&mut discarded) => match read? {
+                0 => return Ok(()),
+                _ => continue,
+            },
+        };
+
+        write_half.write_all(lines.as_bytes()).await?;
+    }
+}
+
+/// Reads the handshake of a client, returning the name it introduced itself with.
+async fn read_handshake(reader: &mut BufReader<impl AsyncReadExt + Unpin>) -> io::Result<String> {
+    let mut handshake = Vec::new();
+    reader
+        .take(TELEMETRY_HANDSHAKE_MAX_SIZE)
+        .read_until(b'\0', &mut handshake)
+        .await?;
+    if handshake.last() != Some(&b'\0') {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            "Incomplete handshake",
+        ));
+    }
+    let handshake = String::from_utf8_lossy(&handshake);
+
+    let client_name = handshake
+        .strip_prefix(TELEMETRY_PROTOCOL)
+        .and_then(|rest| rest.split('\n').next())
+        .ok_or_else(|| io::Error::new(ErrorKind::InvalidData, "Not a Tacview client"))?;
+
+    Ok(client_name.trim_end_matches('\0').to_string())
+}
+
+/// Binds the listener Tacview clients connect to.
+pub fn bind(address: SocketAddr) -> io::Result<TcpListener> {
+    TcpListener::from_std(bind_tcp_listener(address)?)
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpStream,
+        time,
+    };
+
+    use crate::common::{
+        dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+        unit_type::Level1UnitType,
+    };
+
+    use super::{bind, TelemetryServer};
+
+    #[tokio::test]
+    async fn test_stream_units_to_tacview_client() {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).expect("Failed to bind server");
+        let address = listener.local_addr().unwrap();
+        let server = Arc::new(TelemetryServer::new("Test hub".to_string()));
+        tokio::spawn(server.clone().serve(listener));
+        server.publish_unit(&build_dcs_unit("UNIT-1", 60));
+
+        let mut client = BufReader::new(TcpStream::connect(address).await.unwrap());
+        let mut handshake = Vec::new();
+        client.read_until(b'\0', &mut handshake).await.unwrap();
+        client
+            .get_mut()
+            .write_all(b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\nInstructor\n0\0")
+            .await
+            .unwrap();
+        let header = read_lines(&mut client, 7).await;
+        server.publish_unit(&build_dcs_unit("UNIT-2", 61));
+        server.publish_removal(&build_dcs_unit("UNIT-1", 60));
+        let updates = read_lines(&mut client, 3).await;
+
+        assert_eq!(
+            handshake,
+            b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\nTest hub\n\0"
+        );
+        assert_eq!(header[0], "FileType=text/acmi/tacview");
+        assert_eq!(header[2], "0,ReferenceTime=2024-03-08T08:00:00Z");
+        assert_eq!(header[5], "#60");
+        assert!(header[6].starts_with("1,T=41.6200000|41.6000000|10.0|0|0|0.0,Type="));
+        assert_eq!(updates[0], "#61");
+        assert!(updates[1].starts_with("2,T="));
+        assert_eq!(updates[2], "-1");
+    }
+
+    #[tokio::test]
+    async fn test_client_joins_stream_once_handshake_is_completed() {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).expect("Failed to bind server");
+        let address = listener.local_addr().unwrap();
+        let server = Arc::new(TelemetryServer::new("Test hub".to_string()));
+        tokio::spawn(server.clone().serve(listener));
+        server.publish_unit(&build_dcs_unit("UNIT-1", 60));
+
+        let mut client = BufReader::new(TcpStream::connect(address).await.unwrap());
+        let mut handshake = Vec::new();
+        client.read_until(b'\0', &mut handshake).await.unwrap();
+        // Published while the client has yet to answer
+        server.publish_unit(&build_dcs_unit("UNIT-2", 61));
+        client
+            .get_mut()
+            .write_all(b"XtraLib.Stream.0\nTacview.RealTimeTelemetry.0\nInstructor\n0\0")
+            .await
+            .unwrap();
+        let joined = read_lines(&mut client, 8).await;
+        server.publish_removal(&build_dcs_unit("UNIT-1", 61));
+        let updates = read_lines(&mut client, 1).await;
+
+        assert_eq!(joined[5], "#61");
+        assert!(joined[6].starts_with("1,T="));
+        assert!(joined[7].starts_with("2,T="));
+        assert_eq!(updates, vec!["-1"]);
+    }
+
+    #[tokio::test]
+    async fn test_client_with_oversized_handshake_is_disconnected() {
+        let listener = bind("127.0.0.1:0".parse().unwrap()).expect("Failed to bind server");
+        let address = listener.local_addr().unwrap();
+        let server = Arc::new(TelemetryServer::new("Test hub".to_string()));
+        tokio::spawn(server.clone().serve(listener));
+
+        let mut client = BufReader::new(TcpStream::connect(address).await.unwrap());
+        let mut handshake = Vec::new();
+        client.read_until(b'\0', &mut handshake).await.unwrap();
+        let _ = client.get_mut().write_all(&[b'A'; 8192]).await;
+        let mut rest = Vec::new();
+        let read = time::timeout(Duration::from_secs(5), client.read_to_end(&mut rest)).await;
+
+        assert!(read.is_ok(), "Client was not disconnected");
+        assert!(rest.is_empty());
+    }
+
+    async fn read_lines(client: &mut BufReader<TcpStream>, count: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for _ in 0..count {
+            let mut line = String::new();
+            time::timeout(Duration::from_secs(5), client.read_line(&mut line))
+                .await
+                .expect("Timed out waiting for the stream")
+                .expect("Failed to read the stream");
+            lines.push(line.trim_end().to_string());
+        }
+
+        lines
+    }
+
+    fn build_dcs_unit(unit_name: &str, mission_time_elapsed: i32) -> DcsUnit {
+        DcsUnit {
+            unit_name: unit_name.to_string(),
+            group_name: "GROUP-1".to_string(),
+            coalition: Coalition::BLUFOR,
+            position: Position3D {
+                latitude: 41.6,
+                longitude: 41.62,
+                altitude: 10.0,
+                heading: 0.0,
+            },
+            unit_type: UnitType {
+                level_1: Level1UnitType::GROUND,
+                level_2: 17,
+                level_3: 26,
+                level_4: 0,
+                type_name: "M1126 Stryker ICV".to_string(),
+            },
+            mission_date: "2024-03-08".to_string(),
+            mission_start_time: 28800,
+            mission_time_elapsed,
+        }
+    }
+}
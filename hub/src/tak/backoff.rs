@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+/// Delay before the first reconnection attempt
+pub const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between reconnection attempts
+pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Exponential backoff between reconnection attempts.
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    next: Duration,
+}
+
+impl Backoff {
+    /// Instantiates a new `Backoff` doubling from `initial` up to `max`.
+    pub fn new(initial: Duration, max: Duration) -> Backoff {
+        Backoff {
+            initial,
+            max,
+            next: initial,
+        }
+    }
+
+    /// Returns the delay before the next attempt and doubles the one after.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(self.max);
+        delay
+    }
+
+    /// Starts over from the initial delay, e.g. after a successful connection.
+    pub fn reset(&mut self) {
+        self.next = self.initial;
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(INITIAL_RECONNECT_DELAY, MAX_RECONNECT_DELAY)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::time::Duration;
+
+    use super::Backoff;
+
+    #[test]
+    fn given_repeated_failures_when_delayed_then_delay_doubles_up_to_max() {
+        // Arrange
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+
+        // Act
+        let delays: Vec<u64> = (0..5).map(|_| backoff.next_delay().as_secs()).collect();
+
+        // Assert
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+    }
+
+    #[test]
+    fn given_reset_when_delayed_then_delay_starts_over() {
+        // Arrange
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        backoff.next_delay();
+        backoff.next_delay();
+
+        // Act
+        backoff.reset();
+        let result = backoff.next_delay();
+
+        // Assert
+        assert_eq!(result, Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,8 @@
+pub mod backoff;
+pub mod tak_mesh;
+pub mod tak_mesh_config;
+pub mod tak_message;
+pub mod tak_protocol;
+pub mod tak_server_config;
+pub mod tak_stream;
+pub mod tls;
@@ -0,0 +1,161 @@
+use std::{
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+};
+
+use log::{error, info, warn};
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::cursor_on_target::Event;
+
+use super::{tak_mesh_config::TakMeshConfig, tak_protocol::TakProtocol};
+
+/// Publishes CoT to the multicast group of a TAK mesh network, so devices on the same network
+/// see the units without a TAK Server.
+pub struct TakMesh {
+    socket: UdpSocket,
+    address: SocketAddrV4,
+    protocol: TakProtocol,
+}
+
+impl TakMesh {
+    /// Opens a socket sending to the multicast group on the configured interface.
+    pub fn start(config: TakMeshConfig) -> io::Result<TakMesh> {
+        let interface = config.interface.unwrap_or(Ipv4Addr::UNSPECIFIED);
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_multicast_if_v4(&interface)?;
+        socket.set_multicast_ttl_v4(config.ttl())?;
+        // Lets a TAK client running on the same machine receive the messages too
+        socket.set_multicast_loop_v4(true)?;
+        socket.bind(&SocketAddrV4::new(interface, 0).into())?;
+        socket.set_nonblocking(true)?;
+
+        info!(
+            "Publishing CoT to TAK mesh {} via {}",
+            config.address(),
+            interface
+        );
+
+        Ok(TakMesh {
+            socket: socket.into(),
+            address: config.address(),
+            protocol: config.protocol,
+        })
+    }
+
+    /// Sends a CoT event as a single datagram. Messages the socket cannot take right away are
+    /// dropped, as a later update supersedes them.
+    pub fn publish(&self, event: &Event) {
+        let message = match self.protocol.encode_mesh_message(event) {
+            Ok(message) => message,
+            Err(err) => {
+                error!("Failed to encode TAK mesh message: {}", err);
+                return;
+            }
+        };
+
+        match self.socket.send_to(&message, self.address) {
+            Ok(_) => {}
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                warn!("TAK mesh socket is busy, dropping message")
+            }
+            Err(err) => warn!("Failed to send to TAK mesh {}: {}", self.address, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddrV4, UdpSocket},
+        time::Duration,
+    };
+
+    use prost::Message;
+
+    use crate::{
+        cursor_on_target::{xml_serializer::ToXml, Detail, Event, Point},
+        tak::{tak_mesh_config::TakMeshConfig, tak_message::TakMessage, tak_protocol::TakProtocol},
+    };
+
+    use super::TakMesh;
+
+    fn start_receiver() -> (UdpSocket, SocketAddrV4) {
+        // Multicast routes are not available everywhere, a unicast receiver stands in for the group
+        let receiver = UdpSocket::bind("127.0.0.1:0").expect("Failed to bind TAK client stand-in");
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let address = SocketAddrV4::new(Ipv4Addr::LOCALHOST, receiver.local_addr().unwrap().port());
+        (receiver, address)
+    }
+
+    #[test]
+    fn test_tak_mesh_sends_each_event_as_datagram() {
+        // Arrange
+        let (receiver, address) = start_receiver();
+        let config = TakMeshConfig {
+            interface: Some(Ipv4Addr::LOCALHOST),
+            address: Some(address),
+            ..Default::default()
+        };
+        let tak_mesh = TakMesh::start(config).expect("Failed to start TAK mesh");
+        let mut buffer = [0; 1024];
+
+        // Act
+        tak_mesh.publish(&build_event("1"));
+        tak_mesh.publish(&build_event("2"));
+        let first_length = receiver.recv(&mut buffer).expect("Did not receive message");
+        let first = String::from_utf8_lossy(&buffer[..first_length]).into_owned();
+        let second_length = receiver.recv(&mut buffer).expect("Did not receive message");
+        let second = String::from_utf8_lossy(&buffer[..second_length]).into_owned();
+
+        // Assert
+        assert_eq!(first, build_event("1").to_xml().unwrap());
+        assert_eq!(second, build_event("2").to_xml().unwrap());
+    }
+
+    #[test]
+    fn test_tak_mesh_sends_protobuf_with_mesh_header() {
+        // Arrange
+        let (receiver, address) = start_receiver();
+        let config = TakMeshConfig {
+            interface: Some(Ipv4Addr::LOCALHOST),
+            address: Some(address),
+            protocol: TakProtocol::Protobuf,
+            ..Default::default()
+        };
+        let tak_mesh = TakMesh::start(config).expect("Failed to start TAK mesh");
+        let mut buffer = [0; 1024];
+
+        // Act
+        tak_mesh.publish(&build_event("1"));
+        let length = receiver.recv(&mut buffer).expect("Did not receive message");
+
+        // Assert
+        assert_eq!(buffer[..3], [0xbf, 0x01, 0xbf]);
+        let message = TakMessage::decode(&buffer[3..length]).expect("Failed to decode message");
+        assert_eq!(message.cot_event.unwrap().uid, "1");
+    }
+
+    fn build_event(uid: &str) -> Event {
+        Event {
+            unit_type: "a-f-G".to_string(),
+            how: "m-g".to_string(),
+            uid: uid.to_string(),
+            time: "2005-04-05T11:43:38Z".to_string(),
+            stale: "2005-04-05T11:44:38Z".to_string(),
+            point: Point {
+                lat: 42.0,
+                lon: 41.5,
+                hae: 120.0,
+            },
+            detail: Detail {
+                call_sign: Some(uid.to_string()),
+                track: None,
+                link: None,
+                remarks: None,
+            },
+        }
+    }
+}
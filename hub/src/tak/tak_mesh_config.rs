@@ -0,0 +1,62 @@
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+use serde::{Deserialize, Serialize};
+
+use super::tak_protocol::TakProtocol;
+
+/// Multicast group ATAK devices exchange situational awareness on
+pub const TAK_MESH_ADDRESS: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(239, 2, 3, 1), 6969);
+
+/// Default multicast TTL, keeping the traffic on the local network
+pub const TAK_MESH_TTL: u32 = 1;
+
+/// Publishing to the multicast group of a TAK mesh network.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct TakMeshConfig {
+    /// IPv4 address of the network interface to send on; the OS picks one without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interface: Option<Ipv4Addr>,
+
+    /// Group and port to send to; defaults to 239.2.3.1:6969
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<SocketAddrV4>,
+
+    /// Number of routers the messages may cross; defaults to 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u32>,
+
+    /// Encoding of the datagrams, which every device on the network must understand
+    #[serde(default)]
+    pub protocol: TakProtocol,
+}
+
+impl TakMeshConfig {
+    /// The configured address, or the standard ATAK mesh group.
+    pub fn address(&self) -> SocketAddrV4 {
+        self.address.unwrap_or(TAK_MESH_ADDRESS)
+    }
+
+    /// The configured TTL, or one that keeps messages on the local network.
+    pub fn ttl(&self) -> u32 {
+        self.ttl.unwrap_or(TAK_MESH_TTL)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{TakMeshConfig, TAK_MESH_ADDRESS, TAK_MESH_TTL};
+
+    #[test]
+    fn given_default_config_when_read_then_standard_mesh_group_is_used() {
+        // Arrange
+        let config = TakMeshConfig::default();
+
+        // Act
+        let address = config.address();
+        let ttl = config.ttl();
+
+        // Assert
+        assert_eq!(address, TAK_MESH_ADDRESS);
+        assert_eq!(ttl, TAK_MESH_TTL);
+    }
+}
@@ -0,0 +1,234 @@
+use chrono::DateTime;
+
+use crate::cursor_on_target::{
+    xml_serializer::{SerializationError, ToXml},
+    Detail as CotDetail, Event,
+};
+
+// Messages of TAK Protocol Version 1, mirroring the `.proto` files shipped with ATAK
+// See https://github.com/deptofdefense/AndroidTacticalAssaultKit-CIV/tree/master/commoncommo/core/impl/protobuf
+// Only the fields the hub produces are declared, unknown fields are skipped when decoding.
+
+/// Top level message of TAK Protocol Version 1
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TakMessage {
+    /// Protocol control information, e.g. the versions supported by the sender
+    #[prost(message, optional, tag = "1")]
+    pub tak_control: Option<TakControl>,
+
+    /// The CoT event carried by the message
+    #[prost(message, optional, tag = "2")]
+    pub cot_event: Option<CotEvent>,
+}
+
+/// Protocol control information of a `TakMessage`
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TakControl {
+    /// Lowest protocol version supported by the sender
+    #[prost(uint32, tag = "1")]
+    pub min_proto_version: u32,
+
+    /// Highest protocol version supported by the sender
+    #[prost(uint32, tag = "2")]
+    pub max_proto_version: u32,
+
+    /// Uid of the contact sending the message
+    #[prost(string, tag = "3")]
+    pub contact_uid: String,
+}
+
+/// A CoT event, with times in milliseconds since the Unix epoch
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct CotEvent {
+    #[prost(string, tag = "1")]
+    pub cot_type: String,
+
+    #[prost(string, tag = "2")]
+    pub access: String,
+
+    #[prost(string, tag = "3")]
+    pub qos: String,
+
+    #[prost(string, tag = "4")]
+    pub opex: String,
+
+    #[prost(string, tag = "5")]
+    pub uid: String,
+
+    #[prost(uint64, tag = "6")]
+    pub send_time: u64,
+
+    #[prost(uint64, tag = "7")]
+    pub start_time: u64,
+
+    #[prost(uint64, tag = "8")]
+    pub stale_time: u64,
+
+    #[prost(string, tag = "9")]
+    pub how: String,
+
+    #[prost(double, tag = "10")]
+    pub lat: f64,
+
+    #[prost(double, tag = "11")]
+    pub lon: f64,
+
+    #[prost(double, tag = "12")]
+    pub hae: f64,
+
+    #[prost(double, tag = "13")]
+    pub ce: f64,
+
+    #[prost(double, tag = "14")]
+    pub le: f64,
+
+    #[prost(message, optional, tag = "15")]
+    pub detail: Option<Detail>,
+}
+
+/// Detail of a `CotEvent`. Sub-schemas without a dedicated field are carried as XML.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Detail {
+    /// Children of the `<detail>` element that have no dedicated field
+    #[prost(string, tag = "1")]
+    pub xml_detail: String,
+
+    #[prost(message, optional, tag = "2")]
+    pub contact: Option<Contact>,
+
+    #[prost(message, optional, tag = "7")]
+    pub track: Option<Track>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Contact {
+    #[prost(string, tag = "1")]
+    pub endpoint: String,
+
+    #[prost(string, tag = "2")]
+    pub callsign: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Track {
+    #[prost(double, tag = "1")]
+    pub speed: f64,
+
+    #[prost(double, tag = "2")]
+    pub course: f64,
+}
+
+impl TryFrom<&Event> for TakMessage {
+    type Error = SerializationError;
+
+    fn try_from(event: &Event) -> Result<Self, Self::Error> {
+        let time = timestamp_millis(&event.time)?;
+
+        Ok(TakMessage {
+            tak_control: None,
+            cot_event: Some(CotEvent {
+                cot_type: event.unit_type.clone(),
+                access: String::new(),
+                qos: String::new(),
+                opex: String::new(),
+                uid: event.uid.clone(),
+                send_time: time,
+                start_time: time,
+                stale_time: timestamp_millis(&event.stale)?,
+                how: event.how.clone(),
+                lat: event.point.lat,
+                lon: event.point.lon,
+                hae: event.point.hae as f64,
+                ce: 0.0,
+                le: 0.0,
+                detail: Some(Detail {
+                    xml_detail: xml_detail(&event.detail)?,
+                    contact: event.detail.call_sign.as_ref().map(|call_sign| Contact {
+                        endpoint: String::new(),
+                        callsign: call_sign.clone(),
+                    }),
+                    track: event.detail.track.as_ref().map(|track| Track {
+                        speed: track.speed,
+                        course: track.course,
+                    }),
+                }),
+            }),
+        })
+    }
+}
+
+fn timestamp_millis(time: &str) -> Result<u64, SerializationError> {
+    let time = DateTime::parse_from_rfc3339(time)?;
+    Ok(time.timestamp_millis().max(0) as u64)
+}
+
+/// The XML of the detail sub-schemas that have no dedicated protobuf field.
+fn xml_detail(detail: &CotDetail) -> Result<String, SerializationError> {
+    let remaining = CotDetail {
+        call_sign: None,
+        track: None,
+        ..detail.clone()
+    };
+    let xml = remaining.to_xml()?;
+
+    // Only the children of the detail element are sent
+    Ok(xml
+        .strip_prefix("<detail>")
+        .and_then(|xml| xml.strip_suffix("</detail>"))
+        .unwrap_or_default()
+        .to_string())
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::cursor_on_target::{Detail, Event, Link, Point, Track};
+
+    use super::TakMessage;
+
+    #[test]
+    fn given_event_when_converted_then_times_are_milliseconds_and_detail_is_split() {
+        // Arrange
+        let event = Event {
+            unit_type: "a-h-G".to_string(),
+            how: "m-g".to_string(),
+            uid: "Tank 1".to_string(),
+            time: "2005-04-05T11:43:38Z".to_string(),
+            stale: "2005-04-05T11:44:38Z".to_string(),
+            point: Point {
+                lat: 42.0,
+                lon: 41.5,
+                hae: 120.0,
+            },
+            detail: Detail {
+                call_sign: Some("Tank 1".to_string()),
+                track: Some(Track {
+                    course: 90.0,
+                    speed: 10.0,
+                    slope: 0.0,
+                }),
+                link: Some(Link {
+                    uid: "Platoon".to_string(),
+                    unit_type: "a-h-G".to_string(),
+                }),
+                remarks: Some("Moving <east>".to_string()),
+            },
+        };
+
+        // Act
+        let result = TakMessage::try_from(&event).expect("Failed to convert event");
+
+        // Assert
+        let cot_event = result.cot_event.expect("Missing CoT event");
+        let detail = cot_event.detail.expect("Missing detail");
+        assert_eq!(cot_event.cot_type, "a-h-G");
+        assert_eq!(cot_event.send_time, 1_112_701_418_000);
+        assert_eq!(cot_event.stale_time, 1_112_701_478_000);
+        assert_eq!(cot_event.hae, 120.0);
+        assert_eq!(detail.contact.unwrap().callsign, "Tank 1");
+        assert_eq!(detail.track.unwrap().course, 90.0);
+        assert_eq!(
+            detail.xml_detail,
+            r#"<link uid="Platoon" type="a-h-G" relation="none"/><__forcedelete/><remarks>Moving &lt;east&gt;</remarks>"#
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use prost::Message;
+use quick_xml::{events::Event as XmlEvent, Reader};
+use serde::{Deserialize, Serialize};
+
+use crate::cursor_on_target::{
+    xml_serializer::{SerializationError, ToXml},
+    xml_writer::{XmlError, XmlWriter},
+    Event,
+};
+
+use super::tak_message::TakMessage;
+
+/// First byte of every TAK Protocol message, which can never start an XML document
+pub const TAK_PROTOCOL_MAGIC: u8 = 0xbf;
+
+/// Version of TAK Protocol using protobuf messages
+pub const TAK_PROTOCOL_VERSION: u32 = 1;
+
+/// CoT type a TAK Server advertises the protocol versions it supports with
+pub const PROTOCOL_SUPPORT_TYPE: &str = "t-x-takp-v";
+
+/// CoT type a client requests a protocol version with
+pub const PROTOCOL_REQUEST_TYPE: &str = "t-x-takp-q";
+
+/// CoT type a TAK Server answers a protocol request with
+pub const PROTOCOL_RESPONSE_TYPE: &str = "t-x-takp-r";
+
+/// Encoding of the CoT sent to TAK clients and servers.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TakProtocol {
+    /// CoT XML, understood by every TAK client
+    #[default]
+    Xml,
+
+    /// TAK Protocol Version 1, protobuf messages that are a fraction of the size of the XML
+    Protobuf,
+}
+
+impl TakProtocol {
+    /// Encodes an event as a datagram for a mesh network, protobuf messages being preceded by
+    /// the `0xbf 0x01 0xbf` header.
+    pub fn encode_mesh_message(self, event: &Event) -> Result<Vec<u8>, SerializationError> {
+        match self {
+            TakProtocol::Xml => Ok(event.to_xml()?.into_bytes()),
+            TakProtocol::Protobuf => {
+                let mut message = vec![
+                    TAK_PROTOCOL_MAGIC,
+                    TAK_PROTOCOL_VERSION as u8,
+                    TAK_PROTOCOL_MAGIC,
+                ];
+                TakMessage::try_from(event)?
+                    .encode(&mut message)
+                    .expect("Vec grows as needed");
+                Ok(message)
+            }
+        }
+    }
+
+    /// Encodes an event for a streaming connection. XML events are terminated by a new line,
+    /// protobuf messages are preceded by `0xbf` and their varint encoded length.
+    pub fn encode_stream_message(self, event: &Event) -> Result<Vec<u8>, SerializationError> {
+        match self {
+            TakProtocol::Xml => {
+                let mut message = event.to_xml()?.into_bytes();
+                message.push(b'\n');
+                Ok(message)
+            }
+            TakProtocol::Protobuf => {
+                let mut message = vec![TAK_PROTOCOL_MAGIC];
+                TakMessage::try_from(event)?
+                    .encode_length_delimited(&mut message)
+                    .expect("Vec grows as needed");
+                Ok(message)
+            }
+        }
+    }
+}
+
+/// Messages exchanged in XML to switch a streaming connection to protobuf.
+#[derive(Debug, PartialEq)]
+pub enum NegotiationMessage {
+    /// The server supports these protocol versions
+    Support(Vec<u32>),
+
+    /// The server accepted, or refused, the requested version
+    Response(bool),
+}
+
+/// Reads a protocol negotiation message, returning `None` for any other event.
+pub fn parse_negotiation_message(xml: &str) -> Option<NegotiationMessage> {
+    let mut reader = Reader::from_str(xml);
+    let mut event_type = None;
+    let mut versions = Vec::new();
+    let mut status = false;
+
+    loop {
+        let start = match reader.read_event().ok()? {
+            XmlEvent::Start(start) | XmlEvent::Empty(start) => start,
+            XmlEvent::Eof => break,
+            _ => continue,
+        };
+        let attribute = |name: &str| {
+            start
+                .try_get_attribute(name)
+                .ok()
+                .flatten()
+                .and_then(|attribute| attribute.unescape_value().ok())
+                .map(|value| value.into_owned())
+        };
+
+        match start.name().as_ref() {
+            b"event" => event_type = attribute("type"),
+            b"TakProtocolSupport" => {
+                if let Some(version) = attribute("version").and_then(|v| v.parse().ok()) {
+                    versions.push(version);
+                }
+            }
+            b"TakResponse" => status = attribute("status").as_deref() == Some("true"),
+            _ => {}
+        }
+    }
+
+    match event_type.as_deref() {
+        Some(PROTOCOL_SUPPORT_TYPE) => Some(NegotiationMessage::Support(versions)),
+        Some(PROTOCOL_RESPONSE_TYPE) => Some(NegotiationMessage::Response(status)),
+        _ => None,
+    }
+}
+
+/// Builds the XML event requesting a TAK Server to switch to `version` of the protocol.
+pub fn build_protocol_request(version: u32, now: DateTime<Utc>) -> Result<String, XmlError> {
+    let time = now.to_rfc3339_opts(SecondsFormat::Secs, true);
+    let stale =
+        (now + Duration::try_minutes(1).unwrap()).to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut writer = XmlWriter::new();
+    writer
+        .declaration()
+        .start_element("event")
+        .attribute("version", "2.0")
+        .attribute("uid", "protouid")
+        .attribute("type", PROTOCOL_REQUEST_TYPE)
+        .attribute("how", "m-g")
+        .attribute("time", &time)
+        .attribute("start", &time)
+        .attribute("stale", &stale)
+        .start_element("point")
+        .attribute("lat", "0.0")
+        .attribute("lon", "0.0")
+        .attribute("hae", "0.0")
+        .attribute("ce", "999999")
+        .attribute("le", "999999")
+        .end_element()
+        .start_element("detail")
+        .start_element("TakControl")
+        .start_element("TakRequest")
+        .attribute("version", version)
+        .end_element()
+        .end_element()
+        .end_element()
+        .end_element();
+    writer.finish()
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use chrono::{TimeZone, Utc};
+    use prost::Message;
+
+    use crate::{
+        cursor_on_target::{Detail, Event, Point},
+        tak::tak_message::TakMessage,
+    };
+
+    use super::{
+        build_protocol_request, parse_negotiation_message, NegotiationMessage, TakProtocol,
+    };
+
+    #[test]
+    fn given_protobuf_when_event_is_encoded_then_messages_are_framed_per_transport() {
+        // Arrange
+        let event = build_event();
+
+        // Act
+        let mesh = TakProtocol::Protobuf
+            .encode_mesh_message(&event)
+            .expect("Failed to encode mesh message");
+        let stream = TakProtocol::Protobuf
+            .encode_stream_message(&event)
+            .expect("Failed to encode stream message");
+        let xml = TakProtocol::Xml
+            .encode_stream_message(&event)
+            .expect("Failed to encode XML message");
+
+        // Assert
+        assert_eq!(mesh[..3], [0xbf, 0x01, 0xbf]);
+        let from_mesh = TakMessage::decode(&mesh[3..]).expect("Failed to decode mesh message");
+        assert_eq!(from_mesh.cot_event.as_ref().unwrap().uid, "Tank 1");
+
+        assert_eq!(stream[0], 0xbf);
+        let from_stream =
+            TakMessage::decode_length_delimited(&stream[1..]).expect("Failed to decode stream");
+        assert_eq!(from_stream, from_mesh);
+
+        assert!(stream.len() < xml.len() / 2);
+        assert_eq!(xml.last(), Some(&b'\n'));
+    }
+
+    #[test]
+    fn given_negotiation_events_when_parsed_then_versions_and_status_are_read() {
+        // Arrange
+        let support = r#"<event version="2.0" uid="protouid" type="t-x-takp-v" time="2024-01-01T00:00:00Z" start="2024-01-01T00:00:00Z" stale="2024-01-01T00:01:00Z" how="m-g"><point lat="0.0" lon="0.0" hae="0.0" ce="999999" le="999999"/><detail><TakControl><TakProtocolSupport version="1"/></TakControl></detail></event>"#;
+        let response = r#"<event version="2.0" uid="protouid" type="t-x-takp-r" how="m-g"><detail><TakControl><TakResponse status="true"/></TakControl></detail></event>"#;
+        let other = r#"<event version="2.0" uid="1" type="a-f-G" how="m-g"/>"#;
+
+        // Act / Assert
+        assert_eq!(
+            parse_negotiation_message(support),
+            Some(NegotiationMessage::Support(vec![1]))
+        );
+        assert_eq!(
+            parse_negotiation_message(response),
+            Some(NegotiationMessage::Response(true))
+        );
+        assert_eq!(parse_negotiation_message(other), None);
+    }
+
+    #[test]
+    fn given_version_when_protocol_request_is_built_then_tak_request_is_generated() {
+        // Arrange
+        let now = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let expected = r#"<?xml version="1.0" standalone="yes"?><event version="2.0" uid="protouid" type="t-x-takp-q" how="m-g" time="2024-01-01T00:00:00Z" start="2024-01-01T00:00:00Z" stale="2024-01-01T00:01:00Z"><point lat="0.0" lon="0.0" hae="0.0" ce="999999" le="999999"/><detail><TakControl><TakRequest version="1"/></TakControl></detail></event>"#;
+
+        // Act
+        let result = build_protocol_request(1, now).expect("Failed to build protocol request");
+
+        // Assert
+        assert_eq!(result, expected);
+    }
+
+    fn build_event() -> Event {
+        Event {
+            unit_type: "a-h-G".to_string(),
+            how: "m-g".to_string(),
+            uid: "Tank 1".to_string(),
+            time: "2005-04-05T11:43:38Z".to_string(),
+            stale: "2005-04-05T11:44:38Z".to_string(),
+            point: Point {
+                lat: 42.0,
+                lon: 41.5,
+                hae: 120.0,
+            },
+            detail: Detail {
+                call_sign: Some("Tank 1".to_string()),
+                track: None,
+                link: None,
+                remarks: None,
+            },
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use super::tak_protocol::TakProtocol;
+
+/// Default TAK Server streaming port for plain TCP
+pub const TAK_SERVER_TCP_PORT: u16 = 8087;
+
+/// Default TAK Server streaming port for TLS
+pub const TAK_SERVER_TLS_PORT: u16 = 8089;
+
+/// Connection to the streaming input of a TAK Server.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TakServerConfig {
+    /// Host name or IP address of the TAK Server
+    pub host: String,
+
+    /// Streaming port; defaults to 8087, or 8089 with TLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+
+    /// TLS settings; the connection is plain TCP without them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsConfig>,
+
+    /// Encoding to stream in; protobuf is only used once the server agreed to it
+    #[serde(default)]
+    pub protocol: TakProtocol,
+}
+
+impl TakServerConfig {
+    /// The configured port, or the default one for the transport.
+    pub fn port(&self) -> u16 {
+        match (self.port, &self.tls) {
+            (Some(port), _) => port,
+            (None, Some(_)) => TAK_SERVER_TLS_PORT,
+            (None, None) => TAK_SERVER_TCP_PORT,
+        }
+    }
+}
+
+/// Certificates for a TLS connection to a TAK Server. Files ending in `.p12` or `.pfx` are read
+/// as PKCS#12, anything else as PEM.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TlsConfig {
+    /// Client certificate chain, including the private key if PKCS#12
+    pub certificate_path: String,
+
+    /// Private key of the client certificate, if it is not part of `certificate_path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_path: Option<String>,
+
+    /// Password of a PKCS#12 client certificate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate_password: Option<String>,
+
+    /// Certificate authorities trusted to sign the server certificate
+    pub trust_store_path: String,
+
+    /// Password of a PKCS#12 trust store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_store_password: Option<String>,
+
+    /// Name expected in the server certificate, if it differs from the host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+}
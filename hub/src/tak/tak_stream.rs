@@ -0,0 +1,628 @@
+use std::{io, time::Duration};
+
+use chrono::Utc;
+use log::{error, info, warn};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf},
+    net::TcpStream,
+    runtime::Handle,
+    sync::mpsc::{channel, error::TrySendError, Receiver, Sender},
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+use tokio_rustls::TlsConnector;
+
+use crate::cursor_on_target::Event;
+
+use super::{
+    backoff::Backoff,
+    tak_protocol::{
+        build_protocol_request, parse_negotiation_message, NegotiationMessage, TakProtocol,
+        TAK_PROTOCOL_VERSION,
+    },
+    tak_server_config::TakServerConfig,
+    tls::{build_tls_connector, server_name},
+};
+
+/// Number of messages buffered while the TAK Server is unreachable
+pub const TAK_STREAM_QUEUE_SIZE: usize = 4096;
+
+/// Time allowed for connecting to the TAK Server, including the TLS handshake
+pub const TAK_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time the TAK Server is given to offer protocol versions, and to answer the request for one
+pub const TAK_NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time the queued events are still written for once the stream is dropped
+pub const TAK_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connection to a TAK Server, either plain TCP or TLS
+trait TakConnection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> TakConnection for T {}
+
+/// Streams CoT to the streaming input of a TAK Server, reconnecting with a backoff whenever the
+/// connection is lost. Once dropped, the events queued are written for up to
+/// `TAK_DRAIN_TIMEOUT` before the connection is closed.
+pub struct TakStream {
+    message_sender: Sender<Event>,
+    connection_task: JoinHandle<()>,
+}
+
+impl TakStream {
+    /// Starts connecting to the TAK Server in the background. Fails right away if the TLS
+    /// certificates cannot be loaded.
+    pub fn start(config: TakServerConfig) -> io::Result<TakStream> {
+        let tls_connector = match &config.tls {
+            Some(tls) => Some(build_tls_connector(tls)?),
+            None => None,
+        };
+        let (message_sender, message_receiver) = channel(TAK_STREAM_QUEUE_SIZE);
+
+        let connection_task =
+            tokio::spawn(run_connection_loop(config, tls_connector, message_receiver));
+
+        Ok(TakStream {
+            message_sender,
+            connection_task,
+        })
+    }
+
+    /// Queues a CoT event for the TAK Server. Events are dropped while the queue is full.
+    pub fn publish(&self, event: Event) {
+        if let Err(TrySendError::Full(_)) = self.message_sender.try_send(event) {
+            warn!("TAK Server queue is full, dropping message");
+        }
+    }
+}
+
+impl Drop for TakStream {
+    fn drop(&mut self) {
+        // Closing the queue ends the connection loop once the queued events are written, which
+        // never happens while the TAK Server is unreachable
+        let connection_task = self.connection_task.abort_handle();
+        if let Ok(runtime) = Handle::try_current() {
+            runtime.spawn(async move {
+                sleep(TAK_DRAIN_TIMEOUT).await;
+                connection_task.abort();
+            });
+        } else {
+            connection_task.abort();
+        }
+    }
+}
+
+async fn run_connection_loop(
+    config: TakServerConfig,
+    tls_connector: Option<TlsConnector>,
+    mut message_receiver: Receiver<Event>,
+) {
+    let address = format!("{}:{}", config.host, config.port());
+    let mut backoff = Backoff::default();
+    // An event that could not be written before the connection was lost
+    let mut pending_message: Option<Event> = None;
+
+    loop {
+        let connection = match connect(&config, tls_connector.as_ref()).await {
+            Ok(connection) => connection,
+            Err(err) => {
+                let delay = backoff.next_delay();
+                warn!(
+                    "Failed to connect to TAK Server {}: {}. Retrying in {:?}",
+                    address, err, delay
+                );
+                sleep(delay).await;
+                continue;
+            }
+        };
+
+        info!("Connected to TAK Server {}", address);
+        backoff.reset();
+
+        let (mut read_half, mut write_half) = tokio::io::split(connection);
+        let result = match config.protocol {
+            TakProtocol::Xml => Ok(TakProtocol::Xml),
+            TakProtocol::Protobuf => negotiate_protocol(&mut read_half, &mut write_half).await,
+        };
+        let result = match result {
+            Ok(protocol) => {
+                stream_messages(
+                    read_half,
+                    write_half,
+                    &mut message_receiver,
+                    &mut pending_message,
+                    protocol,
+                )
+                .await
+            }
+            Err(err) => Err(err),
+        };
+
+        match result {
+            // The sending side is gone, nothing left to stream
+            Ok(()) => return,
+            Err(err) => warn!("Lost connection to TAK Server {}: {}", address, err),
+        }
+
+        sleep(backoff.next_delay()).await;
+    }
+}
+
+async fn connect(
+    config: &TakServerConfig,
+    tls_connector: Option<&TlsConnector>,
+) -> io::Result<Box<dyn TakConnection>> {
+    let connect = async {
+        let stream = TcpStream::connect((config.host.as_str(), config.port())).await?;
+
+        let connection: Box<dyn TakConnection> = match (tls_connector, &config.tls) {
+            (Some(tls_connector), Some(tls)) => {
+                let server_name = server_name(&config.host, tls)?;
+                Box::new(tls_connector.connect(server_name, stream).await?)
+            }
+            _ => Box::new(stream),
+        };
+
+        Ok(connection)
+    };
+
+    match timeout(TAK_CONNECT_TIMEOUT, connect).await {
+        Ok(result) => result,
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "Timed out connecting",
+        )),
+    }
+}
+
+/// Asks the TAK Server to switch to protobuf if it offers TAK Protocol Version 1, falling back to
+/// XML if it does not offer it or refuses the request.
+async fn negotiate_protocol(
+    read_half: &mut ReadHalf<Box<dyn TakConnection>>,
+    write_half: &mut WriteHalf<Box<dyn TakConnection>>,
+) -> io::Result<TakProtocol> {
+    let mut buffer = Vec::new();
+
+    let offer = timeout(TAK_NEGOTIATION_TIMEOUT, async {
+        loop {
+            if let NegotiationMessage::Support(versions) =
+                read_negotiation_message(read_half, &mut buffer).await?
+            {
+                return Ok::<_, io::Error>(versions);
+            }
+        }
+    })
+    .await;
+    let versions = match offer {
+        Ok(versions) => versions?,
+        Err(_) => {
+            warn!("TAK Server did not offer a protocol version, streaming XML");
+            return Ok(TakProtocol::Xml);
+        }
+    };
+    if !versions.contains(&TAK_PROTOCOL_VERSION) {
+        warn!("TAK Server does not support protobuf, streaming XML");
+        return Ok(TakProtocol::Xml);
+    }
+
+    let request = build_protocol_request(TAK_PROTOCOL_VERSION, Utc::now())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    write_half.write_all(request.as_bytes()).await?;
+    write_half.flush().await?;
+
+    let response = timeout(TAK_NEGOTIATION_TIMEOUT, async {
+        loop {
+            if let NegotiationMessage::Response(status) =
+                read_negotiation_message(read_half, &mut buffer).await?
+            {
+                return Ok::<_, io::Error>(status);
+            }
+        }
+    })
+    .await;
+    match response {
+        Ok(Ok(true)) => {
+            info!("TAK Server accepted protobuf");
+            Ok(TakProtocol::Protobuf)
+        }
+        Ok(Ok(false)) | Err(_) => {
+            warn!("TAK Server did not accept protobuf, streaming XML");
+            Ok(TakProtocol::Xml)
+        }
+        Ok(Err(err)) => Err(err),
+    }
+}
+
+/// Reads XML events from the server until one of them is part of the protocol negotiation.
+/// Bytes following that event are kept in `buffer`.
+async fn read_negotiation_message(
+    read_half: &mut ReadHalf<Box<dyn TakConnection>>,
+    buffer: &mut Vec<u8>,
+) -> io::Result<NegotiationMessage> {
+    const EVENT_END: &[u8] = b"</event>";
+
+    loop {
+        while let Some(position) = buffer
+            .windows(EVENT_END.len())
+            .position(|window| window == EVENT_END)
+        {
+            let xml: Vec<u8> = buffer.drain(..position + EVENT_END.len()).collect();
+            if let Some(message) = parse_negotiation_message(&String::from_utf8_lossy(&xml)) {
+                return Ok(message);
+            }
+        }
+
+        let mut read_buffer = [0; 4096];
+        let read = read_half.read(&mut read_buffer).await?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Connection closed by the server",
+            ));
+        }
+        buffer.extend_from_slice(&read_buffer[..read]);
+    }
+}
+
+/// Writes queued events until the connection fails, or returns `Ok` once no more events can
+/// be queued. Anything the server sends is discarded, reading only serves to detect when it
+/// closes the connection.
+async fn stream_messages(
+    mut read_half: ReadHalf<Box<dyn TakConnection>>,
+    mut write_half: WriteHalf<Box<dyn TakConnection>>,
+    message_receiver: &mut Receiver<Event>,
+    pending_message: &mut Option<Event>,
+    protocol: TakProtocol,
+) -> io::Result<()> {
+    if let Some(event) = pending_message.as_ref() {
+        write_message(&mut write_half, event, protocol).await?;
+        *pending_message = None;
+    }
+
+    let mut read_buffer = [0; 4096];
+    loop {
+        tokio::select! {
+            message = message_receiver.recv() => {
+                let event = match message {
+                    Some(event) => event,
+                    None => return Ok(()),
+                };
+
+                let event = pending_message.insert(event);
+                write_message(&mut write_half, event, protocol).await?;
+                *pending_message = None;
+            }
+            read = read_half.read(&mut read_buffer) => {
+                if read? == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Connection closed by the server",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Writes an event in the negotiated encoding. Events that cannot be encoded are skipped.
+async fn write_message(
+    write_half: &mut WriteHalf<Box<dyn TakConnection>>,
+    event: &Event,
+    protocol: TakProtocol,
+) -> io::Result<()> {
+    let message = match protocol.encode_stream_message(event) {
+        Ok(message) => message,
+        Err(err) => {
+            error!("Failed to encode TAK Server message: {}", err);
+            return Ok(());
+        }
+    };
+
+    write_half.write_all(&message).await?;
+    write_half.flush().await
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+
+    use prost::Message;
+    use rcgen::{
+        BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+        KeyPair,
+    };
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+        time::timeout,
+    };
+    use tokio_rustls::{
+        rustls::{
+            crypto::ring,
+            pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer},
+            server::WebPkiClientVerifier,
+            RootCertStore, ServerConfig,
+        },
+        TlsAcceptor,
+    };
+
+    use crate::{
+        cursor_on_target::{xml_serializer::ToXml, Detail, Event, Point},
+        tak::{
+            tak_message::TakMessage,
+            tak_protocol::TakProtocol,
+            tak_server_config::{TakServerConfig, TlsConfig},
+        },
+    };
+
+    use super::TakStream;
+
+    async fn read_line<R: AsyncRead + Unpin>(reader: &mut BufReader<R>) -> String {
+        let mut line = String::new();
+        timeout(Duration::from_secs(5), reader.read_line(&mut line))
+            .await
+            .expect("Did not receive message in time")
+            .expect("Failed to read message");
+        line
+    }
+
+    #[tokio::test]
+    async fn test_tak_stream_delivers_messages_and_reconnects() {
+        // Arrange
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind TAK Server stand-in");
+        let config = TakServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(listener.local_addr().unwrap().port()),
+            tls: None,
+            protocol: TakProtocol::Xml,
+        };
+        let tak_stream = TakStream::start(config).expect("Failed to start TAK stream");
+
+        // Act
+        tak_stream.publish(build_event("1"));
+        let (connection, _) = timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("TAK stream did not connect in time")
+            .expect("Failed to accept TAK stream");
+        let mut reader = BufReader::new(connection);
+        let first = read_line(&mut reader).await;
+
+        // The server going away must not lose later messages
+        drop(reader);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        tak_stream.publish(build_event("2"));
+        let (connection, _) = timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("TAK stream did not reconnect in time")
+            .expect("Failed to accept TAK stream");
+        let second = read_line(&mut BufReader::new(connection)).await;
+
+        // Assert
+        assert_eq!(first, expected_line("1"));
+        assert_eq!(second, expected_line("2"));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_tak_stream_writes_queued_messages_then_closes() {
+        // Arrange
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind TAK Server stand-in");
+        let config = TakServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(listener.local_addr().unwrap().port()),
+            tls: None,
+            protocol: TakProtocol::Xml,
+        };
+        let tak_stream = TakStream::start(config).expect("Failed to start TAK stream");
+
+        // Act
+        tak_stream.publish(build_event("1"));
+        tak_stream.publish(build_event("2"));
+        drop(tak_stream);
+        let (connection, _) = timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("TAK stream did not connect in time")
+            .expect("Failed to accept TAK stream");
+        let mut reader = BufReader::new(connection);
+        let first = read_line(&mut reader).await;
+        let second = read_line(&mut reader).await;
+        let closed = read_line(&mut reader).await;
+
+        // Assert
+        assert_eq!(first, expected_line("1"));
+        assert_eq!(second, expected_line("2"));
+        assert_eq!(closed, "");
+    }
+
+    #[tokio::test]
+    async fn test_tak_stream_authenticates_with_client_certificate_over_tls() {
+        // Arrange
+        let directory = std::env::temp_dir().join(format!("hub-tak-tls-{}", std::process::id()));
+        fs::create_dir_all(&directory).expect("Failed to create certificate directory");
+
+        let ca_key = KeyPair::generate().unwrap();
+        let ca = build_ca(&ca_key);
+        let (server_certificate, server_key) = build_certificate(
+            &ca,
+            &ca_key,
+            "localhost",
+            ExtendedKeyUsagePurpose::ServerAuth,
+        );
+        let (client_certificate, client_key) =
+            build_certificate(&ca, &ca_key, "JTAC", ExtendedKeyUsagePurpose::ClientAuth);
+        let write = |name: &str, contents: String| -> PathBuf {
+            let path = directory.join(name);
+            fs::write(&path, contents).expect("Failed to write certificate");
+            path
+        };
+        let trust_store_path = write("ca.pem", ca.pem());
+        let certificate_path = write("client.pem", client_certificate.pem());
+        let key_path = write("client.key", client_key.serialize_pem());
+
+        let provider = Arc::new(ring::default_provider());
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.der().clone()).unwrap();
+        let client_verifier =
+            WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                .build()
+                .unwrap();
+        let server_config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_client_cert_verifier(client_verifier)
+            .with_single_cert(
+                vec![server_certificate.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(server_key.serialize_der())),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind TAK Server stand-in");
+
+        let config = TakServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(listener.local_addr().unwrap().port()),
+            tls: Some(TlsConfig {
+                certificate_path: certificate_path.to_string_lossy().into_owned(),
+                key_path: Some(key_path.to_string_lossy().into_owned()),
+                certificate_password: None,
+                trust_store_path: trust_store_path.to_string_lossy().into_owned(),
+                trust_store_password: None,
+                server_name: Some("localhost".to_string()),
+            }),
+            protocol: TakProtocol::Xml,
+        };
+
+        // Act
+        let tak_stream = TakStream::start(config).expect("Failed to start TAK stream");
+        tak_stream.publish(build_event("1"));
+        let (connection, _) = timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("TAK stream did not connect in time")
+            .expect("Failed to accept TAK stream");
+        let tls_connection = acceptor
+            .accept(connection)
+            .await
+            .expect("TLS handshake failed");
+        let has_client_certificate = tls_connection.get_ref().1.peer_certificates().is_some();
+        let message = read_line(&mut BufReader::new(tls_connection)).await;
+        fs::remove_dir_all(&directory).ok();
+
+        // Assert
+        assert!(has_client_certificate);
+        assert_eq!(message, expected_line("1"));
+    }
+
+    #[tokio::test]
+    async fn test_tak_stream_switches_to_protobuf_when_server_accepts() {
+        // Arrange
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind TAK Server stand-in");
+        let config = TakServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(listener.local_addr().unwrap().port()),
+            tls: None,
+            protocol: TakProtocol::Protobuf,
+        };
+        let tak_stream = TakStream::start(config).expect("Failed to start TAK stream");
+        let (connection, _) = timeout(Duration::from_secs(5), listener.accept())
+            .await
+            .expect("TAK stream did not connect in time")
+            .expect("Failed to accept TAK stream");
+        let mut reader = BufReader::new(connection);
+
+        // Act
+        tak_stream.publish(build_event("1"));
+        reader
+            .get_mut()
+            .write_all(br#"<?xml version="1.0"?><event version="2.0" uid="protouid" type="t-x-takp-v" how="m-g" time="2024-01-01T00:00:00Z" start="2024-01-01T00:00:00Z" stale="2024-01-01T00:01:00Z"><point lat="0.0" lon="0.0" hae="0.0" ce="999999" le="999999"/><detail><TakControl><TakProtocolSupport version="1"/></TakControl></detail></event>"#)
+            .await
+            .unwrap();
+        let mut request = Vec::new();
+        timeout(
+            Duration::from_secs(5),
+            reader.read_until(b'>', &mut request),
+        )
+        .await
+        .expect("Did not receive request in time")
+        .unwrap();
+        while !request.ends_with(b"</event>") {
+            reader.read_until(b'>', &mut request).await.unwrap();
+        }
+        reader
+            .get_mut()
+            .write_all(br#"<?xml version="1.0"?><event version="2.0" uid="protouid" type="t-x-takp-r" how="m-g" time="2024-01-01T00:00:00Z" start="2024-01-01T00:00:00Z" stale="2024-01-01T00:01:00Z"><point lat="0.0" lon="0.0" hae="0.0" ce="999999" le="999999"/><detail><TakControl><TakResponse status="true"/></TakControl></detail></event>"#)
+            .await
+            .unwrap();
+
+        let mut message = Vec::new();
+        let decoded = loop {
+            let mut read_buffer = [0; 1024];
+            let read = timeout(Duration::from_secs(5), reader.read(&mut read_buffer))
+                .await
+                .expect("Did not receive message in time")
+                .unwrap();
+            message.extend_from_slice(&read_buffer[..read]);
+            if let Ok(decoded) = TakMessage::decode_length_delimited(&message[1..]) {
+                break decoded;
+            }
+        };
+
+        // Assert
+        assert!(String::from_utf8_lossy(&request).contains(r#"type="t-x-takp-q""#));
+        assert_eq!(message[0], 0xbf);
+        assert_eq!(decoded.cot_event.unwrap().uid, "1");
+    }
+
+    fn build_event(uid: &str) -> Event {
+        Event {
+            unit_type: "a-f-G".to_string(),
+            how: "m-g".to_string(),
+            uid: uid.to_string(),
+            time: "2005-04-05T11:43:38Z".to_string(),
+            stale: "2005-04-05T11:44:38Z".to_string(),
+            point: Point {
+                lat: 42.0,
+                lon: 41.5,
+                hae: 120.0,
+            },
+            detail: Detail {
+                call_sign: Some(uid.to_string()),
+                track: None,
+                link: None,
+                remarks: None,
+            },
+        }
+    }
+
+    fn expected_line(uid: &str) -> String {
+        format!("{}\n", build_event(uid).to_xml().unwrap())
+    }
+
+    fn build_ca(key: &KeyPair) -> Certificate {
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "TAK Test CA");
+        params.self_signed(key).unwrap()
+    }
+
+    fn build_certificate(
+        ca: &Certificate,
+        ca_key: &KeyPair,
+        name: &str,
+        usage: ExtendedKeyUsagePurpose,
+    ) -> (Certificate, KeyPair) {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec![name.to_string()]).unwrap();
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.extended_key_usages = vec![usage];
+        (params.signed_by(&key, ca, ca_key).unwrap(), key)
+    }
+}
@@ -0,0 +1,133 @@
+use std::{
+    fs,
+    io::{self, BufReader, ErrorKind},
+    sync::Arc,
+};
+
+use p12_keystore::{KeyStore, KeyStoreEntry};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName},
+        ClientConfig, RootCertStore,
+    },
+    TlsConnector,
+};
+
+use super::tak_server_config::TlsConfig;
+
+/// Builds a connector authenticating with the client certificate of `config` and trusting the
+/// certificate authorities of its trust store.
+pub fn build_tls_connector(config: &TlsConfig) -> io::Result<TlsConnector> {
+    let mut roots = RootCertStore::empty();
+    for certificate in load_trust_store(config)? {
+        roots.add(certificate).map_err(invalid_data)?;
+    }
+
+    let (chain, key) = load_client_certificate(config)?;
+    let client_config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(invalid_data)?
+        .with_root_certificates(roots)
+        .with_client_auth_cert(chain, key)
+        .map_err(invalid_data)?;
+
+    Ok(TlsConnector::from(Arc::new(client_config)))
+}
+
+/// The name the server certificate is verified against.
+pub fn server_name(host: &str, config: &TlsConfig) -> io::Result<ServerName<'static>> {
+    let name = config.server_name.as_deref().unwrap_or(host);
+    ServerName::try_from(name.to_string()).map_err(invalid_data)
+}
+
+fn load_trust_store(config: &TlsConfig) -> io::Result<Vec<CertificateDer<'static>>> {
+    let certificates = if is_pkcs12(&config.trust_store_path) {
+        let key_store = read_pkcs12(
+            &config.trust_store_path,
+            config.trust_store_password.as_deref(),
+        )?;
+
+        let mut certificates = Vec::new();
+        for (_, entry) in key_store.entries() {
+            match entry {
+                KeyStoreEntry::Certificate(certificate) => certificates.push(certificate),
+                KeyStoreEntry::PrivateKeyChain(chain) => certificates.extend(chain.chain()),
+            }
+        }
+        certificates
+            .into_iter()
+            .map(|certificate| CertificateDer::from(certificate.as_der().to_vec()))
+            .collect()
+    } else {
+        read_pem_certificates(&config.trust_store_path)?
+    };
+
+    if certificates.is_empty() {
+        return Err(invalid_data(format!(
+            "No certificates found in trust store '{}'",
+            config.trust_store_path
+        )));
+    }
+
+    Ok(certificates)
+}
+
+fn load_client_certificate(
+    config: &TlsConfig,
+) -> io::Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+    if is_pkcs12(&config.certificate_path) {
+        let key_store = read_pkcs12(
+            &config.certificate_path,
+            config.certificate_password.as_deref(),
+        )?;
+        let (_, key_chain) = key_store.private_key_chain().ok_or_else(|| {
+            invalid_data(format!(
+                "No private key found in '{}'",
+                config.certificate_path
+            ))
+        })?;
+
+        let chain = key_chain
+            .chain()
+            .iter()
+            .map(|certificate| CertificateDer::from(certificate.as_der().to_vec()))
+            .collect();
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_chain.key().to_vec()));
+
+        return Ok((chain, key));
+    }
+
+    let chain = read_pem_certificates(&config.certificate_path)?;
+    let key_path = config
+        .key_path
+        .as_deref()
+        .unwrap_or(&config.certificate_path);
+    let key = rustls_pemfile::private_key(&mut BufReader::new(fs::File::open(key_path)?))?
+        .ok_or_else(|| invalid_data(format!("No private key found in '{}'", key_path)))?;
+
+    Ok((chain, key))
+}
+
+fn read_pem_certificates(file_path: &str) -> io::Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(fs::File::open(file_path)?);
+    rustls_pemfile::certs(&mut reader).collect()
+}
+
+fn read_pkcs12(file_path: &str, password: Option<&str>) -> io::Result<KeyStore> {
+    KeyStore::from_pkcs12(&fs::read(file_path)?, password.unwrap_or_default()).map_err(|err| {
+        invalid_data(format!(
+            "Failed to read PKCS#12 file '{}': {}",
+            file_path, err
+        ))
+    })
+}
+
+fn is_pkcs12(file_path: &str) -> bool {
+    let file_path = file_path.to_lowercase();
+    file_path.ends_with(".p12") || file_path.ends_with(".pfx")
+}
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, err.to_string())
+}
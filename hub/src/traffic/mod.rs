@@ -0,0 +1,4 @@
+pub mod movement;
+pub mod traffic_config;
+pub mod traffic_generator;
+pub mod traffic_sender;
@@ -0,0 +1,225 @@
+use std::f64::consts::{PI, TAU};
+
+use serde::{Deserialize, Serialize};
+
+/// Meters per degree of latitude, close enough for the few kilometers a scenario covers
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// A position relative to the anchor of a group, in meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalPosition {
+    /// Meters east of the anchor
+    pub east: f64,
+
+    /// Meters north of the anchor
+    pub north: f64,
+
+    /// The heading of the unit (in radians, clockwise from north)
+    pub heading: f64,
+}
+
+impl LocalPosition {
+    /// The latitude and longitude of the position, for an anchor at `latitude`, `longitude`.
+    pub fn to_lat_lon(self, latitude: f64, longitude: f64) -> (f64, f64) {
+        (
+            latitude + self.north / METERS_PER_DEGREE,
+            longitude + self.east / (METERS_PER_DEGREE * latitude.to_radians().cos()),
+        )
+    }
+}
+
+/// How the units of a group move around its anchor, tagged by `kind`, e.g.
+/// `{ kind = "orbit", radius_m = 5000.0, speed_mps = 120.0 }`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Movement {
+    /// Units standing in a line from west to east, e.g. a SAM site
+    Stationary {
+        #[serde(default = "default_spacing_m")]
+        spacing_m: f64,
+    },
+
+    /// Units spread evenly on a circle flown counterclockwise around the anchor
+    Orbit { radius_m: f64, speed_mps: f64 },
+
+    /// Units following each other on a straight road leaving the anchor
+    Convoy {
+        heading_deg: f64,
+        speed_mps: f64,
+        #[serde(default = "default_spacing_m")]
+        spacing_m: f64,
+    },
+
+    /// Units spread evenly on a racetrack pattern starting at the anchor, its straight legs
+    /// along `heading_deg` and its turns to the right
+    Racetrack {
+        heading_deg: f64,
+        length_m: f64,
+        turn_radius_m: f64,
+        speed_mps: f64,
+    },
+}
+
+fn default_spacing_m() -> f64 {
+    50.0
+}
+
+impl Movement {
+    /// Where unit `index` of a group of `count` units is, `elapsed` seconds after it spawned.
+    pub fn position(&self, elapsed: f64, index: usize, count: usize) -> LocalPosition {
+        match *self {
+            Movement::Stationary { spacing_m } => LocalPosition {
+                east: index as f64 * spacing_m,
+                north: 0.0,
+                heading: 0.0,
+            },
+            Movement::Orbit {
+                radius_m,
+                speed_mps,
+            } => {
+                let angle =
+                    TAU * index as f64 / count.max(1) as f64 + speed_mps * elapsed / radius_m;
+                LocalPosition {
+                    east: radius_m * angle.cos(),
+                    north: radius_m * angle.sin(),
+                    heading: normalize(f64::atan2(-angle.sin(), angle.cos())),
+                }
+            }
+            Movement::Convoy {
+                heading_deg,
+                speed_mps,
+                spacing_m,
+            } => {
+                let heading = heading_deg.to_radians();
+                let distance = speed_mps * elapsed - index as f64 * spacing_m;
+                LocalPosition {
+                    east: distance * heading.sin(),
+                    north: distance * heading.cos(),
+                    heading: normalize(heading),
+                }
+            }
+            Movement::Racetrack {
+                heading_deg,
+                length_m,
+                turn_radius_m,
+                speed_mps,
+            } => {
+                let perimeter = 2.0 * length_m + TAU * turn_radius_m;
+                let distance = (speed_mps * elapsed
+                    + perimeter * index as f64 / count.max(1) as f64)
+                    .rem_euclid(perimeter);
+                let (along, right, turned) = racetrack_point(distance, length_m, turn_radius_m);
+                let heading = heading_deg.to_radians();
+                LocalPosition {
+                    east: along * heading.sin() + right * heading.cos(),
+                    north: along * heading.cos() - right * heading.sin(),
+                    heading: normalize(heading + turned),
+                }
+            }
+        }
+    }
+}
+
+/// The point `distance` meters into the racetrack, as meters along and to the right of the
+/// first leg, and how far the unit turned from the heading of the first leg.
+fn racetrack_point(distance: f64, length: f64, radius: f64) -> (f64, f64, f64) {
+    let turn = PI * radius;
+
+    if distance < length {
+        return (distance, 0.0, 0.0);
+    }
+    if distance < length + turn {
+        let angle = (distance - length) / radius;
+        return (
+            length + radius * angle.sin(),
+            radius - radius * angle.cos(),
+            angle,
+        );
+    }
+    if distance < 2.0 * length + turn {
+        return (length - (distance - length - turn), 2.0 * radius, PI);
+    }
+
+    let angle = (distance - 2.0 * length - turn) / radius;
+    (
+        -radius * angle.sin(),
+        radius + radius * angle.cos(),
+        PI + angle,
+    )
+}
+
+fn normalize(heading: f64) -> f64 {
+    heading.rem_euclid(TAU)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::f64::consts::PI;
+
+    use super::Movement;
+
+    const EPSILON: f64 = 1e-6;
+
+    #[test]
+    fn given_orbit_when_positioned_then_units_stay_on_circle_heading_along_it() {
+        // Arrange
+        let orbit = Movement::Orbit {
+            radius_m: 1000.0,
+            speed_mps: 100.0,
+        };
+
+        // Act
+        let start = orbit.position(0.0, 0, 2);
+        let opposite = orbit.position(0.0, 1, 2);
+        let later = orbit.position(5.0, 0, 2);
+
+        // Assert
+        assert!((start.east - 1000.0).abs() < EPSILON && start.north.abs() < EPSILON);
+        assert!((start.heading - 0.0).abs() < EPSILON);
+        assert!((opposite.east + 1000.0).abs() < EPSILON);
+        assert!((later.east.hypot(later.north) - 1000.0).abs() < EPSILON);
+        assert!(later.north > 0.0);
+    }
+
+    #[test]
+    fn given_convoy_when_positioned_then_units_trail_the_leader() {
+        // Arrange
+        let convoy = Movement::Convoy {
+            heading_deg: 90.0,
+            speed_mps: 10.0,
+            spacing_m: 50.0,
+        };
+
+        // Act
+        let leader = convoy.position(10.0, 0, 3);
+        let last = convoy.position(10.0, 2, 3);
+
+        // Assert
+        assert!((leader.east - 100.0).abs() < EPSILON);
+        assert!((last.east - 0.0).abs() < EPSILON);
+        assert!((leader.heading - PI / 2.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn given_racetrack_when_positioned_then_units_come_back_after_a_lap() {
+        // Arrange
+        let racetrack = Movement::Racetrack {
+            heading_deg: 0.0,
+            length_m: 10_000.0,
+            turn_radius_m: 2000.0,
+            speed_mps: 100.0,
+        };
+        let lap = (2.0 * 10_000.0 + 2.0 * PI * 2000.0) / 100.0;
+
+        // Act
+        let start = racetrack.position(0.0, 0, 1);
+        let far_leg = racetrack.position(lap / 2.0, 0, 1);
+        let after_lap = racetrack.position(lap, 0, 1);
+
+        // Assert
+        assert!(start.east.abs() < EPSILON && start.north.abs() < EPSILON);
+        assert!((far_leg.east - 4000.0).abs() < EPSILON);
+        assert!((far_leg.heading - PI).abs() < EPSILON);
+        assert!(after_lap.east.abs() < 1e-3 && after_lap.north.abs() < 1e-3);
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{
+        dcs_unit::{Coalition, UnitType},
+        unit_type::Level1UnitType,
+    },
+    udp_listener::DCS_LISTENER_PORT,
+};
+
+use super::movement::Movement;
+
+/// A scenario of synthetic DCS export traffic.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TrafficConfig {
+    /// Address of the hub's DCS listener
+    #[serde(default = "default_target")]
+    pub target: SocketAddr,
+
+    /// Milliseconds between two export cycles
+    #[serde(default = "default_export_interval_ms")]
+    pub export_interval_ms: u64,
+
+    /// The largest datagram sent, larger frames being split as the export script does
+    #[serde(default = "default_max_datagram_size")]
+    pub max_datagram_size: usize,
+
+    /// Share of the frames preceded by a malformed record, from 0 to 1
+    #[serde(default)]
+    pub malformed_ratio: f64,
+
+    /// Seed of the malformed datagrams, for reproducible runs
+    #[serde(default)]
+    pub seed: u64,
+
+    /// The date of the mission
+    #[serde(default = "default_mission_date")]
+    pub mission_date: String,
+
+    /// The start time of the mission, in seconds since midnight
+    #[serde(default = "default_mission_start_time")]
+    pub mission_start_time: i32,
+
+    /// The groups of units taking part
+    pub groups: Vec<GroupConfig>,
+}
+
+/// Units of the same coalition and type, moving together.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct GroupConfig {
+    /// Name of the group, its units being named after it, e.g. `Convoy-1` and `Convoy-2`
+    pub name: String,
+
+    /// DCS coalition ID: 0 for neutral, 1 for REDFOR and 2 for BLUFOR
+    pub coalition: Coalition,
+
+    /// The DCS type of the units
+    pub unit_type: UnitType,
+
+    /// Number of units in the group
+    pub count: usize,
+
+    /// Latitude the group moves around
+    pub latitude: f64,
+
+    /// Longitude the group moves around
+    pub longitude: f64,
+
+    /// Altitude of the units, in meters above sea level
+    #[serde(default)]
+    pub altitude: f32,
+
+    /// How the units move
+    pub movement: Movement,
+
+    /// Mission time the group spawns at, in seconds since the start of the mission
+    #[serde(default)]
+    pub spawn_at: i32,
+
+    /// Mission time the group despawns at, if it does
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub despawn_at: Option<i32>,
+}
+
+fn default_target() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, DCS_LISTENER_PORT))
+}
+
+fn default_export_interval_ms() -> u64 {
+    1000
+}
+
+/// As the export script
+fn default_max_datagram_size() -> usize {
+    8192
+}
+
+fn default_mission_date() -> String {
+    "2024-03-08".to_string()
+}
+
+fn default_mission_start_time() -> i32 {
+    8 * 3600
+}
+
+impl Default for TrafficConfig {
+    /// A convoy, a SAM site, fighters on CAP and racetrack, a ship, and a patrol that despawns
+    /// after ten minutes, around Batumi
+    fn default() -> Self {
+        TrafficConfig {
+            target: default_target(),
+            export_interval_ms: default_export_interval_ms(),
+            max_datagram_size: default_max_datagram_size(),
+            malformed_ratio: 0.0,
+            seed: 0,
+            mission_date: default_mission_date(),
+            mission_start_time: default_mission_start_time(),
+            groups: vec![
+                GroupConfig {
+                    name: "Convoy".to_string(),
+                    coalition: Coalition::BLUFOR,
+                    unit_type: unit_type(Level1UnitType::GROUND, 17, 26, "M1126 Stryker ICV"),
+                    count: 8,
+                    latitude: 41.60,
+                    longitude: 41.62,
+                    altitude: 10.0,
+                    movement: Movement::Convoy {
+                        heading_deg: 60.0,
+                        speed_mps: 12.0,
+                        spacing_m: 40.0,
+                    },
+                    spawn_at: 0,
+                    despawn_at: None,
+                },
+                GroupConfig {
+                    name: "SAM".to_string(),
+                    coalition: Coalition::REDFOR,
+                    unit_type: unit_type(Level1UnitType::GROUND, 16, 27, "SA-11 Buk LN 9A310M1"),
+                    count: 6,
+                    latitude: 41.75,
+                    longitude: 41.85,
+                    altitude: 300.0,
+                    movement: Movement::Stationary { spacing_m: 120.0 },
+                    spawn_at: 0,
+                    despawn_at: None,
+                },
+                GroupConfig {
+                    name: "CAP".to_string(),
+                    coalition: Coalition::BLUFOR,
+                    unit_type: unit_type(Level1UnitType::AIR, 1, 1, "F-16C_50"),
+                    count: 2,
+                    latitude: 41.55,
+                    longitude: 41.70,
+                    altitude: 7500.0,
+                    movement: Movement::Orbit {
+                        radius_m: 15_000.0,
+                        speed_mps: 220.0,
+                    },
+                    spawn_at: 0,
+                    despawn_at: None,
+                },
+                GroupConfig {
+                    name: "Flanker".to_string(),
+                    coalition: Coalition::REDFOR,
+                    unit_type: unit_type(Level1UnitType::AIR, 1, 1, "Su-27"),
+                    count: 2,
+                    latitude: 42.00,
+                    longitude: 42.10,
+                    altitude: 9000.0,
+                    movement: Movement::Racetrack {
+                        heading_deg: 300.0,
+                        length_m: 40_000.0,
+                        turn_radius_m: 6000.0,
+                        speed_mps: 240.0,
+                    },
+                    spawn_at: 0,
+                    despawn_at: None,
+                },
+                GroupConfig {
+                    name: "Freighter".to_string(),
+                    coalition: Coalition::NEUTRAL,
+                    unit_type: unit_type(Level1UnitType::SEA, 12, 0, "Dry-cargo ship-1"),
+                    count: 1,
+                    latitude: 41.50,
+                    longitude: 41.40,
+                    altitude: 0.0,
+                    movement: Movement::Convoy {
+                        heading_deg: 200.0,
+                        speed_mps: 6.0,
+                        spacing_m: 500.0,
+                    },
+                    spawn_at: 0,
+                    despawn_at: None,
+                },
+                GroupConfig {
+                    name: "Patrol".to_string(),
+                    coalition: Coalition::REDFOR,
+                    unit_type: unit_type(Level1UnitType::GROUND, 17, 25, "BTR-80"),
+                    count: 4,
+                    latitude: 41.70,
+                    longitude: 41.75,
+                    altitude: 150.0,
+                    movement: Movement::Orbit {
+                        radius_m: 800.0,
+                        speed_mps: 8.0,
+                    },
+                    spawn_at: 60,
+                    despawn_at: Some(660),
+                },
+            ],
+        }
+    }
+}
+
+fn unit_type(level_1: Level1UnitType, level_2: u8, level_3: u8, type_name: &str) -> UnitType {
+    UnitType {
+        level_1,
+        level_2,
+        level_3,
+        level_4: 0,
+        type_name: type_name.to_string(),
+    }
+}
+
+impl TrafficConfig {
+    /// Loads a scenario from a TOML file.
+    pub fn from_file(file_path: &str) -> io::Result<TrafficConfig> {
+        toml::from_str(&fs::read_to_string(file_path)?)
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /// The scenario as written to a TOML file.
+    pub fn to_toml(&self) -> io::Result<String> {
+        toml::to_string(self).map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+
+    /// Multiplies the number of units of every group by `factor`, e.g. for load tests.
+    pub fn scale(&mut self, factor: usize) {
+        for group in &mut self.groups {
+            group.count *= factor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::TrafficConfig;
+
+    #[test]
+    fn given_default_scenario_when_written_as_toml_then_it_reads_back() {
+        // Arrange
+        let config = TrafficConfig::default();
+
+        // Act
+        let toml = config.to_toml().expect("Failed to write scenario");
+        let result: TrafficConfig = toml::from_str(&toml).expect("Failed to read scenario");
+
+        // Assert
+        assert_eq!(result, config);
+    }
+}
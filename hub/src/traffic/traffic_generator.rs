@@ -0,0 +1,143 @@
+use crate::common::{
+    dcs_frame::{DcsFrame, FrameHeader, FrameUnit, DCS_FRAME_VERSION},
+    dcs_unit::Position3D,
+};
+
+use super::traffic_config::{GroupConfig, TrafficConfig};
+
+/// Builds the frames the export script would send for a scenario.
+pub struct TrafficGenerator {
+    config: TrafficConfig,
+    sequence: u32,
+}
+
+impl TrafficGenerator {
+    pub fn new(config: TrafficConfig) -> TrafficGenerator {
+        TrafficGenerator {
+            config,
+            sequence: 0,
+        }
+    }
+
+    /// The frame of the next export cycle, the mission clock advancing by the export interval
+    /// every cycle.
+    pub fn next_frame(&mut self) -> DcsFrame {
+        let elapsed_ms = self.sequence as u64 * self.config.export_interval_ms;
+        let frame = self.frame_at(self.sequence, (elapsed_ms / 1000) as i32);
+        self.sequence = self.sequence.wrapping_add(1);
+
+        frame
+    }
+
+    /// The frame numbered `sequence`, carrying the units alive `mission_time_elapsed` seconds
+    /// into the mission.
+    pub fn frame_at(&self, sequence: u32, mission_time_elapsed: i32) -> DcsFrame {
+        let units = self
+            .config
+            .groups
+            .iter()
+            .filter(|group| is_alive(group, mission_time_elapsed))
+            .flat_map(|group| group_units(group, mission_time_elapsed))
+            .collect();
+
+        DcsFrame {
+            version: DCS_FRAME_VERSION,
+            header: FrameHeader {
+                sequence,
+                mission_date: self.config.mission_date.clone(),
+                mission_start_time: self.config.mission_start_time,
+                mission_time_elapsed,
+            },
+            units,
+        }
+    }
+}
+
+fn is_alive(group: &GroupConfig, mission_time_elapsed: i32) -> bool {
+    mission_time_elapsed >= group.spawn_at
+        && group
+            .despawn_at
+            .is_none_or(|despawn_at| mission_time_elapsed < despawn_at)
+}
+
+fn group_units(
+    group: &GroupConfig,
+    mission_time_elapsed: i32,
+) -> impl Iterator<Item = FrameUnit> + '_ {
+    let elapsed = (mission_time_elapsed - group.spawn_at) as f64;
+
+    (0..group.count).map(move |index| {
+        let position = group.movement.position(elapsed, index, group.count);
+        let (latitude, longitude) = position.to_lat_lon(group.latitude, group.longitude);
+
+        FrameUnit {
+            unit_name: format!("{}-{}", group.name, index + 1),
+            group_name: group.name.clone(),
+            coalition: group.coalition,
+            position: Position3D {
+                latitude,
+                longitude,
+                altitude: group.altitude,
+                heading: position.heading,
+            },
+            unit_type: group.unit_type.clone(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use crate::traffic::traffic_config::TrafficConfig;
+
+    use super::TrafficGenerator;
+
+    #[test]
+    fn given_default_scenario_when_patrol_despawns_then_its_units_leave_the_frame() {
+        // Arrange
+        let config = TrafficConfig::default();
+        let total: usize = config.groups.iter().map(|group| group.count).sum();
+        let patrol = config
+            .groups
+            .iter()
+            .find(|group| group.name == "Patrol")
+            .unwrap()
+            .clone();
+        let generator = TrafficGenerator::new(config);
+
+        // Act
+        let before_spawn = generator.frame_at(0, 0);
+        let spawned = generator.frame_at(1, patrol.spawn_at);
+        let despawned = generator.frame_at(2, patrol.despawn_at.unwrap());
+
+        // Assert
+        assert_eq!(before_spawn.units.len(), total - patrol.count);
+        assert_eq!(spawned.units.len(), total);
+        assert!(spawned
+            .units
+            .iter()
+            .any(|unit| unit.unit_name == "Patrol-4"));
+        assert_eq!(despawned.units.len(), total - patrol.count);
+        assert_eq!(despawned.header.sequence, 2);
+    }
+
+    #[test]
+    fn given_scaled_scenario_when_next_frame_then_clock_follows_export_interval() {
+        // Arrange
+        let mut config = TrafficConfig {
+            export_interval_ms: 500,
+            ..TrafficConfig::default()
+        };
+        config.groups.retain(|group| group.name == "Convoy");
+        config.scale(250);
+        let mut generator = TrafficGenerator::new(config);
+
+        // Act
+        let frames: Vec<_> = (0..3).map(|_| generator.next_frame()).collect();
+
+        // Assert
+        assert_eq!(frames[0].units.len(), 2000);
+        assert_eq!(frames[2].header.sequence, 2);
+        assert_eq!(frames[2].header.mission_time_elapsed, 1);
+        assert_eq!(frames[0].units[1999].unit_name, "Convoy-2000");
+    }
+}
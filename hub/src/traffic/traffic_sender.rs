@@ -0,0 +1,256 @@
+use std::{
+    io,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use log::{info, warn};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::{
+    net::UdpSocket,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::{
+    common::dcs_frame::DcsFrame,
+    udp_listener::{DCS_MAX_RECORD_SIZE, DCS_MSG_DELIMITER},
+};
+
+use super::{traffic_config::TrafficConfig, traffic_generator::TrafficGenerator};
+
+/// How often the counts of what was sent are logged
+pub const TRAFFIC_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Running counts of what the sender has sent.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TrafficStats {
+    /// Frames sent
+    pub frames: u64,
+
+    /// Units carried by the frames
+    pub units: u64,
+
+    /// Datagrams sent, malformed ones included
+    pub datagrams: u64,
+
+    /// Malformed records sent
+    pub malformed: u64,
+
+    /// Frames sent as records larger than the hub accepts, which it drops
+    pub oversized: u64,
+}
+
+/// Sends frames to the hub the way the export script does.
+pub struct TrafficSender {
+    socket: UdpSocket,
+    max_datagram_size: usize,
+    malformed_ratio: f64,
+    rng: StdRng,
+    stats: TrafficStats,
+}
+
+impl TrafficSender {
+    /// Creates a sender to the target of the scenario.
+    pub async fn connect(config: &TrafficConfig) -> io::Result<TrafficSender> {
+        let local_address = match config.target {
+            SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+        };
+        let socket = UdpSocket::bind(local_address).await?;
+        socket.connect(config.target).await?;
+
+        Ok(TrafficSender {
+            socket,
+            max_datagram_size: config.max_datagram_size.max(1),
+            malformed_ratio: config.malformed_ratio.clamp(0.0, 1.0),
+            rng: StdRng::seed_from_u64(config.seed),
+            stats: TrafficStats::default(),
+        })
+    }
+
+    /// Sends a frame, preceded by a malformed record for the configured share of the frames.
+    pub async fn send_frame(&mut self, frame: &DcsFrame) -> io::Result<()> {
+        let record = to_record(frame)?;
+        // The delimiter does not count towards the size of a record
+        if record.len() - 1 > DCS_MAX_RECORD_SIZE {
+            if self.stats.oversized == 0 {
+                warn!(
+                    "Frames of {} units are {} bytes, more than the {} bytes the hub accepts; \
+                     it will drop them",
+                    frame.units.len(),
+                    record.len() - 1,
+                    DCS_MAX_RECORD_SIZE
+                );
+            }
+            self.stats.oversized += 1;
+        }
+
+        if self.rng.gen_bool(self.malformed_ratio) {
+            let malformed = malformed_record(&mut self.rng, &record);
+            self.send_record(&malformed).await?;
+            self.stats.malformed += 1;
+        }
+
+        self.send_record(&record).await?;
+        self.stats.frames += 1;
+        self.stats.units += frame.units.len() as u64;
+
+        Ok(())
+    }
+
+    pub fn stats(&self) -> TrafficStats {
+        self.stats
+    }
+
+    /// Sends a record in datagram-sized chunks; the hub reassembles them up to the trailing
+    /// newline.
+    async fn send_record(&mut self, record: &[u8]) -> io::Result<()> {
+        for datagram in record.chunks(self.max_datagram_size) {
+            self.socket.send(datagram).await?;
+            self.stats.datagrams += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// The frame as a newline-terminated JSON record.
+fn to_record(frame: &DcsFrame) -> io::Result<Vec<u8>> {
+    let mut record = serde_json::to_vec(frame)?;
+    record.push(DCS_MSG_DELIMITER);
+
+    Ok(record)
+}
+
+/// A newline-terminated record the hub cannot decode: either `record` cut short, as if
+/// datagrams were lost, or random bytes.
+fn malformed_record(rng: &mut StdRng, record: &[u8]) -> Vec<u8> {
+    let json = &record[..record.len() - 1];
+    let mut malformed: Vec<u8> = if rng.gen_bool(0.5) && json.len() > 1 {
+        json[..rng.gen_range(1..json.len())].to_vec()
+    } else {
+        (0..rng.gen_range(1..256))
+            .map(|_| rng.gen_range(b'!'..=b'~'))
+            .collect()
+    };
+    malformed.push(DCS_MSG_DELIMITER);
+
+    malformed
+}
+
+/// Sends the traffic of a scenario every export interval, for `cycles` export cycles or until
+/// stopped, and returns what was sent.
+pub async fn run(config: TrafficConfig, cycles: Option<u64>) -> io::Result<TrafficStats> {
+    let mut sender = TrafficSender::connect(&config).await?;
+    let mut interval = time::interval(Duration::from_millis(config.export_interval_ms.max(1)));
+    // A slow send delays the next cycles, rather than bursting them to catch up as DCS never does
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let target = config.target;
+    let mut generator = TrafficGenerator::new(config);
+    let mut last_report = Instant::now();
+
+    info!("Sending traffic to {}", target);
+
+    while cycles.is_none_or(|cycles| sender.stats().frames < cycles) {
+        interval.tick().await;
+        sender.send_frame(&generator.next_frame()).await?;
+
+        if last_report.elapsed() >= TRAFFIC_REPORT_INTERVAL {
+            report(&sender.stats());
+            last_report = Instant::now();
+        }
+    }
+
+    report(&sender.stats());
+    Ok(sender.stats())
+}
+
+fn report(stats: &TrafficStats) {
+    info!(
+        "Sent {} frames of {} units in {} datagrams, with {} malformed records and {} oversized \
+         frames",
+        stats.frames, stats.units, stats.datagrams, stats.malformed, stats.oversized
+    );
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::time::Duration;
+
+    use tokio::{net::UdpSocket, time};
+
+    use crate::{
+        common::dcs_frame::DcsFrame,
+        traffic::traffic_config::TrafficConfig,
+        udp_listener::{DCS_MAX_RECORD_SIZE, DCS_MSG_DELIMITER},
+    };
+
+    use super::run;
+
+    #[tokio::test]
+    async fn test_send_chunked_frames_with_malformed_records() {
+        let receiver = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind receiver");
+        let config = TrafficConfig {
+            target: receiver.local_addr().unwrap(),
+            export_interval_ms: 10,
+            max_datagram_size: 512,
+            malformed_ratio: 1.0,
+            ..TrafficConfig::default()
+        };
+
+        let stats = run(config, Some(2)).await.expect("Failed to send traffic");
+
+        let mut bytes = Vec::new();
+        let mut buffer = [0u8; 1024];
+        for _ in 0..stats.datagrams {
+            let size = time::timeout(Duration::from_secs(5), receiver.recv(&mut buffer))
+                .await
+                .expect("Timed out waiting for traffic")
+                .expect("Failed to receive traffic");
+            assert!(size <= 512);
+            bytes.extend_from_slice(&buffer[..size]);
+        }
+        let records: Vec<_> = bytes
+            .split(|&byte| byte == DCS_MSG_DELIMITER)
+            .filter(|record| !record.is_empty())
+            .collect();
+
+        assert_eq!(stats.frames, 2);
+        assert_eq!(stats.malformed, 2);
+        assert_eq!(stats.oversized, 0);
+        assert!(stats.datagrams > 4);
+        assert_eq!(records.len(), 4);
+        for (index, record) in records.iter().enumerate() {
+            let frame = serde_json::from_slice::<DcsFrame>(record);
+            if index % 2 == 0 {
+                assert!(frame.is_err());
+            } else {
+                let frame = frame.expect("Invalid frame");
+                assert_eq!(frame.header.sequence, (index / 2) as u32);
+                assert_eq!(frame.units.len() as u64, stats.units / 2);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_frames_larger_than_hub_accepts_are_counted() {
+        let receiver = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind receiver");
+        let mut config = TrafficConfig {
+            target: receiver.local_addr().unwrap(),
+            max_datagram_size: 60_000,
+            ..TrafficConfig::default()
+        };
+        config.groups.truncate(1);
+        config.groups[0].count = 10_000;
+
+        let stats = run(config, Some(1)).await.expect("Failed to send traffic");
+
+        assert_eq!(stats.frames, 1);
+        assert_eq!(stats.oversized, 1);
+        assert!(stats.datagrams * 60_000 > DCS_MAX_RECORD_SIZE as u64);
+    }
+}
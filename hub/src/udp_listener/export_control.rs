@@ -0,0 +1,155 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{dcs_unit::Coalition, unit_type::Level1UnitType},
+    user_config::user_config::UserConfig,
+};
+
+use super::DCS_MSG_DELIMITER;
+
+pub const EXPORT_CONTROL_VERSION: u32 = 1;
+
+/// How long the settings last sent to an export script are trusted to still be in effect, in
+/// case the datagram was lost or the script restarted
+pub const EXPORT_CONTROL_RESEND_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Settings the hub pushes back to the DCS export script, so units the hub would discard are not
+/// exported in the first place. Sent as a single flat JSON record the script can read without a
+/// JSON library.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ExportSettings {
+    /// Version of the control record, records of other versions are ignored by the script
+    pub version: u32,
+
+    /// The number of DCS frames between two exports
+    pub export_frequency_frames: i32,
+
+    /// DCS coalition IDs of the units to export
+    pub coalition_ids: Vec<u8>,
+
+    /// Level 1 DCS unit type IDs of the units to export
+    pub unit_type_ids: Vec<u8>,
+}
+
+impl ExportSettings {
+    /// The export frequency and the coalitions and unit types the hub is configured for.
+    pub fn from_config(user_config: &UserConfig) -> ExportSettings {
+        let coalitions = [Coalition::NEUTRAL, Coalition::REDFOR, Coalition::BLUFOR];
+        let unit_types = [
+            Level1UnitType::AIR,
+            Level1UnitType::GROUND,
+            Level1UnitType::SEA,
+        ];
+
+        ExportSettings {
+            version: EXPORT_CONTROL_VERSION,
+            export_frequency_frames: user_config.export_frequency_frames,
+            coalition_ids: coalitions
+                .into_iter()
+                .filter(|coalition| user_config.coalition_flag.includes(*coalition))
+                .map(|coalition| coalition as u8)
+                .collect(),
+            unit_type_ids: unit_types
+                .into_iter()
+                .filter(|unit_type| user_config.unit_type_flag.includes(*unit_type))
+                .map(|unit_type| unit_type as u8)
+                .collect(),
+        }
+    }
+
+    /// The newline-terminated record sent to the script.
+    pub fn to_record(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut record = serde_json::to_vec(self)?;
+        record.push(DCS_MSG_DELIMITER);
+
+        Ok(record)
+    }
+}
+
+/// Keeps track of the settings sent to each export script the hub receives from.
+#[derive(Default)]
+pub struct ExportControl {
+    sent: HashMap<SocketAddr, (ExportSettings, Instant)>,
+}
+
+impl ExportControl {
+    /// Whether `settings` should be sent to the script at `exporter`, because they changed or
+    /// were not sent for `EXPORT_CONTROL_RESEND_INTERVAL`. They are assumed to be sent if so.
+    pub fn should_send(
+        &mut self,
+        exporter: SocketAddr,
+        settings: &ExportSettings,
+        now: Instant,
+    ) -> bool {
+        let is_current = self.sent.get(&exporter).is_some_and(|(sent, sent_at)| {
+            sent == settings && now.duration_since(*sent_at) < EXPORT_CONTROL_RESEND_INTERVAL
+        });
+        if is_current {
+            return false;
+        }
+
+        self.sent.insert(exporter, (settings.clone(), now));
+        true
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::{net::SocketAddr, time::Instant};
+
+    use crate::user_config::{
+        coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag, user_config::UserConfig,
+    };
+
+    use super::{ExportControl, ExportSettings, EXPORT_CONTROL_RESEND_INTERVAL};
+
+    #[test]
+    fn given_user_config_when_converted_then_dcs_ids_are_listed() {
+        // Arrange
+        let user_config = UserConfig {
+            coalition_flag: CoalitionFlag::REDFOR | CoalitionFlag::BLUFOR,
+            unit_type_flag: UnitTypeFlag::SEA,
+            export_frequency_frames: 50,
+            ..Default::default()
+        };
+
+        // Act
+        let settings = ExportSettings::from_config(&user_config);
+        let record = settings.to_record().unwrap();
+
+        // Assert
+        assert_eq!(
+            String::from_utf8(record).unwrap(),
+            "{\"version\":1,\"export_frequency_frames\":50,\"coalition_ids\":[1,2],\"unit_type_ids\":[3]}\n"
+        );
+    }
+
+    #[test]
+    fn given_sent_settings_when_unchanged_then_they_are_only_resent_after_interval() {
+        // Arrange
+        let mut control = ExportControl::default();
+        let exporter: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let other_exporter: SocketAddr = "127.0.0.1:50001".parse().unwrap();
+        let settings = ExportSettings::from_config(&UserConfig::default());
+        let changed = ExportSettings {
+            export_frequency_frames: 10,
+            ..settings.clone()
+        };
+        let now = Instant::now();
+
+        // Act & Assert
+        assert!(control.should_send(exporter, &settings, now));
+        assert!(!control.should_send(exporter, &settings, now));
+        assert!(control.should_send(other_exporter, &settings, now));
+        assert!(control.should_send(exporter, &changed, now));
+        assert!(!control.should_send(exporter, &changed, now));
+        assert!(control.should_send(exporter, &changed, now + EXPORT_CONTROL_RESEND_INTERVAL));
+    }
+}
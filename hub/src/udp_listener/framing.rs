@@ -0,0 +1,246 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use super::DCS_MSG_DELIMITER;
+
+/// Running counts of what the framing layer has seen.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FramingStats {
+    /// Datagrams received from all senders
+    pub datagrams: u64,
+
+    /// Complete records extracted from the datagrams
+    pub records: u64,
+
+    /// Records dropped for exceeding the maximum record size
+    pub truncated: u64,
+
+    /// Records that could not be decoded
+    pub malformed: u64,
+}
+
+/// Partially received record for a single sender.
+struct PartialRecord {
+    bytes: Vec<u8>,
+
+    /// Set once the record has overflowed; bytes are skipped until the next delimiter.
+    overflowed: bool,
+
+    /// When the last datagram of the record was received
+    received_at: Instant,
+}
+
+impl Default for PartialRecord {
+    fn default() -> Self {
+        PartialRecord {
+            bytes: Vec::new(),
+            overflowed: false,
+            received_at: Instant::now(),
+        }
+    }
+}
+
+/// Splits UDP datagrams into newline-delimited records, buffering any trailing partial record
+/// per sender until the rest of it arrives.
+pub struct RecordAssembler {
+    partial_by_sender: HashMap<SocketAddr, PartialRecord>,
+    max_record_size: usize,
+    stats: FramingStats,
+}
+
+impl RecordAssembler {
+    /// Creates a new `RecordAssembler` that drops any record longer than `max_record_size` bytes.
+    pub fn new(max_record_size: usize) -> RecordAssembler {
+        RecordAssembler {
+            partial_by_sender: HashMap::new(),
+            max_record_size,
+            stats: FramingStats::default(),
+        }
+    }
+
+    /// Appends a datagram received from `sender` and returns every record it completed.
+    pub fn push(&mut self, sender: SocketAddr, datagram: &[u8]) -> Vec<String> {
+        self.stats.datagrams += 1;
+
+        let partial = self.partial_by_sender.entry(sender).or_default();
+        partial.received_at = Instant::now();
+        let mut records = Vec::new();
+        let mut chunks = datagram.split(|&byte| byte == DCS_MSG_DELIMITER).peekable();
+
+        while let Some(chunk) = chunks.next() {
+            let is_complete = chunks.peek().is_some();
+
+            if !partial.overflowed {
+                if partial.bytes.len() + chunk.len() > self.max_record_size {
+                    partial.bytes.clear();
+                    partial.overflowed = true;
+                    self.stats.truncated += 1;
+                } else {
+                    partial.bytes.extend_from_slice(chunk);
+                }
+            }
+
+            if !is_complete {
+                break;
+            }
+
+            let bytes = std::mem::take(&mut partial.bytes);
+            if partial.overflowed {
+                partial.overflowed = false;
+                continue;
+            }
+
+            if bytes.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            match String::from_utf8(bytes) {
+                Ok(record) => {
+                    self.stats.records += 1;
+                    records.push(record);
+                }
+                Err(_) => self.stats.malformed += 1,
+            }
+        }
+
+        if partial.bytes.is_empty() && !partial.overflowed {
+            self.partial_by_sender.remove(&sender);
+        }
+
+        records
+    }
+
+    /// Drops the partial records of senders that sent nothing for `max_age` by `now`, e.g. an
+    /// export stopped in the middle of a record.
+    pub fn expire(&mut self, now: Instant, max_age: Duration) {
+        self.partial_by_sender
+            .retain(|_, partial| now.saturating_duration_since(partial.received_at) < max_age);
+    }
+
+    /// Counts a record that was framed correctly but could not be decoded.
+    pub fn record_malformed(&mut self) {
+        self.stats.malformed += 1;
+    }
+
+    /// Returns the running framing counts.
+    pub fn stats(&self) -> FramingStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::{
+        net::SocketAddr,
+        time::{Duration, Instant},
+    };
+
+    use super::{FramingStats, RecordAssembler};
+
+    fn sender(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn given_datagram_with_several_records_when_pushed_then_all_records_are_returned() {
+        // Arrange
+        let mut assembler = RecordAssembler::new(1024);
+
+        // Act
+        let result = assembler.push(sender(1), b"{\"a\":1}\n{\"b\":2}\n{\"c\":3}\n");
+
+        // Assert
+        assert_eq!(result, vec![r#"{"a":1}"#, r#"{"b":2}"#, r#"{"c":3}"#]);
+    }
+
+    #[test]
+    fn given_record_split_across_datagrams_when_pushed_then_record_is_reassembled() {
+        // Arrange
+        let mut assembler = RecordAssembler::new(1024);
+
+        // Act
+        let first = assembler.push(sender(1), b"{\"a\":1}\n{\"long");
+        let second = assembler.push(sender(1), b"_name\":");
+        let third = assembler.push(sender(1), b"2}\n");
+
+        // Assert
+        assert_eq!(first, vec![r#"{"a":1}"#]);
+        assert!(second.is_empty());
+        assert_eq!(third, vec![r#"{"long_name":2}"#]);
+    }
+
+    #[test]
+    fn given_interleaved_senders_when_pushed_then_partial_records_are_kept_per_sender() {
+        // Arrange
+        let mut assembler = RecordAssembler::new(1024);
+
+        // Act
+        assembler.push(sender(1), b"{\"from\":");
+        assembler.push(sender(2), b"{\"from\":");
+        let from_second = assembler.push(sender(2), b"2}\n");
+        let from_first = assembler.push(sender(1), b"1}\n");
+
+        // Assert
+        assert_eq!(from_first, vec![r#"{"from":1}"#]);
+        assert_eq!(from_second, vec![r#"{"from":2}"#]);
+    }
+
+    #[test]
+    fn given_record_exceeding_max_size_when_pushed_then_record_is_dropped_and_counted() {
+        // Arrange
+        let mut assembler = RecordAssembler::new(8);
+
+        // Act
+        let first = assembler.push(sender(1), b"0123456789");
+        let second = assembler.push(sender(1), b"abc\n{}\n");
+
+        // Assert
+        assert!(first.is_empty());
+        assert_eq!(second, vec!["{}"]);
+        assert_eq!(
+            assembler.stats(),
+            FramingStats {
+                datagrams: 2,
+                records: 1,
+                truncated: 1,
+                malformed: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn given_invalid_utf8_record_when_pushed_then_record_is_counted_as_malformed() {
+        // Arrange
+        let mut assembler = RecordAssembler::new(1024);
+
+        // Act
+        let result = assembler.push(sender(1), b"\xff\xfe\n{}\n");
+
+        // Assert
+        assert_eq!(result, vec!["{}"]);
+        assert_eq!(assembler.stats().malformed, 1);
+    }
+
+    #[test]
+    fn given_sender_silent_mid_record_when_expired_then_partial_record_is_dropped() {
+        // Arrange
+        let mut assembler = RecordAssembler::new(1024);
+        assembler.push(sender(1), b"{\"stale");
+        assembler.push(sender(2), b"{\"b\":");
+
+        // Act
+        assembler.expire(
+            Instant::now() + Duration::from_secs(120),
+            Duration::from_secs(60),
+        );
+        let first = assembler.push(sender(1), b"{\"a\":1}\n");
+        let second = assembler.push(sender(2), b"2}\n");
+
+        // Assert
+        assert_eq!(first, vec![r#"{"a":1}"#]);
+        assert_eq!(second, vec!["2}"]);
+    }
+}
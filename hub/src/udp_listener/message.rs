@@ -0,0 +1,73 @@
+use std::error::Error;
+
+use serde::Deserialize;
+
+use crate::common::{
+    dcs_frame::{DcsFrame, DCS_FRAME_VERSION},
+    dcs_unit::DcsUnit,
+};
+
+/// A decoded record of the DCS export.
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum DcsMessage {
+    /// All units of one export cycle sharing a mission clock
+    Frame(DcsFrame),
+
+    /// A single unit, as sent by export scripts predating frames
+    Unit(DcsUnit),
+}
+
+/// Decodes a single newline-delimited record of the DCS export.
+pub fn parse_record(record: &str) -> Result<DcsMessage, Box<dyn Error>> {
+    let message = serde_json::from_str::<DcsMessage>(record)?;
+
+    if let DcsMessage::Frame(frame) = &message {
+        if frame.version != DCS_FRAME_VERSION {
+            return Err(format!("Unsupported frame version {}", frame.version).into());
+        }
+    }
+
+    Ok(message)
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use super::{parse_record, DcsMessage};
+
+    #[test]
+    fn given_legacy_unit_record_when_parsed_then_unit_is_returned() {
+        // Arrange
+        let record = r#"{"unit_name":"UNIT-1","group_name":"GROUP-1","coalition":2,"position":{"latitude":30.0,"longitude":-85.9,"altitude":132.67,"heading":2.0},"unit_type":{"level_1":1,"level_2":1},"mission_date":"2024-03-08","mission_start_time":28800,"mission_time_elapsed":3600}"#;
+
+        // Act
+        let result = parse_record(record).expect("Failed to parse record");
+
+        // Assert
+        assert!(matches!(result, DcsMessage::Unit(unit) if unit.unit_name == "UNIT-1"));
+    }
+
+    #[test]
+    fn given_frame_record_when_parsed_then_frame_is_returned() {
+        // Arrange
+        let record = r#"{"version":1,"header":{"sequence":3,"mission_date":"2024-03-08","mission_start_time":28800,"mission_time_elapsed":3600},"units":[]}"#;
+
+        // Act
+        let result = parse_record(record).expect("Failed to parse record");
+
+        // Assert
+        assert!(matches!(result, DcsMessage::Frame(frame) if frame.header.sequence == 3));
+    }
+
+    #[test]
+    fn given_frame_with_unsupported_version_when_parsed_then_returns_error() {
+        // Arrange
+        let record = r#"{"version":99,"header":{"sequence":3,"mission_date":"2024-03-08","mission_start_time":28800,"mission_time_elapsed":3600},"units":[]}"#;
+
+        // Act
+        let result = parse_record(record);
+
+        // Assert
+        assert!(result.is_err());
+    }
+}
@@ -1,109 +1,255 @@
-use std::error::Error;
+pub mod export_control;
+mod framing;
+mod message;
+mod sequence;
+
+use std::{
+    error::Error,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+use log::{debug, info, warn};
 use tokio::net::UdpSocket;
 
-use crate::common::dcs_unit::DcsUnit;
+use crate::common::{dcs_unit::DcsUnit, socket::bind_udp_socket};
+
+use self::{
+    export_control::{ExportControl, ExportSettings},
+    framing::{FramingStats, RecordAssembler},
+    message::{parse_record, DcsMessage},
+    sequence::{SequenceStats, SequenceStatus, SequenceTracker},
+};
 
 pub const DCS_LISTENER_PORT: u16 = 34254;
 pub const DCS_MSG_DELIMITER: u8 = b'\n';
-pub const DCS_LISTENER_BUFFER_SIZE: usize = 1024;
+pub const DCS_LISTENER_BUFFER_SIZE: usize = 65_535;
+pub const DCS_MAX_RECORD_SIZE: usize = 1024 * 1024;
+pub const DCS_STATS_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// How long the partial record and sequence number of a silent sender are kept
+pub const DCS_SENDER_EXPIRY: Duration = Duration::from_secs(60);
+
+/// Reassembly and ordering state kept across datagrams.
+struct ReceiveState {
+    assembler: RecordAssembler,
+    sequence_tracker: SequenceTracker,
+}
+
+/// Snapshot of the counts reported by the listener.
+#[derive(Clone, Copy)]
+struct ListenerStats {
+    framing: FramingStats,
+    sequence: SequenceStats,
+}
+
+impl ReceiveState {
+    fn new() -> ReceiveState {
+        ReceiveState {
+            assembler: RecordAssembler::new(DCS_MAX_RECORD_SIZE),
+            sequence_tracker: SequenceTracker::default(),
+        }
+    }
+
+    /// Forgets the senders that went silent, e.g. an export stopped in the middle of a record.
+    fn expire_senders(&mut self, now: Instant) {
+        self.assembler.expire(now, DCS_SENDER_EXPIRY);
+        self.sequence_tracker.expire(now, DCS_SENDER_EXPIRY);
+    }
+
+    fn stats(&self) -> ListenerStats {
+        ListenerStats {
+            framing: self.assembler.stats(),
+            sequence: self.sequence_tracker.stats(),
+        }
+    }
+}
 
 /// Starts a thread that will continuously listen for the DCS units export.
 ///
 /// # Arguments
+/// * `address` - Address the DCS export is sent to.
+/// * `export_settings` - Closure returning the settings pushed back to the export scripts.
 /// * `unit_handler` - Closure for handling any captured DCS units from the export.
-pub async fn listen<F>(unit_handler: F) -> Result<(), Box<dyn Error>>
+pub async fn listen<S, F>(
+    address: SocketAddr,
+    export_settings: S,
+    unit_handler: F,
+) -> Result<(), Box<dyn Error>>
 where
+    S: Fn() -> ExportSettings + Send + Sync + 'static,
     F: Fn(DcsUnit) + Send + Sync + 'static,
 {
-    let socket = setup_socket().await?;
-    start_receiving_loop(socket, unit_handler).await;
+    let socket = setup_socket(address).await?;
+    start_receiving_loop(socket, export_settings, unit_handler).await;
 
     Ok(())
 }
 
-async fn start_receiving_loop<F>(socket: UdpSocket, unit_handler: F)
+async fn start_receiving_loop<S, F>(socket: UdpSocket, export_settings: S, unit_handler: F)
 where
+    S: Fn() -> ExportSettings + Send + Sync + 'static,
     F: Fn(DcsUnit) + Send + Sync + 'static,
 {
-    let buffer = [0u8; DCS_LISTENER_BUFFER_SIZE];
+    let mut buffer = vec![0u8; DCS_LISTENER_BUFFER_SIZE];
+    let mut state = ReceiveState::new();
+    let mut export_control = ExportControl::default();
+    let mut reported_stats = state.stats();
+    let mut last_report = Instant::now();
 
-    println!("Waiting for data...");
+    info!("Waiting for data...");
 
     loop {
-        match receive_next(&socket, buffer).await {
-            Ok(unit) => unit_handler(unit),
+        let sender = match receive_next(&socket, &mut buffer, &mut state).await {
+            Ok((sender, units)) => {
+                units.into_iter().for_each(&unit_handler);
+                Some(sender)
+            }
             Err(e) => {
-                eprintln!("Error receiving message: {}", e);
+                warn!("Error receiving message: {}", e);
+                None
             }
+        };
+
+        if let Some(sender) = sender {
+            send_export_settings(&socket, &mut export_control, sender, &export_settings()).await;
+        }
+
+        if last_report.elapsed() >= DCS_STATS_REPORT_INTERVAL {
+            report_dropped_records(&reported_stats, &state.stats());
+            reported_stats = state.stats();
+            state.expire_senders(Instant::now());
+            last_report = Instant::now();
         }
     }
 }
 
-async fn setup_socket() -> Result<UdpSocket, Box<dyn Error>> {
-    UdpSocket::bind(("127.0.0.1", DCS_LISTENER_PORT))
-        .await
-        .map_err(|e| e.into())
+async fn setup_socket(address: SocketAddr) -> Result<UdpSocket, Box<dyn Error>> {
+    bind_udp_socket(address)
+        .and_then(UdpSocket::from_std)
+        .map_err(|e| format!("Failed to bind DCS listener to {}: {}", address, e).into())
 }
 
+/// Receives the next datagram, returning its sender and the units of the records it completed.
 async fn receive_next(
     socket: &UdpSocket,
-    mut buffer: [u8; 1024],
-) -> Result<DcsUnit, Box<dyn Error>> {
-    let size = socket.recv(&mut buffer).await?;
+    buffer: &mut [u8],
+    state: &mut ReceiveState,
+) -> Result<(SocketAddr, Vec<DcsUnit>), Box<dyn Error>> {
+    let (size, sender) = socket.recv_from(buffer).await?;
 
     if size == 0 {
         // No data received, possibly due to a closed connection or other issue
         return Err("No data received".into());
     }
 
-    // Iterate over the buffer to find the delimiter and extract the message
-    for (index, &byte) in buffer[..size].iter().enumerate() {
-        if byte == DCS_MSG_DELIMITER {
-            let msg = String::from_utf8_lossy(&buffer[..index]);
-            let unit = serde_json::from_str::<DcsUnit>(&msg)?;
-            return Ok(unit);
+    let mut units = Vec::new();
+    for record in state.assembler.push(sender, &buffer[..size]) {
+        match parse_record(&record) {
+            Ok(DcsMessage::Unit(unit)) => units.push(unit),
+            Ok(DcsMessage::Frame(frame)) => {
+                match state.sequence_tracker.observe(sender, frame.header.sequence) {
+                    SequenceStatus::OutOfOrder => continue,
+                    SequenceStatus::Restarted => {
+                        info!("Export from {} restarted its frame sequence", sender)
+                    }
+                    _ => {}
+                }
+                units.extend(frame.into_units());
+            }
+            Err(_) => state.assembler.record_malformed(),
         }
     }
 
-    // Delimiter not found in the current datagram
-    Err("Message delimiter not found in the datagram".into())
+    Ok((sender, units))
+}
+
+/// Replies to the export script that sent the last datagram with the export settings, unless it
+/// is known to have them already.
+async fn send_export_settings(
+    socket: &UdpSocket,
+    export_control: &mut ExportControl,
+    exporter: SocketAddr,
+    settings: &ExportSettings,
+) {
+    if !export_control.should_send(exporter, settings, Instant::now()) {
+        return;
+    }
+
+    let record = match settings.to_record() {
+        Ok(record) => record,
+        Err(e) => {
+            warn!("Failed to serialize export settings: {}", e);
+            return;
+        }
+    };
+
+    match socket.send_to(&record, exporter).await {
+        Ok(_) => debug!("Sent export settings to {}", exporter),
+        Err(e) => warn!("Failed to send export settings to {}: {}", exporter, e),
+    }
+}
+
+/// Logs how many records and frames were lost since the previous report, if any.
+fn report_dropped_records(previous: &ListenerStats, current: &ListenerStats) {
+    let truncated = current.framing.truncated - previous.framing.truncated;
+    let malformed = current.framing.malformed - previous.framing.malformed;
+    let missed = current.sequence.missed - previous.sequence.missed;
+    let out_of_order = current.sequence.out_of_order - previous.sequence.out_of_order;
+
+    if truncated > 0 {
+        warn!(
+            "Dropped {} truncated records ({} records received so far)",
+            truncated, current.framing.records
+        );
+    }
+    if malformed > 0 {
+        warn!(
+            "Dropped {} malformed records ({} records received so far)",
+            malformed, current.framing.records
+        );
+    }
+    if missed > 0 {
+        warn!(
+            "Missed {} frames ({} frames received so far)",
+            missed, current.sequence.frames
+        );
+    }
+    if out_of_order > 0 {
+        warn!(
+            "Discarded {} out-of-order frames ({} frames received so far)",
+            out_of_order, current.sequence.frames
+        );
+    }
 }
 
 #[cfg(test)]
 mod integration_tests {
 
-    use std::time::Duration;
+    use std::{
+        net::SocketAddr,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
 
     use tokio::{net::UdpSocket, time::timeout};
 
-    use crate::common::{dcs_unit::{Coalition, DcsUnit, Position3D, UnitType}, unit_type::Level1UnitType};
+    use crate::{
+        common::{
+            dcs_frame::{DcsFrame, FrameHeader, FrameUnit, DCS_FRAME_VERSION},
+            dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
+            unit_type::Level1UnitType,
+        },
+        user_config::user_config::UserConfig,
+    };
 
-    use super::{listen, DCS_LISTENER_PORT, DCS_MSG_DELIMITER};
+    use super::{
+        export_control::ExportSettings, listen, start_receiving_loop, DCS_LISTENER_PORT,
+        DCS_MSG_DELIMITER,
+    };
 
     #[tokio::test]
     async fn test_listen() {
         // Create a few units
-        let mut units = Vec::new();
-        for i in 0..3 {
-            units.push(DcsUnit {
-                unit_name: format!("UNIT-{}", i),
-                group_name: format!("GROUP-{}", i),
-                coalition: Coalition::BLUFOR,
-                position: Position3D {
-                    latitude: 30.0090027 + (i as f64),
-                    longitude: -85.9578735 + (i as f64),
-                    altitude: 132.67 + (i as f32),
-                    heading: 0.0568 + (i as f64),
-                },
-                unit_type: UnitType {
-                    level_1: Level1UnitType::AIR,
-                    level_2: 1,
-                },
-                mission_date: "2024-03-08".to_string(),
-                mission_start_time: 28800,
-                mission_time_elapsed: 3600,
-            });
-        }
+        let units = build_units(3, "GROUP");
 
         // Create a channel to signal that units_handler was called
         let (tx, mut rx) = tokio::sync::mpsc::channel(1);
@@ -121,9 +267,13 @@ mod integration_tests {
 
         // Start the listener
         tokio::spawn(async move {
-            listen(unit_handler)
-                .await
-                .expect("Unable to start listener")
+            listen(
+                SocketAddr::from(([127, 0, 0, 1], DCS_LISTENER_PORT)),
+                default_export_settings,
+                unit_handler,
+            )
+            .await
+            .expect("Unable to start listener")
         });
 
         // Create a separate socket for sending messages
@@ -159,4 +309,244 @@ mod integration_tests {
 
         assert_eq!(units_count, units.len());
     }
+
+    #[tokio::test]
+    async fn test_listen_reassembles_shared_and_split_datagrams() {
+        // Units with group names long enough to span several kilobytes
+        let units = build_units(3, &"LONG-GROUP-NAME-".repeat(256));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let unit_handler = move |received_unit: DcsUnit| {
+            tx.send(received_unit).expect("Failed to send signal");
+        };
+
+        // Start the receiving loop on an OS-assigned port
+        let listener_socket = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Unable to create listener socket");
+        let listener_address = listener_socket.local_addr().unwrap();
+        tokio::spawn(start_receiving_loop(
+            listener_socket,
+            default_export_settings,
+            unit_handler,
+        ));
+
+        let sender_socket = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Unable to create sender socket");
+        sender_socket
+            .connect(listener_address)
+            .await
+            .expect("Unable to connect to listener");
+
+        // The first two units share a datagram, the third is split across several datagrams
+        let mut payload = String::new();
+        for unit in &units {
+            payload.push_str(&serde_json::to_string(&unit).unwrap());
+            payload.push(DCS_MSG_DELIMITER as char);
+        }
+        let third_unit_start = payload.len() - serde_json::to_string(&units[2]).unwrap().len() - 1;
+        let (shared, split) = payload.split_at(third_unit_start);
+
+        sender_socket
+            .send(shared.as_bytes())
+            .await
+            .expect("Unable to send message");
+        for chunk in split.as_bytes().chunks(1000) {
+            sender_socket
+                .send(chunk)
+                .await
+                .expect("Unable to send message");
+        }
+
+        for expected in &units {
+            let unit = timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("Did not receive unit in time")
+                .expect("Unit channel closed");
+            assert_eq!(&unit, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_listen_accepts_frames_and_legacy_units() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let unit_handler = move |received_unit: DcsUnit| {
+            tx.send(received_unit).expect("Failed to send signal");
+        };
+
+        let listener_socket = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Unable to create listener socket");
+        let listener_address = listener_socket.local_addr().unwrap();
+        tokio::spawn(start_receiving_loop(
+            listener_socket,
+            default_export_settings,
+            unit_handler,
+        ));
+
+        let sender_socket = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Unable to create sender socket");
+        sender_socket
+            .connect(listener_address)
+            .await
+            .expect("Unable to connect to listener");
+
+        // A frame, a stale frame that must be discarded, then a legacy single-unit record
+        let frame = build_frame(2, build_units(2, "FRAME"));
+        let stale_frame = build_frame(1, build_units(1, "STALE"));
+        let legacy_unit = build_units(1, "LEGACY").remove(0);
+        let records = vec![
+            serde_json::to_string(&frame).unwrap(),
+            serde_json::to_string(&stale_frame).unwrap(),
+            serde_json::to_string(&legacy_unit).unwrap(),
+        ];
+
+        for mut record in records {
+            record.push(DCS_MSG_DELIMITER as char);
+            sender_socket
+                .send(record.as_bytes())
+                .await
+                .expect("Unable to send message");
+        }
+
+        let mut expected = frame.into_units();
+        expected.push(legacy_unit);
+        for expected in &expected {
+            let unit = timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("Did not receive unit in time")
+                .expect("Unit channel closed");
+            assert_eq!(&unit, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_settings_are_pushed_to_exporter() {
+        let settings = Arc::new(Mutex::new(default_export_settings()));
+        let export_settings = {
+            let settings = settings.clone();
+            move || settings.lock().unwrap().clone()
+        };
+
+        let listener_socket = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Unable to create listener socket");
+        let listener_address = listener_socket.local_addr().unwrap();
+        tokio::spawn(start_receiving_loop(
+            listener_socket,
+            export_settings,
+            |_: DcsUnit| {},
+        ));
+
+        // Stands in for the export script, which receives on the socket it sends from
+        let exporter_socket = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .expect("Unable to create exporter socket");
+        exporter_socket
+            .connect(listener_address)
+            .await
+            .expect("Unable to connect to listener");
+
+        // The settings are sent in reply to the first frame
+        send_frame(&exporter_socket, 1).await;
+        assert_eq!(
+            receive_export_settings(&exporter_socket).await,
+            Some(default_export_settings())
+        );
+
+        // Unchanged settings are not sent again
+        send_frame(&exporter_socket, 2).await;
+        assert_eq!(receive_export_settings(&exporter_socket).await, None);
+
+        // Changed settings are sent in reply to the next frame
+        let changed = ExportSettings {
+            export_frequency_frames: 25,
+            coalition_ids: vec![Coalition::REDFOR as u8],
+            ..default_export_settings()
+        };
+        *settings.lock().unwrap() = changed.clone();
+        send_frame(&exporter_socket, 3).await;
+        assert_eq!(
+            receive_export_settings(&exporter_socket).await,
+            Some(changed)
+        );
+    }
+
+    fn default_export_settings() -> ExportSettings {
+        ExportSettings::from_config(&UserConfig::default())
+    }
+
+    async fn send_frame(exporter_socket: &UdpSocket, sequence: u32) {
+        let mut record = serde_json::to_string(&build_frame(sequence, vec![])).unwrap();
+        record.push(DCS_MSG_DELIMITER as char);
+
+        exporter_socket
+            .send(record.as_bytes())
+            .await
+            .expect("Unable to send message");
+    }
+
+    async fn receive_export_settings(exporter_socket: &UdpSocket) -> Option<ExportSettings> {
+        let mut buffer = [0u8; 1024];
+        let size = timeout(
+            Duration::from_millis(500),
+            exporter_socket.recv(&mut buffer),
+        )
+        .await
+        .ok()?
+        .expect("Unable to receive export settings");
+
+        assert_eq!(buffer[size - 1], DCS_MSG_DELIMITER);
+        Some(serde_json::from_slice(&buffer[..size - 1]).expect("Invalid export settings"))
+    }
+
+    fn build_frame(sequence: u32, units: Vec<DcsUnit>) -> DcsFrame {
+        DcsFrame {
+            version: DCS_FRAME_VERSION,
+            header: FrameHeader {
+                sequence,
+                mission_date: "2024-03-08".to_string(),
+                mission_start_time: 28800,
+                mission_time_elapsed: 3600,
+            },
+            units: units
+                .into_iter()
+                .map(|unit| FrameUnit {
+                    unit_name: unit.unit_name,
+                    group_name: unit.group_name,
+                    coalition: unit.coalition,
+                    position: unit.position,
+                    unit_type: unit.unit_type,
+                })
+                .collect(),
+        }
+    }
+
+    fn build_units(count: usize, group_prefix: &str) -> Vec<DcsUnit> {
+        (0..count)
+            .map(|i| DcsUnit {
+                unit_name: format!("UNIT-{}", i),
+                group_name: format!("{}-{}", group_prefix, i),
+                coalition: Coalition::BLUFOR,
+                position: Position3D {
+                    latitude: 30.0090027 + (i as f64),
+                    longitude: -85.9578735 + (i as f64),
+                    altitude: 132.67 + (i as f32),
+                    heading: 0.0568 + (i as f64),
+                },
+                unit_type: UnitType {
+                    level_1: Level1UnitType::AIR,
+                    level_2: 1,
+                    level_3: 0,
+                    level_4: 0,
+                    type_name: String::new(),
+                },
+                mission_date: "2024-03-08".to_string(),
+                mission_start_time: 28800,
+                mission_time_elapsed: 3600,
+            })
+            .collect()
+    }
 }
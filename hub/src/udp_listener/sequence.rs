@@ -0,0 +1,222 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+/// How far behind the last sequence number a frame may be before it is treated as an export
+/// restart rather than an out-of-order frame.
+pub const SEQUENCE_RESTART_WINDOW: u32 = 1000;
+
+/// Where a frame falls relative to the frames previously received from the same sender.
+#[derive(Debug, PartialEq)]
+pub enum SequenceStatus {
+    /// The first frame received from the sender
+    First,
+
+    /// The frame directly following the previous one
+    InOrder,
+
+    /// The frame follows a number of frames that were never received
+    Gap(u32),
+
+    /// The frame is a duplicate or arrived after a newer frame
+    OutOfOrder,
+
+    /// The sequence started over, e.g. because the mission was restarted
+    Restarted,
+}
+
+/// Running counts of frame ordering problems.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SequenceStats {
+    /// Frames received from all senders
+    pub frames: u64,
+
+    /// Frames that were skipped by the sequence number and never received
+    pub missed: u64,
+
+    /// Frames received after a newer frame from the same sender
+    pub out_of_order: u64,
+}
+
+/// Detects dropped and out-of-order frames from their sequence numbers, per sender.
+#[derive(Default)]
+pub struct SequenceTracker {
+    /// The newest sequence number of each sender, and when a frame was last received from it
+    last_by_sender: HashMap<SocketAddr, (u32, Instant)>,
+    stats: SequenceStats,
+}
+
+impl SequenceTracker {
+    /// Records a frame sequence number received from `sender`.
+    pub fn observe(&mut self, sender: SocketAddr, sequence: u32) -> SequenceStatus {
+        self.stats.frames += 1;
+
+        let now = Instant::now();
+        let last = match self.last_by_sender.insert(sender, (sequence, now)) {
+            Some((last, _)) => last,
+            None => return SequenceStatus::First,
+        };
+
+        let ahead = sequence.wrapping_sub(last);
+        if ahead == 1 {
+            return SequenceStatus::InOrder;
+        }
+
+        if ahead != 0 && ahead <= u32::MAX / 2 {
+            self.stats.missed += (ahead - 1) as u64;
+            return SequenceStatus::Gap(ahead - 1);
+        }
+
+        if last.wrapping_sub(sequence) > SEQUENCE_RESTART_WINDOW {
+            return SequenceStatus::Restarted;
+        }
+
+        // Keep the newest frame as the reference
+        self.last_by_sender.insert(sender, (last, now));
+        self.stats.out_of_order += 1;
+        SequenceStatus::OutOfOrder
+    }
+
+    /// Forgets the senders that sent no frame for `max_age` by `now`.
+    pub fn expire(&mut self, now: Instant, max_age: Duration) {
+        self.last_by_sender
+            .retain(|_, (_, received_at)| now.saturating_duration_since(*received_at) < max_age);
+    }
+
+    /// Returns the running ordering counts.
+    pub fn stats(&self) -> SequenceStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use std::{
+        net::SocketAddr,
+        time::{Duration, Instant},
+    };
+
+    use super::{SequenceStats, SequenceStatus, SequenceTracker};
+
+    fn sender(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn given_consecutive_sequence_numbers_when_observed_then_frames_are_in_order() {
+        // Arrange
+        let mut tracker = SequenceTracker::default();
+
+        // Act
+        let results: Vec<_> = (5..8).map(|i| tracker.observe(sender(1), i)).collect();
+
+        // Assert
+        assert_eq!(
+            results,
+            vec![
+                SequenceStatus::First,
+                SequenceStatus::InOrder,
+                SequenceStatus::InOrder
+            ]
+        );
+    }
+
+    #[test]
+    fn given_skipped_sequence_numbers_when_observed_then_gap_is_reported() {
+        // Arrange
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(sender(1), 1);
+
+        // Act
+        let result = tracker.observe(sender(1), 5);
+
+        // Assert
+        assert_eq!(result, SequenceStatus::Gap(3));
+        assert_eq!(tracker.stats().missed, 3);
+    }
+
+    #[test]
+    fn given_older_or_duplicate_sequence_number_when_observed_then_frame_is_out_of_order() {
+        // Arrange
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(sender(1), 10);
+
+        // Act
+        let older = tracker.observe(sender(1), 9);
+        let duplicate = tracker.observe(sender(1), 10);
+        let next = tracker.observe(sender(1), 11);
+
+        // Assert
+        assert_eq!(older, SequenceStatus::OutOfOrder);
+        assert_eq!(duplicate, SequenceStatus::OutOfOrder);
+        assert_eq!(next, SequenceStatus::InOrder);
+        assert_eq!(
+            tracker.stats(),
+            SequenceStats {
+                frames: 4,
+                missed: 0,
+                out_of_order: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn given_sequence_far_behind_when_observed_then_export_restart_is_assumed() {
+        // Arrange
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(sender(1), 50_000);
+
+        // Act
+        let restarted = tracker.observe(sender(1), 0);
+        let next = tracker.observe(sender(1), 1);
+
+        // Assert
+        assert_eq!(restarted, SequenceStatus::Restarted);
+        assert_eq!(next, SequenceStatus::InOrder);
+    }
+
+    #[test]
+    fn given_sequence_wrapping_around_when_observed_then_frames_are_in_order() {
+        // Arrange
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(sender(1), u32::MAX);
+
+        // Act
+        let result = tracker.observe(sender(1), 0);
+
+        // Assert
+        assert_eq!(result, SequenceStatus::InOrder);
+    }
+
+    #[test]
+    fn given_several_senders_when_observed_then_sequences_are_tracked_independently() {
+        // Arrange
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(sender(1), 100);
+
+        // Act
+        let result = tracker.observe(sender(2), 0);
+
+        // Assert
+        assert_eq!(result, SequenceStatus::First);
+    }
+
+    #[test]
+    fn given_silent_sender_when_expired_then_next_frame_is_first() {
+        // Arrange
+        let mut tracker = SequenceTracker::default();
+        tracker.observe(sender(1), 100);
+
+        // Act
+        tracker.expire(
+            Instant::now() + Duration::from_secs(120),
+            Duration::from_secs(60),
+        );
+        let result = tracker.observe(sender(1), 5);
+
+        // Assert
+        assert_eq!(result, SequenceStatus::First);
+    }
+}
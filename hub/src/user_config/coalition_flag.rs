@@ -1,20 +1,53 @@
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
 
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-/// Represents the 3 DCS coalitions.
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+use super::named_flags::{deserialize_flag, serialize_flag, FlagNames};
+use crate::common::dcs_unit::Coalition;
+
+/// Represents the 3 DCS coalitions, stored as a list of names, e.g. `["redfor", "blufor"]`.
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct CoalitionFlag(pub u8);
 
 impl CoalitionFlag {
     pub const NEUTRAL: CoalitionFlag = CoalitionFlag(1);
     pub const REDFOR: CoalitionFlag = CoalitionFlag(2);
     pub const BLUFOR: CoalitionFlag = CoalitionFlag(4);
-    
+
+    /// The name of each coalition in the configuration
+    const NAMES: FlagNames = &[
+        ("neutral", CoalitionFlag::NEUTRAL.0),
+        ("redfor", CoalitionFlag::REDFOR.0),
+        ("blufor", CoalitionFlag::BLUFOR.0),
+    ];
+
     /// Returns `CoalitionFlag(0)`.
     pub fn empty() -> CoalitionFlag {
         CoalitionFlag(0)
     }
+
+    /// Whether the flag includes `coalition`.
+    pub fn includes(self, coalition: Coalition) -> bool {
+        let flag = match coalition {
+            Coalition::NEUTRAL => CoalitionFlag::NEUTRAL,
+            Coalition::REDFOR => CoalitionFlag::REDFOR,
+            Coalition::BLUFOR => CoalitionFlag::BLUFOR,
+        };
+
+        (self & flag) != CoalitionFlag::empty()
+    }
+}
+
+impl Serialize for CoalitionFlag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_flag(self.0, Self::NAMES, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CoalitionFlag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_flag(Self::NAMES, deserializer).map(CoalitionFlag)
+    }
 }
 
 impl BitOr for CoalitionFlag {
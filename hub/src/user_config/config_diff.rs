@@ -0,0 +1,197 @@
+use std::fmt::{self, Display};
+
+use serde_json::Value;
+
+use super::user_config::UserConfig;
+
+/// Settings that are only read when the hub starts
+pub const RESTART_REQUIRED_SETTINGS: &[&str] = &[
+    "listener_address",
+    "sinks",
+    "recording",
+    "cot_type_map_path",
+    "geoid_model_path",
+];
+
+/// A setting that differs between two configurations.
+#[derive(Debug, PartialEq)]
+pub struct ConfigChange {
+    /// Path of the setting, separated by dots
+    pub key: String,
+
+    /// The previous value, if the setting was set
+    pub old: Option<Value>,
+
+    /// The new value, if the setting is set
+    pub new: Option<Value>,
+}
+
+impl ConfigChange {
+    /// Whether the change only takes effect once the hub is restarted.
+    pub fn requires_restart(&self) -> bool {
+        let mut path = self.key.split('.');
+        let setting = path.next().unwrap_or_default();
+        // The outputs of the sinks are only started once, their filters are swapped in live
+        if setting == "sinks" && path.nth(1) == Some("filter") {
+            return false;
+        }
+        RESTART_REQUIRED_SETTINGS.contains(&setting)
+    }
+}
+
+impl Display for ConfigChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "unset".to_string(),
+        };
+
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.key,
+            describe(&self.old),
+            describe(&self.new)
+        )
+    }
+}
+
+/// Lists the settings that differ between `old` and `new`, down to the individual fields of
+/// nested settings.
+pub fn diff(old: &UserConfig, new: &UserConfig) -> Vec<ConfigChange> {
+    let mut changes = Vec::new();
+    let old = serde_json::to_value(old).unwrap_or_default();
+    let new = serde_json::to_value(new).unwrap_or_default();
+
+    diff_values(String::new(), Some(&old), Some(&new), &mut changes);
+    changes
+}
+
+fn diff_values(
+    key: String,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<ConfigChange>,
+) {
+    let child_key = |child: &str| match key.is_empty() {
+        true => child.to_string(),
+        false => format!("{}.{}", key, child),
+    };
+
+    match (old, new) {
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for child in keys {
+                diff_values(child_key(child), old.get(child), new.get(child), changes);
+            }
+        }
+        // Lists of names, such as the coalitions, change as a whole
+        (Some(Value::Array(old)), Some(Value::Array(new)))
+            if old.iter().chain(new).any(Value::is_object) =>
+        {
+            for index in 0..old.len().max(new.len()) {
+                diff_values(
+                    child_key(&index.to_string()),
+                    old.get(index),
+                    new.get(index),
+                    changes,
+                );
+            }
+        }
+        (old, new) if old != new => changes.push(ConfigChange {
+            key,
+            old: old.cloned(),
+            new: new.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use serde_json::json;
+
+    use crate::{
+        sink::sink_config::{OutputConfig, SinkConfig, WebSocketSinkConfig},
+        tak::tak_mesh_config::TakMeshConfig,
+        user_config::{coalition_flag::CoalitionFlag, user_config::UserConfig},
+    };
+
+    use super::{diff, ConfigChange};
+
+    #[test]
+    fn given_changed_settings_when_diffed_then_each_changed_field_is_listed() {
+        // Arrange
+        let old = UserConfig::default();
+        let mut new = UserConfig {
+            coalition_flag: CoalitionFlag::REDFOR,
+            cot_type_map_path: Some("cot_types.json".to_string()),
+            ..Default::default()
+        };
+        new.sinks[0].filter.unit_name_patterns = vec!["SAM*".to_string()];
+
+        // Act
+        let result = diff(&old, &new);
+
+        // Assert
+        assert_eq!(
+            result,
+            vec![
+                ConfigChange {
+                    key: "coalitions".to_string(),
+                    old: Some(json!(["blufor"])),
+                    new: Some(json!(["redfor"])),
+                },
+                ConfigChange {
+                    key: "cot_type_map_path".to_string(),
+                    old: None,
+                    new: Some(json!("cot_types.json")),
+                },
+                ConfigChange {
+                    key: "sinks.0.filter.unit_name_patterns".to_string(),
+                    old: None,
+                    new: Some(json!(["SAM*"])),
+                },
+            ]
+        );
+        assert!(!result[0].requires_restart());
+        assert!(result[1].requires_restart());
+        assert!(!result[2].requires_restart());
+        assert_eq!(
+            result[1].to_string(),
+            r#"cot_type_map_path: unset -> "cot_types.json""#
+        );
+    }
+
+    #[test]
+    fn given_changed_sink_output_when_diffed_then_restart_is_required() {
+        // Arrange
+        let old = UserConfig::default();
+        let mut new = UserConfig::default();
+        new.sinks[0].output = OutputConfig::WebSocket(WebSocketSinkConfig {
+            address: "0.0.0.0:9000".parse().unwrap(),
+            ..Default::default()
+        });
+        new.sinks.push(SinkConfig::new(OutputConfig::TakMesh(
+            TakMeshConfig::default(),
+        )));
+
+        // Act
+        let result = diff(&old, &new);
+
+        // Assert
+        assert_eq!(
+            result.iter().map(|change| &change.key).collect::<Vec<_>>(),
+            vec!["sinks.0.address", "sinks.1"]
+        );
+        assert!(result.iter().all(ConfigChange::requires_restart));
+    }
+
+    #[test]
+    fn given_same_settings_when_diffed_then_nothing_is_listed() {
+        assert!(diff(&UserConfig::default(), &UserConfig::default()).is_empty());
+    }
+}
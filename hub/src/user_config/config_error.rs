@@ -0,0 +1,47 @@
+use std::{
+    fmt::{self, Display},
+    net::SocketAddr,
+};
+
+use super::config_migration::CONFIG_VERSION;
+
+/// Reasons a `UserConfig` cannot be used.
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    /// The setting needs a fixed port for others to connect to, but has port 0
+    MissingPort(&'static str),
+
+    /// More than one sink is configured to listen on the address, or on all interfaces at its port
+    DuplicateAddress(SocketAddr),
+
+    /// A setting given on the command line does not exist or has an invalid value
+    InvalidSetting { key: String, reason: String },
+
+    /// The configuration file was written for a newer version of the hub
+    UnsupportedVersion(u64),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::MissingPort(setting) => {
+                write!(f, "'{}' needs a port other than 0", setting)
+            }
+            ConfigError::DuplicateAddress(address) => {
+                write!(f, "More than one sink listens on {}", address)
+            }
+            ConfigError::InvalidSetting { key, reason } => {
+                write!(f, "Invalid setting '{}': {}", key, reason)
+            }
+            ConfigError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "Configuration version {} is newer than the supported version {}",
+                    version, CONFIG_VERSION
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
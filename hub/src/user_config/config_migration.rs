@@ -0,0 +1,175 @@
+use serde_json::{json, Map, Value};
+
+use super::config_error::ConfigError;
+
+/// The version of the configuration file written by this hub
+pub const CONFIG_VERSION: u64 = 1;
+
+/// Settings renamed in version 1, when the flags went from integers to lists of names
+const VERSION_1_RENAMES: [(&str, &str); 2] = [
+    ("coalition_flag", "coalitions"),
+    ("unit_type_flag", "unit_types"),
+];
+
+/// Outputs that were top-level settings in version 0, before outputs were configurable as sinks
+const LEGACY_SINK_KEYS: [&str; 2] = ["tak_server", "tak_mesh"];
+
+/// Brings the settings of a configuration file written by any earlier version up to date,
+/// removing the `version` field. Files without a version predate it and are version 0.
+///
+/// Returns the version the file was written in.
+pub fn migrate(settings: &mut Value) -> Result<u64, ConfigError> {
+    let Value::Object(settings) = settings else {
+        return Ok(CONFIG_VERSION);
+    };
+
+    let version = match settings.remove("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| ConfigError::InvalidSetting {
+                key: "version".to_string(),
+                reason: format!("expected a non-negative integer, got {}", version),
+            })?,
+    };
+    if version > CONFIG_VERSION {
+        return Err(ConfigError::UnsupportedVersion(version));
+    }
+
+    if version < 1 {
+        migrate_to_version_1(settings);
+    }
+
+    // Would be ignored otherwise
+    if let Some(key) = LEGACY_SINK_KEYS
+        .iter()
+        .find(|key| settings.contains_key(**key))
+    {
+        return Err(ConfigError::InvalidSetting {
+            key: key.to_string(),
+            reason: format!("configure a sink of type '{}' in 'sinks' instead", key),
+        });
+    }
+
+    Ok(version)
+}
+
+/// The flags keep their integer values, which are still read, and are written as names from
+/// then on. The TAK Server and TAK mesh settings become sinks, next to the WebSocket clients
+/// they were published alongside.
+fn migrate_to_version_1(settings: &mut Map<String, Value>) {
+    rename(settings, &VERSION_1_RENAMES);
+
+    for key in LEGACY_SINK_KEYS {
+        let Some(Value::Object(mut output)) = settings.remove(key) else {
+            continue;
+        };
+        output.insert("type".to_string(), Value::from(key));
+
+        let sinks = settings
+            .entry("sinks")
+            .or_insert_with(|| json!([{"type": "web_socket"}]));
+        if let Value::Array(sinks) = sinks {
+            sinks.push(Value::Object(output));
+        }
+    }
+
+    if let Some(Value::Array(sinks)) = settings.get_mut("sinks") {
+        for sink in sinks {
+            if let Some(Value::Object(filter)) = sink.get_mut("filter") {
+                rename(filter, &VERSION_1_RENAMES);
+            }
+        }
+    }
+}
+
+fn rename(settings: &mut Map<String, Value>, renames: &[(&str, &str)]) {
+    for (old, new) in renames {
+        if let Some(value) = settings.remove(*old) {
+            settings.insert(new.to_string(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use serde_json::json;
+
+    use super::{migrate, CONFIG_VERSION};
+    use crate::user_config::config_error::ConfigError;
+
+    #[test]
+    fn given_legacy_settings_when_migrated_then_flags_are_renamed() {
+        // Arrange
+        let mut settings = json!({
+            "coalition_flag": 4,
+            "unit_type_flag": 7,
+            "export_frequency_frames": 100,
+            "sinks": [{"type": "web_socket", "filter": {"coalition_flag": 2}}]
+        });
+
+        // Act
+        let version = migrate(&mut settings);
+
+        // Assert
+        assert_eq!(version, Ok(0));
+        assert_eq!(
+            settings,
+            json!({
+                "coalitions": 4,
+                "unit_types": 7,
+                "export_frequency_frames": 100,
+                "sinks": [{"type": "web_socket", "filter": {"coalitions": 2}}]
+            })
+        );
+    }
+
+    #[test]
+    fn given_legacy_tak_settings_when_migrated_then_they_become_sinks() {
+        // Arrange
+        let mut settings = json!({
+            "coalition_flag": 4,
+            "tak_server": {"host": "tak.example.com", "protocol": "protobuf"},
+            "tak_mesh": null
+        });
+
+        // Act
+        let version = migrate(&mut settings);
+
+        // Assert
+        assert_eq!(version, Ok(0));
+        assert_eq!(
+            settings,
+            json!({
+                "coalitions": 4,
+                "sinks": [
+                    {"type": "web_socket"},
+                    {"type": "tak_server", "host": "tak.example.com", "protocol": "protobuf"}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn given_versioned_settings_with_legacy_tak_settings_when_migrated_then_error_is_returned() {
+        let mut settings = json!({"version": CONFIG_VERSION, "tak_mesh": {}});
+
+        assert!(matches!(
+            migrate(&mut settings),
+            Err(ConfigError::InvalidSetting { key, .. }) if key == "tak_mesh"
+        ));
+    }
+
+    #[test]
+    fn given_versioned_settings_when_migrated_then_only_the_version_is_removed() {
+        let mut current = json!({"version": CONFIG_VERSION, "coalitions": ["blufor"]});
+        let mut newer = json!({"version": CONFIG_VERSION + 1});
+
+        assert_eq!(migrate(&mut current), Ok(CONFIG_VERSION));
+        assert_eq!(current, json!({"coalitions": ["blufor"]}));
+        assert_eq!(
+            migrate(&mut newer),
+            Err(ConfigError::UnsupportedVersion(CONFIG_VERSION + 1))
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    error::Error,
+    fs,
+    hash::{Hash, Hasher},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use log::{info, warn};
+use tokio::{task::JoinHandle, time::interval};
+
+use super::{config_diff::diff, user_config::UserConfig};
+
+/// How often the configuration file is checked for changes
+pub const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The configuration currently in effect. It is replaced as a whole when the file changes, so
+/// readers never see a mix of old and new settings.
+#[derive(Clone)]
+pub struct SharedConfig {
+    current: Arc<RwLock<Arc<UserConfig>>>,
+}
+
+impl SharedConfig {
+    pub fn new(user_config: UserConfig) -> SharedConfig {
+        SharedConfig {
+            current: Arc::new(RwLock::new(Arc::new(user_config))),
+        }
+    }
+
+    /// The configuration in effect, which stays unchanged for as long as it is held.
+    pub fn current(&self) -> Arc<UserConfig> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Puts `user_config` into effect.
+    pub fn replace(&self, user_config: UserConfig) {
+        *self.current.write().unwrap() = Arc::new(user_config);
+    }
+}
+
+/// Identifies a version of the configuration file by a hash of its contents, as edits within
+/// the resolution of the modification time that keep the length would go unnoticed otherwise
+type FileVersion = Option<u64>;
+
+/// Polls the configuration file and puts it into effect whenever it changes, logging what
+/// changed. `load` reads the file the same way it was read at start-up; files it rejects are
+/// ignored, keeping the configuration in effect.
+pub fn watch_config<F>(
+    file_path: String,
+    poll_interval: Duration,
+    shared_config: SharedConfig,
+    load: F,
+) -> JoinHandle<()>
+where
+    F: Fn(&str) -> Result<UserConfig, Box<dyn Error>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut file_version = read_file_version(&file_path);
+        let mut ticks = interval(poll_interval);
+
+        loop {
+            ticks.tick().await;
+
+            let current_version = read_file_version(&file_path);
+            if current_version == file_version {
+                continue;
+            }
+            file_version = current_version;
+
+            reload(&file_path, &shared_config, &load);
+        }
+    })
+}
+
+fn read_file_version(file_path: &str) -> FileVersion {
+    let contents = fs::read(file_path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+
+    Some(hasher.finish())
+}
+
+fn reload<F>(file_path: &str, shared_config: &SharedConfig, load: &F)
+where
+    F: Fn(&str) -> Result<UserConfig, Box<dyn Error>>,
+{
+    let new_config = match load(file_path) {
+        Ok(new_config) => new_config,
+        Err(err) => {
+            warn!(
+                "Ignoring invalid configuration '{}', keeping the current one: {}",
+                file_path, err
+            );
+            return;
+        }
+    };
+
+    let changes = diff(&shared_config.current(), &new_config);
+    if changes.is_empty() {
+        return;
+    }
+
+    for change in &changes {
+        match change.requires_restart() {
+            true => warn!(
+                "Configuration changed, effective after a restart: {}",
+                change
+            ),
+            false => info!("Configuration changed: {}", change),
+        }
+    }
+    shared_config.replace(new_config);
+}
+
+#[cfg(test)]
+mod integration_tests {
+    use std::{fs, time::Duration};
+
+    use tokio::time::{sleep, timeout};
+
+    use crate::user_config::{
+        coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag, user_config::UserConfig,
+    };
+
+    use super::{watch_config, SharedConfig};
+
+    #[tokio::test]
+    async fn test_changed_config_file_is_swapped_in_and_invalid_one_ignored() {
+        // Arrange
+        let file_path = std::env::temp_dir()
+            .join(format!("hub-watch-{}.config", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        UserConfig::default().to_file(&file_path).unwrap();
+        let shared_config = SharedConfig::new(UserConfig::default());
+        let watcher = watch_config(
+            file_path.clone(),
+            Duration::from_millis(20),
+            shared_config.clone(),
+            |file_path| Ok(UserConfig::from_file(file_path)?),
+        );
+        sleep(Duration::from_millis(50)).await;
+
+        // Act
+        let changed = UserConfig {
+            coalition_flag: CoalitionFlag::REDFOR,
+            unit_type_flag: UnitTypeFlag::AIR,
+            ..Default::default()
+        };
+        changed.to_file(&file_path).unwrap();
+        let swapped = timeout(Duration::from_secs(5), async {
+            while shared_config.current().coalition_flag != CoalitionFlag::REDFOR {
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+
+        fs::write(&file_path, "{ not a configuration").unwrap();
+        sleep(Duration::from_millis(200)).await;
+        let after_invalid = shared_config.current();
+        watcher.abort();
+        fs::remove_file(&file_path).ok();
+
+        // Assert
+        assert!(swapped.is_ok(), "Changed configuration was not swapped in");
+        assert_eq!(after_invalid.coalition_flag, CoalitionFlag::REDFOR);
+        assert_eq!(after_invalid.unit_type_flag, UnitTypeFlag::AIR);
+    }
+}
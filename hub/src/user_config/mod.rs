@@ -1,3 +1,8 @@
 pub mod user_config;
 pub mod coalition_flag;
+pub mod config_diff;
+pub mod config_error;
+pub mod config_migration;
+pub mod config_watcher;
+pub mod named_flags;
 pub mod unit_type_flag;
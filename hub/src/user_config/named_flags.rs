@@ -0,0 +1,102 @@
+use std::fmt;
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserializer, Serializer,
+};
+
+/// The name of each bit of a flag, in the order the names are written.
+pub type FlagNames = &'static [(&'static str, u8)];
+
+/// Writes the bits of a flag as a list of names, e.g. `["redfor", "blufor"]`.
+pub fn serialize_flag<S: Serializer>(
+    bits: u8,
+    names: FlagNames,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let included: Vec<&str> = names
+        .iter()
+        .filter(|(_, bit)| bits & bit != 0)
+        .map(|(name, _)| *name)
+        .collect();
+
+    let mut seq = serializer.serialize_seq(Some(included.len()))?;
+    for name in included {
+        seq.serialize_element(name)?;
+    }
+    seq.end()
+}
+
+/// Reads the bits of a flag from a list of names, or from the integer the flags were stored as
+/// before they were named.
+pub fn deserialize_flag<'de, D: Deserializer<'de>>(
+    names: FlagNames,
+    deserializer: D,
+) -> Result<u8, D::Error> {
+    deserializer.deserialize_any(FlagVisitor { names })
+}
+
+struct FlagVisitor {
+    names: FlagNames,
+}
+
+impl<'de> Visitor<'de> for FlagVisitor {
+    type Value = u8;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.names.iter().map(|(name, _)| *name).collect();
+        write!(formatter, "a list of {}", names.join(", "))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<u8, E> {
+        u8::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<u8, E> {
+        u8::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<u8, A::Error> {
+        let mut bits = 0;
+        while let Some(name) = seq.next_element::<String>()? {
+            let (_, bit) = self
+                .names
+                .iter()
+                .find(|(known, _)| known.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| de::Error::invalid_value(de::Unexpected::Str(&name), &self))?;
+            bits |= bit;
+        }
+
+        Ok(bits)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+    use serde_json::Value;
+
+    use crate::user_config::{coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag};
+
+    #[test]
+    fn given_flags_when_serialized_then_names_are_written() {
+        // Arrange
+        let coalition_flag = CoalitionFlag::BLUFOR | CoalitionFlag::NEUTRAL;
+
+        // Act
+        let json = serde_json::to_value(coalition_flag).unwrap();
+
+        // Assert
+        assert_eq!(json, serde_json::json!(["neutral", "blufor"]));
+    }
+
+    #[test]
+    fn given_names_or_legacy_integer_when_deserialized_then_flags_are_read() {
+        let from_names: UnitTypeFlag = serde_json::from_str(r#"["Ground","sea"]"#).unwrap();
+        let from_integer: UnitTypeFlag = serde_json::from_str("5").unwrap();
+
+        assert_eq!(from_names, UnitTypeFlag::GROUND | UnitTypeFlag::SEA);
+        assert_eq!(from_integer, from_names);
+        assert!(serde_json::from_value::<CoalitionFlag>(Value::from(vec!["green"])).is_err());
+    }
+}
@@ -1,20 +1,54 @@
 use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-/// Represents the three high-level DCS unit classifications.
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+use super::named_flags::{deserialize_flag, serialize_flag, FlagNames};
+use crate::common::unit_type::Level1UnitType;
+
+/// Represents the three high-level DCS unit classifications, stored as a list of names,
+/// e.g. `["ground", "air"]`.
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub struct UnitTypeFlag(pub u8);
 
 impl UnitTypeFlag {
     pub const GROUND: UnitTypeFlag = UnitTypeFlag(1);
     pub const AIR: UnitTypeFlag = UnitTypeFlag(2);
     pub const SEA: UnitTypeFlag = UnitTypeFlag(4);
-    
+
+    /// The name of each unit type in the configuration
+    const NAMES: FlagNames = &[
+        ("ground", UnitTypeFlag::GROUND.0),
+        ("air", UnitTypeFlag::AIR.0),
+        ("sea", UnitTypeFlag::SEA.0),
+    ];
+
     /// Returns `UnitTypeFlag(0)`.
     pub fn empty() -> UnitTypeFlag {
         UnitTypeFlag(0)
     }
+
+    /// Whether the flag includes `unit_type`.
+    pub fn includes(self, unit_type: Level1UnitType) -> bool {
+        let flag = match unit_type {
+            Level1UnitType::GROUND => UnitTypeFlag::GROUND,
+            Level1UnitType::AIR => UnitTypeFlag::AIR,
+            Level1UnitType::SEA => UnitTypeFlag::SEA,
+        };
+
+        (self & flag) != UnitTypeFlag::empty()
+    }
+}
+
+impl Serialize for UnitTypeFlag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_flag(self.0, Self::NAMES, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for UnitTypeFlag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_flag(Self::NAMES, deserializer).map(UnitTypeFlag)
+    }
 }
 
 impl BitOr for UnitTypeFlag {
@@ -43,4 +77,4 @@ impl BitAndAssign for UnitTypeFlag {
     fn bitand_assign(&mut self, rhs: Self) {
         self.0 &= rhs.0;
     }
-}
\ No newline at end of file
+}
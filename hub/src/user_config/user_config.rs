@@ -1,51 +1,198 @@
 use std::{
-    fs::File,
-    io::{self, BufRead, BufReader, BufWriter, Write},
+    fs,
+    io::{self, ErrorKind},
+    net::{Ipv4Addr, SocketAddr},
 };
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::common::{
-    dcs_unit::{Coalition, DcsUnit},
-    unit_type::Level1UnitType,
+use crate::common::dcs_unit::DcsUnit;
+
+use crate::{
+    registry::unit_registry::validate_lifecycle,
+    sink::sink_config::{OutputConfig, RecordingConfig, SinkConfig, WebSocketSinkConfig},
+    udp_listener::DCS_LISTENER_PORT,
 };
 
-use super::{coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag};
+use super::{
+    coalition_flag::CoalitionFlag,
+    config_error::ConfigError,
+    config_migration::{migrate, CONFIG_VERSION},
+    unit_type_flag::UnitTypeFlag,
+};
 
 /// Encapsulates the settings configurable by the user.
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct UserConfig {
     /// The coalition(s) the user wants to interact with.
+    #[serde(rename = "coalitions")]
     pub coalition_flag: CoalitionFlag,
 
     /// The unit type(s) the user wants to interact with.
+    #[serde(rename = "unit_types")]
     pub unit_type_flag: UnitTypeFlag,
 
-    /// The frequency at which unit data should be exported from DCS.
+    /// The frequency at which unit data should be exported from DCS, pushed to the export script
+    /// along with the coalitions and unit types.
     pub export_frequency_frames: i32,
+
+    /// The number of export cycles a unit may miss before it is reported as stale.
+    #[serde(default = "default_stale_after_cycles")]
+    pub stale_after_cycles: u32,
+
+    /// The number of export cycles a unit may miss before it is considered destroyed or despawned.
+    #[serde(default = "default_remove_after_cycles")]
+    pub remove_after_cycles: u32,
+
+    /// Path to a table mapping DCS unit types to CoT types, extending the built-in table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cot_type_map_path: Option<String>,
+
+    /// Path to a geoid grid in the NGA `.GRD` format (e.g. EGM96 `WW15MGH.GRD`), used to convert
+    /// DCS altitudes from MSL to height above the ellipsoid. A coarse EGM96 grid is used by
+    /// default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geoid_model_path: Option<String>,
+
+    /// The address the DCS export is received on, e.g. `0.0.0.0:34254` when DCS runs on another
+    /// machine. The export script sends to `127.0.0.1:34254` unless the `DCS_JTAC_TOOLS_ADDRESS`
+    /// and `DCS_JTAC_TOOLS_PORT` environment variables of DCS say otherwise.
+    #[serde(default = "default_listener_address")]
+    pub listener_address: SocketAddr,
+
+    /// The outputs the units are published to, each with its own filter and format.
+    #[serde(default = "default_sinks")]
+    pub sinks: Vec<SinkConfig>,
+
+    /// Recording of the units received from DCS, on by default.
+    #[serde(default)]
+    pub recording: RecordingConfig,
 }
 
-impl UserConfig {
-    /// Loads the user configuration from the file system.
-    pub fn from_file(file_path: &str) -> io::Result<UserConfig> {
-        let mut string_contents = String::new();
-        let file = File::open(file_path)?;
-        let buffer = BufReader::new(file);
+/// The settings as written to the configuration file, preceded by their version
+#[derive(Serialize)]
+struct VersionedConfig<'a> {
+    version: u64,
+
+    #[serde(flatten)]
+    settings: &'a UserConfig,
+}
+
+fn default_stale_after_cycles() -> u32 {
+    3
+}
 
-        for line in buffer.lines() {
-            string_contents.push_str(line?.as_str());
+fn default_remove_after_cycles() -> u32 {
+    10
+}
+
+/// Only reachable from DCS running on the same machine
+pub fn default_listener_address() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, DCS_LISTENER_PORT))
+}
+
+/// WebSocket clients only, as before outputs were configurable
+pub fn default_sinks() -> Vec<SinkConfig> {
+    vec![SinkConfig::new(OutputConfig::WebSocket(
+        WebSocketSinkConfig::default(),
+    ))]
+}
+
+impl Default for UserConfig {
+    /// BLUFOR units of every type, exported every 100 frames to the WebSocket hub
+    fn default() -> Self {
+        UserConfig {
+            coalition_flag: CoalitionFlag::BLUFOR,
+            unit_type_flag: UnitTypeFlag::GROUND | UnitTypeFlag::AIR | UnitTypeFlag::SEA,
+            export_frequency_frames: 100,
+            stale_after_cycles: default_stale_after_cycles(),
+            remove_after_cycles: default_remove_after_cycles(),
+            cot_type_map_path: None,
+            geoid_model_path: None,
+            listener_address: default_listener_address(),
+            sinks: default_sinks(),
+            recording: RecordingConfig::default(),
         }
+    }
+}
 
-        Ok(serde_json::from_str(&string_contents)?)
+impl UserConfig {
+    /// Loads the user configuration from the file system. Files written by an earlier version,
+    /// including the JSON files written before the configuration was versioned, are migrated.
+    pub fn from_file(file_path: &str) -> io::Result<UserConfig> {
+        let (user_config, _) = UserConfig::parse(&fs::read_to_string(file_path)?)?;
+
+        Ok(user_config)
     }
 
     /// Writes the user configuration to the file system.
     pub fn to_file(&self, file_path: &str) -> io::Result<()> {
-        let json = serde_json::to_string(self)?;
-        let file = File::create(file_path)?;
-        let mut buffer = BufWriter::new(file);
+        fs::write(file_path, self.to_toml()?)
+    }
 
-        buffer.write_all(json.as_bytes())?;
+    /// Rewrites a configuration file written by an earlier version in the current format, keeping
+    /// the original file next to it with a `.bak` extension. Returns whether the file was
+    /// migrated.
+    pub fn migrate_file(file_path: &str) -> io::Result<bool> {
+        let contents = fs::read_to_string(file_path)?;
+        let (user_config, version) = UserConfig::parse(&contents)?;
+        if version == CONFIG_VERSION && !is_json(&contents) {
+            return Ok(false);
+        }
+
+        fs::write(format!("{}.bak", file_path), contents)?;
+        user_config.to_file(file_path)?;
+
+        Ok(true)
+    }
+
+    /// The settings in the format of the configuration file.
+    pub fn to_toml(&self) -> io::Result<String> {
+        let versioned = VersionedConfig {
+            version: CONFIG_VERSION,
+            settings: self,
+        };
+
+        toml::to_string(&versioned).map_err(invalid_data)
+    }
+
+    /// Reads the settings in either format, along with the version they were written in.
+    fn parse(contents: &str) -> io::Result<(UserConfig, u64)> {
+        let mut settings: Value = match is_json(contents) {
+            true => serde_json::from_str(contents)?,
+            false => toml::from_str(contents).map_err(invalid_data)?,
+        };
+        let version = migrate(&mut settings).map_err(invalid_data)?;
+
+        Ok((serde_json::from_value(settings)?, version))
+    }
+
+    /// Checks the settings that cannot be enforced by their types.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.listener_address.port() == 0 {
+            return Err(ConfigError::MissingPort("listener_address"));
+        }
+        validate_lifecycle(self.stale_after_cycles, self.remove_after_cycles)?;
+
+        let mut listening_addresses: Vec<SocketAddr> = Vec::new();
+        for sink in &self.sinks {
+            let address = match &sink.output {
+                OutputConfig::WebSocket(web_socket) => web_socket.address,
+                OutputConfig::TacviewServer(tacview_server) => tacview_server.address,
+                _ => continue,
+            };
+            if address.port() == 0 {
+                return Err(ConfigError::MissingPort("sinks.address"));
+            }
+            if listening_addresses
+                .iter()
+                .any(|listening| overlaps(*listening, address))
+            {
+                return Err(ConfigError::DuplicateAddress(address));
+            }
+            listening_addresses.push(address);
+        }
 
         Ok(())
     }
@@ -55,34 +202,30 @@ impl UserConfig {
     }
 
     fn is_unit_type_configured(&self, unit: &DcsUnit) -> bool {
-        match unit.unit_type.level_1 {
-            Level1UnitType::AIR => {
-                (self.unit_type_flag & UnitTypeFlag::AIR) != UnitTypeFlag::empty()
-            }
-            Level1UnitType::GROUND => {
-                (self.unit_type_flag & UnitTypeFlag::GROUND) != UnitTypeFlag::empty()
-            }
-            Level1UnitType::SEA => {
-                (self.unit_type_flag & UnitTypeFlag::SEA) != UnitTypeFlag::empty()
-            }
-        }
+        self.unit_type_flag.includes(unit.unit_type.level_1)
     }
 
     fn is_coalition_configured(&self, unit: &DcsUnit) -> bool {
-        match unit.coalition {
-            Coalition::NEUTRAL => {
-                (self.coalition_flag & CoalitionFlag::NEUTRAL) != CoalitionFlag::empty()
-            }
-            Coalition::REDFOR => {
-                (self.coalition_flag & CoalitionFlag::REDFOR) != CoalitionFlag::empty()
-            }
-            Coalition::BLUFOR => {
-                (self.coalition_flag & CoalitionFlag::BLUFOR) != CoalitionFlag::empty()
-            }
-        }
+        self.coalition_flag.includes(unit.coalition)
     }
 }
 
+/// Whether listeners bound to `a` and `b` would compete for the same connections. Unspecified
+/// addresses listen on every interface, of both IP versions for `[::]`.
+fn overlaps(a: SocketAddr, b: SocketAddr) -> bool {
+    a.port() == b.port() && (a.ip() == b.ip() || a.ip().is_unspecified() || b.ip().is_unspecified())
+}
+
+/// Configuration files were JSON objects before they were versioned, TOML files cannot start with
+/// a brace
+fn is_json(contents: &str) -> bool {
+    contents.trim_start().starts_with('{')
+}
+
+fn invalid_data<E: ToString>(err: E) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, err.to_string())
+}
+
 #[cfg(test)]
 mod unit_tests {
     use crate::{
@@ -90,10 +233,13 @@ mod unit_tests {
             dcs_unit::{Coalition, DcsUnit, Position3D, UnitType},
             unit_type::Level1UnitType,
         },
+        sink::sink_config::{OutputConfig, RecordingConfig, SinkConfig, WebSocketSinkConfig},
+        tacview::tacview_server_config::TacviewServerConfig,
         user_config::{
             coalition_flag::CoalitionFlag,
+            config_error::ConfigError,
             unit_type_flag::UnitTypeFlag,
-            user_config::UserConfig,
+            user_config::{default_listener_address, UserConfig},
         },
     };
 
@@ -182,6 +328,78 @@ mod unit_tests {
         assert!(!config.is_unit_configured(&unit));
     }
 
+    #[test]
+    fn given_listener_address_without_port_when_validated_then_error_is_returned() {
+        let mut config = build_user_config(None, None);
+        config.listener_address = "0.0.0.0:0".parse().unwrap();
+
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::MissingPort("listener_address"))
+        );
+    }
+
+    #[test]
+    fn given_removal_before_stale_when_validated_then_error_is_returned() {
+        let mut config = build_user_config(None, None);
+        config.stale_after_cycles = 5;
+        config.remove_after_cycles = 4;
+
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidSetting { key, .. }) if key == "remove_after_cycles"
+        ));
+    }
+
+    #[test]
+    fn given_web_socket_sinks_on_same_address_when_validated_then_error_is_returned() {
+        let mut config = build_user_config(None, None);
+        let sink = SinkConfig::new(OutputConfig::WebSocket(WebSocketSinkConfig {
+            address: "[::]:9345".parse().unwrap(),
+            ..Default::default()
+        }));
+        config.sinks = vec![sink.clone()];
+        assert_eq!(config.validate(), Ok(()));
+
+        config.sinks.push(sink);
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::DuplicateAddress("[::]:9345".parse().unwrap()))
+        );
+    }
+
+    #[test]
+    fn given_sinks_on_overlapping_addresses_when_validated_then_error_is_returned() {
+        let web_socket = |address: &str| {
+            SinkConfig::new(OutputConfig::WebSocket(WebSocketSinkConfig {
+                address: address.parse().unwrap(),
+                ..Default::default()
+            }))
+        };
+        let tacview_server = |address: &str| {
+            SinkConfig::new(OutputConfig::TacviewServer(TacviewServerConfig {
+                address: address.parse().unwrap(),
+                ..Default::default()
+            }))
+        };
+        let mut config = build_user_config(None, None);
+
+        config.sinks = vec![web_socket("0.0.0.0:9345"), web_socket("127.0.0.1:9345")];
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::DuplicateAddress("127.0.0.1:9345".parse().unwrap()))
+        );
+
+        config.sinks = vec![web_socket("127.0.0.1:9345"), tacview_server("[::]:9345")];
+        assert_eq!(
+            config.validate(),
+            Err(ConfigError::DuplicateAddress("[::]:9345".parse().unwrap()))
+        );
+
+        config.sinks = vec![web_socket("127.0.0.1:9345"), web_socket("192.168.1.10:9345")];
+        assert_eq!(config.validate(), Ok(()));
+    }
+
     fn build_dcs_unit(coalition: Option<Coalition>, unit_type: Option<Level1UnitType>) -> DcsUnit {
         DcsUnit {
             coalition: match coalition {
@@ -202,6 +420,9 @@ mod unit_tests {
                     None => Level1UnitType::AIR,
                 },
                 level_2: 0,
+                level_3: 0,
+                level_4: 0,
+                type_name: String::new(),
             },
             mission_date: String::new(),
             mission_start_time: 0,
@@ -223,6 +444,13 @@ mod unit_tests {
                 None => UnitTypeFlag::empty(),
             } | UnitTypeFlag(8),
             export_frequency_frames: 0,
+            stale_after_cycles: 3,
+            remove_after_cycles: 10,
+            cot_type_map_path: None,
+            geoid_model_path: None,
+            listener_address: default_listener_address(),
+            sinks: vec![],
+            recording: RecordingConfig::default(),
         }
     }
 }
@@ -231,8 +459,16 @@ mod unit_tests {
 mod integration_tests {
     use std::fs;
 
-    use super::UserConfig;
-    use crate::user_config::{coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag};
+    use super::{default_sinks, UserConfig};
+    use crate::{
+        hub::subscription_filter::SubscriptionFilter,
+        sink::sink_config::{OutputConfig, RecordingConfig, SinkConfig},
+        tak::{
+            tak_mesh_config::TakMeshConfig, tak_protocol::TakProtocol,
+            tak_server_config::TakServerConfig,
+        },
+        user_config::{coalition_flag::CoalitionFlag, unit_type_flag::UnitTypeFlag},
+    };
 
     #[test]
     fn test_write_and_read() {
@@ -242,6 +478,34 @@ mod integration_tests {
             coalition_flag: CoalitionFlag::BLUFOR | CoalitionFlag::NEUTRAL,
             unit_type_flag: UnitTypeFlag::GROUND,
             export_frequency_frames: 10,
+            stale_after_cycles: 2,
+            remove_after_cycles: 5,
+            cot_type_map_path: Some("cot_types.json".to_string()),
+            geoid_model_path: Some("WW15MGH.GRD".to_string()),
+            listener_address: "[::]:34254".parse().unwrap(),
+            sinks: vec![
+                SinkConfig::new(OutputConfig::TakServer(TakServerConfig {
+                    host: "tak.example.com".to_string(),
+                    port: None,
+                    tls: None,
+                    protocol: TakProtocol::Protobuf,
+                })),
+                SinkConfig {
+                    output: OutputConfig::TakMesh(TakMeshConfig {
+                        interface: Some("192.168.1.10".parse().unwrap()),
+                        protocol: TakProtocol::Protobuf,
+                        ..Default::default()
+                    }),
+                    filter: SubscriptionFilter {
+                        coalition_flag: Some(CoalitionFlag::BLUFOR),
+                        ..Default::default()
+                    },
+                },
+            ],
+            recording: RecordingConfig {
+                enabled: false,
+                directory: "sessions".to_string(),
+            },
         };
 
         config
@@ -257,4 +521,59 @@ mod integration_tests {
         // Cleanup
         fs::remove_file(file_path).unwrap();
     }
+
+    #[test]
+    fn test_read_config_without_lifecycle_settings() {
+        let file_path = "test_lifecycle_defaults.config";
+        fs::write(
+            file_path,
+            r#"{"coalition_flag":4,"unit_type_flag":7,"export_frequency_frames":100}"#,
+        )
+        .expect("Failed to write config file.");
+
+        let config_from_file =
+            UserConfig::from_file(file_path).expect("Failed to read UserConfig from file.");
+
+        assert_eq!(config_from_file.stale_after_cycles, 3);
+        assert_eq!(config_from_file.remove_after_cycles, 10);
+        assert_eq!(
+            config_from_file.listener_address,
+            "127.0.0.1:34254".parse().unwrap()
+        );
+        assert_eq!(config_from_file.sinks, default_sinks());
+
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let file_path = "test_legacy.config";
+        let legacy = r#"{"coalition_flag":5,"unit_type_flag":2,"export_frequency_frames":100}"#;
+        fs::write(file_path, legacy).expect("Failed to write config file.");
+
+        let migrated = UserConfig::migrate_file(file_path).expect("Failed to migrate config.");
+        let migrated_again = UserConfig::migrate_file(file_path).expect("Failed to read config.");
+
+        assert!(migrated);
+        assert!(!migrated_again);
+        let contents = fs::read_to_string(file_path).unwrap();
+        assert!(contents.starts_with("version = 1\n"));
+        assert!(contents.contains(r#"coalitions = ["neutral", "blufor"]"#));
+        assert!(contents.contains(r#"unit_types = ["air"]"#));
+        assert_eq!(
+            fs::read_to_string(format!("{}.bak", file_path)).unwrap(),
+            legacy
+        );
+
+        let config_from_file =
+            UserConfig::from_file(file_path).expect("Failed to read UserConfig from file.");
+        assert_eq!(
+            config_from_file.coalition_flag,
+            CoalitionFlag::NEUTRAL | CoalitionFlag::BLUFOR
+        );
+        assert_eq!(config_from_file.unit_type_flag, UnitTypeFlag::AIR);
+
+        fs::remove_file(file_path).unwrap();
+        fs::remove_file(format!("{}.bak", file_path)).unwrap();
+    }
 }
@@ -12,8 +12,15 @@ local DcsJtacTools = {}
 
 function DcsJtacTools:Initialize()
     self.frameFrequency = 100
-    self.address = "127.0.0.1"
-    self.port = "34254"
+    -- The hub's listener_address, when it runs on another machine or port
+    self.address = os.getenv("DCS_JTAC_TOOLS_ADDRESS") or "127.0.0.1"
+    self.port = os.getenv("DCS_JTAC_TOOLS_PORT") or "34254"
+    self.frameVersion = 1
+    self.controlVersion = 1
+    self.maxDatagramSize = 8192
+    -- Set by the hub through the control channel, every unit is exported until then
+    self.coalitionIds = nil
+    self.unitTypeIds = nil
 
     local userProfile = os.getenv("userprofile"):gsub("\\","/")
     self.log_file = io.open(userProfile .. "/Saved Games/DCS.openbeta/Logs/DcsJtacTools.log", 'w')
@@ -22,7 +29,9 @@ function DcsJtacTools:Initialize()
     
     self.socket = require("socket")
     self.udp = self.socket.try(self.socket.udp())
+    self.udp:settimeout(0)
     self.currentFrame = 0
+    self.sequence = 0
     self.missionStartTime = LoGetMissionStartTime()
 end
 
@@ -92,6 +101,7 @@ end
 The goal is to quickly extract all units to the DCS JTAC Hub for processing
 ]]
 function DcsJtacTools:ExportUnits()
+    self:receiveControl()
     self.currentFrame = self.currentFrame + 1
 
     if self.currentFrame % self.frameFrequency ~= 0 then
@@ -100,12 +110,13 @@ function DcsJtacTools:ExportUnits()
 
     local worldObjects = LoGetWorldObjects()
     local currentTime = LoGetModelTime()
+    local units = {}
 
     for _, obj in pairs(worldObjects) do
-        if obj.UnitName and obj.Flags.Born and not obj.Flags.Static then
-            local jsonData = string.format([[{"unit_name":"%s","group_name":"%s","coalition":%s,"position":{"latitude":%.5f,"longitude":%.5f,"altitude":%s,"heading":%.5f},"unit_type":{"level_1":%d,"level_2":%d},"mission_date":"%s","mission_start_time":%d,"mission_time_elapsed":%d}]] .. "\n",
-            obj.UnitName,
-            obj.GroupName,
+        if obj.UnitName and obj.Flags.Born and not obj.Flags.Static and self:isExported(obj) then
+            units[#units + 1] = string.format([[{"unit_name":"%s","group_name":"%s","coalition":%s,"position":{"latitude":%.5f,"longitude":%.5f,"altitude":%s,"heading":%.5f},"unit_type":{"level_1":%d,"level_2":%d,"level_3":%d,"level_4":%d,"type_name":"%s"}}]],
+            self:escape(obj.UnitName),
+            self:escape(obj.GroupName),
             obj.CoalitionID,
             obj.LatLongAlt.Lat,
             obj.LatLongAlt.Long,
@@ -113,13 +124,79 @@ function DcsJtacTools:ExportUnits()
             obj.Heading,
             obj.Type.level1,
             obj.Type.level2,
-            string.format("%04d-%02d-%02d", MissionDate.Year, MissionDate.Month, MissionDate.Day),
-            self.missionStartTime,
-            currentTime)
+            obj.Type.level3,
+            obj.Type.level4,
+            self:escape(obj.Name))
+        end
+    end
+
+    local frame = string.format([[{"version":%d,"header":{"sequence":%d,"mission_date":"%s","mission_start_time":%d,"mission_time_elapsed":%d},"units":[%s]}]] .. "\n",
+        self.frameVersion,
+        self.sequence,
+        string.format("%04d-%02d-%02d", MissionDate.Year, MissionDate.Month, MissionDate.Day),
+        self.missionStartTime,
+        currentTime,
+        table.concat(units, ","))
+
+    self.sequence = self.sequence + 1
+    self:send(frame)
+end
+
+-- Sends a message in datagram-sized chunks; the hub reassembles them up to the trailing newline
+function DcsJtacTools:send(message)
+    for i = 1, #message, self.maxDatagramSize do
+        self.socket.try(self.udp:sendto(message:sub(i, i + self.maxDatagramSize - 1), self.address, self.port))
+    end
+end
+
+function DcsJtacTools:isExported(obj)
+    return (not self.coalitionIds or self.coalitionIds[obj.CoalitionID])
+        and (not self.unitTypeIds or self.unitTypeIds[obj.Type.level1])
+end
 
-        self.socket.try(self.udp:sendto(jsonData, self.address, self.port))
+-- Applies the settings the hub sends back to the socket the export is sent from, e.g.
+-- {"version":1,"export_frequency_frames":100,"coalition_ids":[2],"unit_type_ids":[1,2,3]}
+function DcsJtacTools:receiveControl()
+    while true do
+        local message = self.udp:receive()
+        if not message then
+            return
         end
+        self:applyControl(message)
+    end
+end
+
+function DcsJtacTools:applyControl(message)
+    if tonumber(message:match('"version":(%d+)')) ~= self.controlVersion then
+        return
+    end
+
+    local frameFrequency = tonumber(message:match('"export_frequency_frames":(%d+)'))
+    if frameFrequency and frameFrequency > 0 and frameFrequency ~= self.frameFrequency then
+        self.frameFrequency = frameFrequency
+        self:log(string.format("emitting every %d frames", self.frameFrequency))
+    end
+
+    self.coalitionIds = self:parseIds(message:match('"coalition_ids":%[([%d,]*)%]'))
+    self.unitTypeIds = self:parseIds(message:match('"unit_type_ids":%[([%d,]*)%]'))
+end
+
+function DcsJtacTools:parseIds(list)
+    if not list then
+        return nil
+    end
+
+    local ids = {}
+    for id in list:gmatch("%d+") do
+        ids[tonumber(id)] = true
     end
+    return ids
+end
+
+function DcsJtacTools:escape(value)
+    return (tostring(value):gsub('[%c"\\]', function(c)
+        return string.format("\\u%04x", c:byte())
+    end))
 end
 
 function DcsJtacTools:Dispose()